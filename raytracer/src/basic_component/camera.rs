@@ -1,6 +1,6 @@
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
-    utility::{self, random_double},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
+    utility,
 };
 
 #[derive(Copy, Clone)]
@@ -63,15 +63,97 @@ impl Camera {
         }
     }
 
-    pub fn get_ray(&self, u: f64, v: f64) -> Ray {
-        let rd = Vec3::random_in_unit_disk() * self.lens_radius;
+    pub fn get_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Ray {
+        let rd = Vec3::random_in_unit_disk(sampler) * self.lens_radius;
         let offset = self.u * rd.x + self.v * rd.y;
 
         Ray::new(
             self.origin + offset,
             self.lower_left_corner + self.horizontal * u + self.vertical * v - self.origin - offset,
-            random_double(self.time0, self.time1),
+            sampler.random_double(self.time0, self.time1),
         )
         // 光线在拍摄时间内随机生成
     }
+
+    // 焦平面（胶片所在的平面）到原点的距离
+    pub fn focus_dist(&self) -> f64 {
+        Vec3::dot(
+            self.origin - self.lower_left_corner - self.horizontal / 2. - self.vertical / 2.,
+            self.w,
+        )
+    }
+
+    // 胶片上 u, v ∈ [0, 1] 的部分在焦平面上的面积
+    pub fn film_area(&self) -> f64 {
+        self.horizontal.length() * self.vertical.length()
+    }
+
+    // 在镜头上均匀采样一点，针孔相机总是原点
+    pub fn sample_lens(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        if self.lens_radius <= 0. {
+            return self.origin;
+        }
+        let rd = Vec3::random_in_unit_disk(sampler) * self.lens_radius;
+        self.origin + self.u * rd.x + self.v * rd.y
+    }
+
+    // 从镜头上的 lens_point 出发、方向为 dir 的光线对应胶片上的 (u, v)，与 get_ray 的参数相同
+    // 光线朝向相机背后时返回 None
+    pub fn film_position(&self, lens_point: Vec3, dir: Vec3) -> Option<(f64, f64)> {
+        let forward = -Vec3::dot(dir, self.w);
+        if forward <= 0. {
+            return None;
+        }
+        // 与焦平面的交点
+        let q = lens_point + dir * (self.focus_dist() / forward) - self.lower_left_corner;
+        Some((
+            Vec3::dot(q, self.horizontal) / self.horizontal.length_squared(),
+            Vec3::dot(q, self.vertical) / self.vertical.length_squared(),
+        ))
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct CameraConfig {
+    // 构造相机所需的参数，长宽比由输出图像决定
+    pub lookfrom: Vec3,
+    pub lookat: Vec3,
+    pub vup: Vec3,
+    pub vfov: f64,
+    pub aperture: f64,
+    pub focus_dist: f64,
+    pub time0: f64,
+    pub time1: f64,
+}
+
+impl Default for CameraConfig {
+    // 默认为 cornell box 的视角
+    fn default() -> Self {
+        Self {
+            lookfrom: Vec3::new(278., 278., -800.),
+            lookat: Vec3::new(278., 278., 0.),
+            vup: Vec3::new(0., 1., 0.),
+            vfov: 40.,
+            aperture: 0.,
+            focus_dist: 10.,
+            time0: 0.,
+            time1: 1.,
+        }
+    }
+}
+
+impl CameraConfig {
+    pub fn build(&self, aspect_ratio: f64) -> Camera {
+        Camera::new(
+            self.lookfrom,
+            self.lookat,
+            self.vup,
+            self.vfov,
+            aspect_ratio,
+            self.aperture,
+            self.focus_dist,
+            self.time0,
+            self.time1,
+        )
+    }
 }
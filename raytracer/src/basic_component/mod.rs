@@ -1,4 +1,6 @@
 pub mod camera;
 pub mod onb;
 pub mod ray;
+pub mod sampler;
+pub mod spectrum;
 pub mod vec3;
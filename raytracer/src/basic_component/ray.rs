@@ -4,7 +4,8 @@ use crate::basic_component::vec3::Vec3;
 pub struct Ray {
     pub dir: Vec3,
     pub orig: Vec3,
-    pub tm: f64, //光线的出现时间
+    pub tm: f64,         //光线的出现时间
+    pub wavelength: f64, // 光谱模式下的主波长（nm），0 表示按 RGB 计算
 }
 
 impl Ray {
@@ -19,6 +20,7 @@ impl Ray {
             dir: _d,
             orig: _ori,
             tm: t,
+            wavelength: 0.,
         }
     }
 }
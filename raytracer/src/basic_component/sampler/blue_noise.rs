@@ -0,0 +1,193 @@
+use std::sync::Arc;
+
+use super::{hash, sobol::shuffled_sobol_2d, to_unit, Pcg32, Sampler};
+
+// 蓝噪声采样（Georgiev & Fajardo 2016）：所有像素使用同一个 Owen 扰乱的 Sobol 序列，
+// 每个像素再按蓝噪声的值平移（Cranley-Patterson 旋转）
+// 相邻像素的偏移差别很大，误差集中在高频上，采样次数少时看起来比白噪声平滑得多
+pub struct BlueNoiseSampler {
+    mask: Arc<BlueNoiseMask>,
+    seed: u64,
+    x: u32,
+    y: u32,
+    index: u32,
+    dimension: u64,
+}
+
+impl BlueNoiseSampler {
+    pub fn new(mask: Arc<BlueNoiseMask>, seed: u64, x: u32, y: u32, index: u32) -> Self {
+        Self {
+            mask,
+            seed,
+            x,
+            y,
+            index,
+            dimension: 0,
+        }
+    }
+
+    // 每个分量在蓝噪声上取不同的起点，各个维度的偏移互不相关
+    fn shift(&self, value: u32, offset: u64) -> f64 {
+        let size = self.mask.size as u64;
+        let x = (self.x as u64 + (offset & 0xffff)) % size;
+        let y = (self.y as u64 + ((offset >> 16) & 0xffff)) % size;
+        let t = to_unit(value) + self.mask.values[(y * size + x) as usize];
+        if t >= 1. {
+            t - 1.
+        } else {
+            t
+        }
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn get_1d(&mut self) -> f64 {
+        self.get_2d().0
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let seed = hash(self.seed, self.dimension);
+        self.dimension += 1;
+        let (x, y) = shuffled_sobol_2d(self.index, seed);
+        (self.shift(x, hash(seed, 0)), self.shift(y, hash(seed, 1)))
+    }
+}
+
+// 边长为 size 的蓝噪声（可平铺），每个位置是 [0, 1) 中的一个值，所有值均匀分布
+// 用 void-and-cluster 算法生成（Ulichney 1993）：
+// 依次在点集中“最空”的位置加点，加入的先后顺序就是这个位置的值
+pub struct BlueNoiseMask {
+    pub size: usize,
+    pub values: Vec<f64>,
+}
+
+const SIGMA: f64 = 1.5;
+
+impl BlueNoiseMask {
+    pub fn generate(size: usize, seed: u64) -> Self {
+        let n = size * size;
+        // 环面上的高斯核，kernel[dy * size + dx] 为偏移 (dx, dy) 处的能量
+        let mut kernel = vec![0.; n];
+        for dy in 0..size {
+            for dx in 0..size {
+                let fx = dx.min(size - dx) as f64;
+                let fy = dy.min(size - dy) as f64;
+                kernel[dy * size + dx] = (-(fx * fx + fy * fy) / (2. * SIGMA * SIGMA)).exp();
+            }
+        }
+        let kernel = Arc::new(kernel);
+
+        // 随机选取约 1/10 的位置作为初始点集
+        let mut pattern = Pattern::new(size, kernel.clone());
+        let mut rng = Pcg32::new(seed);
+        let initial = (n / 10).max(1);
+        let mut count = 0;
+        while count < initial {
+            let p = rng.random_int(0, n as i32 - 1) as usize;
+            if !pattern.ones[p] {
+                pattern.set(p, true);
+                count += 1;
+            }
+        }
+        // 反复把最密集处的点移到最空的位置，直到移动的就是同一个点
+        for _ in 0..n {
+            let cluster = pattern.tightest_cluster();
+            pattern.set(cluster, false);
+            let void = pattern.largest_void();
+            pattern.set(void, true);
+            if void == cluster {
+                break;
+            }
+        }
+
+        let mut rank = vec![0; n];
+        // 初始的点按从密到疏的顺序依次去掉，排在最前面
+        let mut removing = pattern.clone();
+        for r in (0..initial).rev() {
+            let cluster = removing.tightest_cluster();
+            removing.set(cluster, false);
+            rank[cluster] = r;
+        }
+        // 之后在最空的位置加点，直到填满一半
+        for r in initial..n / 2 {
+            let void = pattern.largest_void();
+            pattern.set(void, true);
+            rank[void] = r;
+        }
+        // 剩下的空位成了少数，把空位看作点集，依次填上最密集处的空位
+        let mut inverse = Pattern::new(size, kernel);
+        for (p, &one) in pattern.ones.iter().enumerate() {
+            if !one {
+                inverse.set(p, true);
+            }
+        }
+        for r in n / 2..n {
+            let cluster = inverse.tightest_cluster();
+            inverse.set(cluster, false);
+            rank[cluster] = r;
+        }
+
+        Self {
+            size,
+            values: rank.iter().map(|&r| (r as f64 + 0.5) / n as f64).collect(),
+        }
+    }
+}
+
+// 二值的点集，以及每个位置受所有点影响的能量之和
+#[derive(Clone)]
+struct Pattern {
+    size: usize,
+    kernel: Arc<Vec<f64>>,
+    ones: Vec<bool>,
+    energy: Vec<f64>,
+}
+
+impl Pattern {
+    fn new(size: usize, kernel: Arc<Vec<f64>>) -> Self {
+        Self {
+            size,
+            kernel,
+            ones: vec![false; size * size],
+            energy: vec![0.; size * size],
+        }
+    }
+
+    fn set(&mut self, p: usize, one: bool) {
+        let sign = if one { 1. } else { -1. };
+        self.ones[p] = one;
+        let (px, py) = (p % self.size, p / self.size);
+        for dy in 0..self.size {
+            let row = (py + dy) % self.size * self.size;
+            for dx in 0..self.size {
+                self.energy[row + (px + dx) % self.size] += sign * self.kernel[dy * self.size + dx];
+            }
+        }
+    }
+
+    // 能量最高的点
+    fn tightest_cluster(&self) -> usize {
+        self.find(true, |e, best| e > best)
+    }
+
+    // 能量最低的空位
+    fn largest_void(&self) -> usize {
+        self.find(false, |e, best| e < best)
+    }
+
+    fn find(&self, one: bool, better: impl Fn(f64, f64) -> bool) -> usize {
+        let mut best: Option<(usize, f64)> = None;
+        for (p, &e) in self.energy.iter().enumerate() {
+            if self.ones[p] != one {
+                continue;
+            }
+            if let Some((_, b)) = best {
+                if !better(e, b) {
+                    continue;
+                }
+            }
+            best = Some((p, e));
+        }
+        best.unwrap().0
+    }
+}
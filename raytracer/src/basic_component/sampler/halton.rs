@@ -0,0 +1,118 @@
+use super::{hash, permutation_element, Pcg32, Sampler};
+
+// Halton 序列：第 d 个维度是采样序号在第 d 个素数进制下的各位数字倒序排列
+// 每个像素用不同的 Owen 扰乱（按数位的前缀对每一位做随机排列），像素之间互不相关
+pub struct HaltonSampler {
+    pixel: u64,
+    index: u32,
+    dimension: usize,
+    rng: Pcg32, // 超出素数表的维度
+}
+
+// 前 128 个素数，越大的进制在少量采样时分布得越差，再往后的维度使用独立的随机数
+const PRIMES: [u32; 128] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311, 313, 317, 331, 337, 347, 349, 353, 359, 367, 373, 379, 383, 389, 397, 401, 409, 419, 421,
+    431, 433, 439, 443, 449, 457, 461, 463, 467, 479, 487, 491, 499, 503, 509, 521, 523, 541, 547,
+    557, 563, 569, 571, 577, 587, 593, 599, 601, 607, 613, 617, 619, 631, 641, 643, 647, 653, 659,
+    661, 673, 677, 683, 691, 701, 709, 719,
+];
+
+impl HaltonSampler {
+    pub fn new(pixel: u64, index: u32, rng: Pcg32) -> Self {
+        Self {
+            pixel,
+            index,
+            dimension: 0,
+            rng,
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn get_1d(&mut self) -> f64 {
+        let d = self.dimension;
+        self.dimension += 1;
+        if d >= PRIMES.len() {
+            return self.rng.get_1d();
+        }
+        owen_scrambled_radical_inverse(PRIMES[d], self.index as u64, hash(self.pixel, d as u64))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let u = self.get_1d();
+        (u, self.get_1d())
+    }
+}
+
+const ONE_MINUS_EPSILON: f64 = 1. - f64::EPSILON / 2.;
+
+// 每一位数字按已经确定的更低位（倒序后的高位）选择一个排列，这样扰乱后仍然保持分层
+fn owen_scrambled_radical_inverse(base: u32, mut a: u64, seed: u64) -> f64 {
+    let inv_base = 1. / base as f64;
+    let mut inv_base_m = 1.;
+    let mut reversed = 0u64;
+    let mut digit_index = 0;
+    // 一直处理到数位小于 f64 的精度，序号更高的位都是 0，但 0 也要被扰乱
+    while 1. - (base - 1) as f64 * inv_base_m < 1. {
+        let next = a / base as u64;
+        let digit = (a - next * base as u64) as u32;
+        // 前缀由已经处理的位数和这些位的值共同决定，全为 0 的前缀长度不同时也要区分开
+        let digit_seed = hash(seed ^ reversed, digit_index) as u32;
+        let digit = permutation_element(digit, base, digit_seed);
+        reversed = reversed * base as u64 + digit as u64;
+        inv_base_m *= inv_base;
+        digit_index += 1;
+        a = next;
+    }
+    (reversed as f64 * inv_base_m).min(ONE_MINUS_EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 第 d 维的前 b^k 个点在 b^k 等分的每一段中各有一个
+    #[test]
+    fn each_dimension_is_stratified() {
+        for &(d, n) in [(0, 64), (1, 81), (2, 125), (5, 169)].iter() {
+            for pixel in 0..3 {
+                let mut counts = vec![0; n];
+                for i in 0..n as u32 {
+                    let mut s = HaltonSampler::new(pixel, i, Pcg32::new(0));
+                    for _ in 0..d {
+                        s.get_1d();
+                    }
+                    let u = s.get_1d();
+                    assert!((0. ..1.).contains(&u));
+                    counts[(u * n as f64) as usize] += 1;
+                }
+                assert!(counts.iter().all(|&c| c == 1), "dimension {}", d);
+            }
+        }
+    }
+
+    #[test]
+    fn first_two_dimensions_fill_a_grid() {
+        // 6 个点在 2 x 3 的格子中各有一个
+        let mut counts = [0; 6];
+        for i in 0..6 {
+            let (x, y) = HaltonSampler::new(7, i, Pcg32::new(0)).get_2d();
+            counts[(y * 3.) as usize * 2 + (x * 2.) as usize] += 1;
+        }
+        assert_eq!(counts, [1; 6]);
+    }
+
+    #[test]
+    fn pixels_are_scrambled_differently() {
+        let first = |pixel| {
+            HaltonSampler::new(pixel, 1, Pcg32::new(0))
+                .get_1d()
+                .to_bits()
+        };
+        assert_eq!(first(0), first(0));
+        assert_ne!(first(0), first(1));
+    }
+}
@@ -0,0 +1,232 @@
+pub mod blue_noise;
+pub mod halton;
+pub mod sobol;
+pub mod stratified;
+
+use std::sync::Arc;
+
+use self::{
+    blue_noise::{BlueNoiseMask, BlueNoiseSampler},
+    halton::HaltonSampler,
+    sobol::SobolSampler,
+    stratified::StratifiedSampler,
+};
+
+// 采样器：一条路径上的每个采样点（像素内的位置、镜头、时间、光源上的点、散射方向……）
+// 依次取出下一个维度的值，低差异序列在每个维度上都比独立的随机数分布得更均匀
+// 二维的采样点应当用 get_2d 一次取出，两个分量才能一起分层
+pub trait Sampler {
+    // 下一个维度，[0, 1) 中的值
+    fn get_1d(&mut self) -> f64;
+    fn get_2d(&mut self) -> (f64, f64);
+
+    // [min, max) 中的实数
+    fn random_double(&mut self, min: f64, max: f64) -> f64 {
+        min + self.get_1d() * (max - min)
+    }
+
+    // [min, max] 中的整数
+    fn random_int(&mut self, min: i32, max: i32) -> i32 {
+        let range = (max as i64 - min as i64 + 1) as f64;
+        ((min as i64 + (self.get_1d() * range) as i64).min(max as i64)) as i32
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum SamplerKind {
+    Independent, // 每个维度都是独立的随机数
+    Stratified,  // 每个维度分成 spp 层，层内抖动
+    Halton,      // Owen 扰乱的 Halton 序列
+    Sobol,       // Owen 扰乱的 Sobol 序列
+    BlueNoise,   // 各像素共用一个 Sobol 序列，按蓝噪声平移，误差呈蓝噪声分布
+}
+
+pub const SAMPLERS: [&str; 5] = ["independent", "stratified", "halton", "sobol", "blue-noise"];
+
+impl SamplerKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "independent" => Some(SamplerKind::Independent),
+            "stratified" => Some(SamplerKind::Stratified),
+            "halton" => Some(SamplerKind::Halton),
+            "sobol" => Some(SamplerKind::Sobol),
+            "blue-noise" => Some(SamplerKind::BlueNoise),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SamplerKind::Independent => "independent",
+            SamplerKind::Stratified => "stratified",
+            SamplerKind::Halton => "halton",
+            SamplerKind::Sobol => "sobol",
+            SamplerKind::BlueNoise => "blue-noise",
+        }
+    }
+}
+
+// 为每个像素的每次采样创建采样器，所有线程共享
+#[derive(Clone)]
+pub struct SamplerConfig {
+    pub kind: SamplerKind,
+    pub seed: u64,
+    pub samples_per_pixel: u32, // 分层采样的层数
+    blue_noise: Option<Arc<BlueNoiseMask>>,
+}
+
+// 蓝噪声的边长，图像上按这个大小平铺
+const BLUE_NOISE_SIZE: usize = 64;
+
+impl SamplerConfig {
+    pub fn new(kind: SamplerKind, seed: u64, samples_per_pixel: u32) -> Self {
+        // 蓝噪声与种子无关，只在需要时生成一次
+        let blue_noise = match kind {
+            SamplerKind::BlueNoise => Some(Arc::new(BlueNoiseMask::generate(BLUE_NOISE_SIZE, 0))),
+            _ => None,
+        };
+        Self {
+            kind,
+            seed,
+            samples_per_pixel: samples_per_pixel.max(1),
+            blue_noise,
+        }
+    }
+
+    // 像素 (x, y) 的第 sample 次采样，结果只由种子、像素位置和采样序号决定
+    pub fn for_pixel(&self, x: u32, y: u32, sample: u32) -> Box<dyn Sampler> {
+        let pixel = pixel_hash(self.seed, x, y);
+        // 超出低差异序列维度的部分使用独立的随机数
+        let rng = Pcg32::new_stream(pixel, sample as u64);
+        match self.kind {
+            SamplerKind::Independent => Box::new(rng),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(
+                pixel,
+                sample,
+                self.samples_per_pixel,
+                rng,
+            )),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(pixel, sample, rng)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(pixel, sample)),
+            SamplerKind::BlueNoise => Box::new(BlueNoiseSampler::new(
+                self.blue_noise.clone().unwrap(),
+                mix(self.seed),
+                x,
+                y,
+                sample,
+            )),
+        }
+    }
+}
+
+fn pixel_hash(seed: u64, x: u32, y: u32) -> u64 {
+    let pixel = ((y as u64) << 32) | x as u64;
+    mix(seed ^ mix(pixel))
+}
+
+// 可复现的随机数生成器（PCG32），也就是独立采样器
+// 每个像素的每次采样都有独立的随机数序列，渲染结果与线程数、调度顺序无关
+#[derive(Copy, Clone)]
+pub struct Pcg32 {
+    state: u64,
+    inc: u64, // 序列编号，必须是奇数
+}
+
+const MULTIPLIER: u64 = 6364136223846793005;
+
+impl Pcg32 {
+    pub fn new(seed: u64) -> Self {
+        Pcg32::new_stream(seed, 0)
+    }
+
+    // 同一个种子下，不同的 stream 给出互不相关的序列
+    pub fn new_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            inc: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(mix(seed));
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(self.inc);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+}
+
+impl Sampler for Pcg32 {
+    fn get_1d(&mut self) -> f64 {
+        // 32 位精度足够，且保证结果严格小于 1
+        to_unit(self.next_u32())
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let u = self.get_1d();
+        (u, self.get_1d())
+    }
+
+    fn random_int(&mut self, min: i32, max: i32) -> i32 {
+        let range = (max as i64 - min as i64 + 1) as u64;
+        (min as i64 + ((self.next_u32() as u64 * range) >> 32) as i64) as i32
+    }
+}
+
+// 32 位定点数转换为 [0, 1) 中的实数
+pub fn to_unit(x: u32) -> f64 {
+    x as f64 / 4294967296.
+}
+
+// SplitMix64 的混合函数，把相近的输入打散
+pub fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// 把种子和维度等编号组合成一个新的种子
+pub fn hash(seed: u64, x: u64) -> u64 {
+    mix(seed ^ mix(x))
+}
+
+// [0, n) 的一个随机排列中第 i 个元素，不需要把排列存下来（Kensler 2013）
+// 不同的 p 给出不同的排列
+pub fn permutation_element(mut i: u32, n: u32, p: u32) -> u32 {
+    let mut w = n - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        // 在 [0, w] 上是排列，超出 n 的部分继续置换直到落回 [0, n)
+        if i < n {
+            break;
+        }
+    }
+    (i.wrapping_add(p)) % n
+}
@@ -0,0 +1,122 @@
+use super::{hash, mix, to_unit, Sampler};
+
+// Owen 扰乱的 Sobol 序列（Burley 2020）
+// 每次取样只用 Sobol 序列的前两个维度，不同的维度对采样序号做不同的打乱，
+// 这样不需要高维的方向数表，每个二维的投影仍然是分层良好的 (0, 2) 序列
+pub struct SobolSampler {
+    seed: u64, // 每个像素不同，像素之间互不相关
+    index: u32,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64, index: u32) -> Self {
+        Self {
+            seed,
+            index,
+            dimension: 0,
+        }
+    }
+
+    fn next(&mut self) -> (u32, u32) {
+        let seed = hash(self.seed, self.dimension);
+        self.dimension += 1;
+        shuffled_sobol_2d(self.index, seed)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn get_1d(&mut self) -> f64 {
+        to_unit(self.next().0)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (x, y) = self.next();
+        (to_unit(x), to_unit(y))
+    }
+}
+
+// 打乱过序号、再做 Owen 扰乱的二维 Sobol 点，结果为 32 位定点数
+pub fn shuffled_sobol_2d(index: u32, seed: u64) -> (u32, u32) {
+    let index = nested_uniform_scramble(index, seed as u32);
+    let x = nested_uniform_scramble(index.reverse_bits(), (seed >> 32) as u32);
+    let y = nested_uniform_scramble(sobol_1(index), mix(seed) as u32);
+    (x, y)
+}
+
+// Sobol 序列的第二个维度，第一个维度就是序号的二进制位倒序
+fn sobol_1(mut index: u32) -> u32 {
+    let mut result = 0;
+    let mut v = 1u32 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    result
+}
+
+// Owen 扰乱：每一位按比它高的位翻转，等价于在二叉树的每个节点随机交换左右子树
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+// 只有低位影响高位的哈希，在位倒序之后就是 Owen 扰乱
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_dimension_matches_the_sobol_sequence() {
+        // 方向数为 0.1、0.11、0.101（二进制），按序号的各位直接异或，不用格雷码
+        let expected = [0., 0.5, 0.75, 0.25, 0.625, 0.125, 0.375, 0.875];
+        for (i, &e) in expected.iter().enumerate() {
+            assert!((to_unit(sobol_1(i as u32)) - e).abs() < 1e-9);
+        }
+    }
+
+    // 前 2^m 个点是 (0, m, 2) 网：面积为 2^-m 的每个基本区间中恰好有一个点
+    fn assert_net(points: &[(f64, f64)], m: u32) {
+        for a in 0..=m {
+            let (nx, ny) = (1usize << a, 1usize << (m - a));
+            let mut counts = vec![0; nx * ny];
+            for &(x, y) in points {
+                counts[(y * ny as f64) as usize * nx + (x * nx as f64) as usize] += 1;
+            }
+            assert!(counts.iter().all(|&c| c == 1), "{} x {} cells", nx, ny);
+        }
+    }
+
+    #[test]
+    fn every_dimension_pair_is_a_net() {
+        let m = 6;
+        for seed in 0..4 {
+            let mut samplers: Vec<SobolSampler> =
+                (0..1 << m).map(|i| SobolSampler::new(seed, i)).collect();
+            for _ in 0..4 {
+                let points: Vec<(f64, f64)> = samplers.iter_mut().map(|s| s.get_2d()).collect();
+                assert!(points
+                    .iter()
+                    .all(|&(x, y)| (0. ..1.).contains(&x) && (0. ..1.).contains(&y)));
+                assert_net(&points, m);
+            }
+        }
+    }
+
+    #[test]
+    fn seeds_give_different_points() {
+        assert_ne!(shuffled_sobol_2d(3, 1), shuffled_sobol_2d(3, 2));
+        assert_eq!(shuffled_sobol_2d(3, 1), shuffled_sobol_2d(3, 1));
+    }
+}
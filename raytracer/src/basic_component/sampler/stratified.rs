@@ -0,0 +1,51 @@
+use super::{hash, permutation_element, Pcg32, Sampler};
+
+// 分层（抖动）采样：每个维度把 [0, 1) 分成 spp 层，一个像素的 spp 次采样各落在不同的层里
+// 采样序号到层的对应是随机的排列，每个像素、每个维度都不同，所以维度之间互不相关
+// 二维时分成 nx * ny >= spp 个格子，每次采样占用其中一个
+pub struct StratifiedSampler {
+    pixel: u64,
+    index: u32,
+    samples_per_pixel: u32,
+    dimension: u64,
+    rng: Pcg32, // 层内的抖动
+}
+
+impl StratifiedSampler {
+    pub fn new(pixel: u64, index: u32, samples_per_pixel: u32, rng: Pcg32) -> Self {
+        Self {
+            pixel,
+            index,
+            samples_per_pixel,
+            dimension: 0,
+            rng,
+        }
+    }
+
+    // 本次采样在下一个维度上落在哪一层
+    fn stratum(&mut self, count: u32) -> u32 {
+        let seed = hash(self.pixel, self.dimension) as u32;
+        self.dimension += 1;
+        permutation_element(self.index % count, count, seed)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn get_1d(&mut self) -> f64 {
+        let n = self.samples_per_pixel;
+        let s = self.stratum(n);
+        (s as f64 + self.rng.get_1d()) / n as f64
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let n = self.samples_per_pixel as f64;
+        let nx = n.sqrt().ceil() as u32;
+        let ny = (n / nx as f64).ceil() as u32;
+        let s = self.stratum(nx * ny);
+        let (jx, jy) = self.rng.get_2d();
+        (
+            ((s % nx) as f64 + jx) / nx as f64,
+            ((s / nx) as f64 + jy) / ny as f64,
+        )
+    }
+}
@@ -0,0 +1,131 @@
+use crate::basic_component::vec3::Vec3;
+
+// 光谱模式采样的波长范围（nm），覆盖 CIE 颜色匹配函数的主要部分
+pub const LAMBDA_MIN: f64 = 360.;
+pub const LAMBDA_MAX: f64 = 830.;
+
+// 一次采样追踪的三个波长：随机的主波长（hero wavelength），另外两个在范围内等距轮转
+// 三个波长上的值正好放进一个 Vec3，路径追踪中的颜色运算都可以照常进行
+#[derive(Copy, Clone)]
+pub struct SampledWavelengths {
+    pub lambda: [f64; 3], // lambda[0] 为主波长
+}
+
+impl SampledWavelengths {
+    pub fn sample(u: f64) -> Self {
+        Self::from_hero(LAMBDA_MIN + u * (LAMBDA_MAX - LAMBDA_MIN))
+    }
+
+    pub fn from_hero(hero: f64) -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let mut lambda = [hero; 3];
+        for (i, l) in lambda.iter_mut().enumerate().skip(1) {
+            *l = LAMBDA_MIN + (hero - LAMBDA_MIN + range * i as f64 / 3.) % range;
+        }
+        Self { lambda }
+    }
+
+    // RGB 的反射率或发光转换为光谱，取三个波长上的值
+    pub fn upsample(&self, rgb: Vec3) -> Vec3 {
+        let s = |i: usize| rgb_to_spectrum(rgb, self.lambda[i]);
+        Vec3::new(s(0), s(1), s(2))
+    }
+
+    // 三个波长上的值对颜色匹配函数的蒙特卡洛估计，波长在范围内均匀分布
+    pub fn to_xyz(&self, values: Vec3) -> Vec3 {
+        let mut xyz = Vec3::new(0., 0., 0.);
+        for i in 0..3 {
+            xyz += cie_xyz(self.lambda[i]) * values[i];
+        }
+        xyz * ((LAMBDA_MAX - LAMBDA_MIN) / 3.)
+    }
+}
+
+// CIE 1931 颜色匹配函数的多瓣高斯拟合（Wyman, Sloan & Shirley 2013）
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+    let g = |mu: f64, s1: f64, s2: f64| {
+        let t = (lambda - mu) / if lambda < mu { s1 } else { s2 };
+        (-0.5 * t * t).exp()
+    };
+    Vec3::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+// XYZ 转换为线性的 sRGB（D65）
+pub fn xyz_to_linear_srgb(xyz: Vec3) -> Vec3 {
+    Vec3::new(
+        3.2404542 * xyz.x - 1.5371385 * xyz.y - 0.4985314 * xyz.z,
+        -0.9692660 * xyz.x + 1.8760108 * xyz.y + 0.0415560 * xyz.z,
+        0.0556434 * xyz.x - 0.2040259 * xyz.y + 1.0572252 * xyz.z,
+    )
+}
+
+// 常数为 1 的光谱对应的 sRGB，用来把等能白点映射回 (1, 1, 1)
+pub fn white_point() -> Vec3 {
+    let steps = 940;
+    let dl = (LAMBDA_MAX - LAMBDA_MIN) / steps as f64;
+    let mut xyz = Vec3::new(0., 0., 0.);
+    for i in 0..steps {
+        xyz += cie_xyz(LAMBDA_MIN + (i as f64 + 0.5) * dl) * dl;
+    }
+    xyz_to_linear_srgb(xyz)
+}
+
+// Smits (1999) 的 RGB 转光谱：白、三原色、三补色的光谱按 RGB 的大小关系叠加
+// 光谱在 380 ~ 720 nm 内分为 10 段，范围外取两端的值
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+pub fn rgb_to_spectrum(rgb: Vec3, lambda: f64) -> f64 {
+    // 各段中心之间线性插值
+    let x = ((lambda - 380.) / 34. - 0.5).max(0.).min(9.);
+    let i = (x as usize).min(8);
+    let f = x - i as f64;
+    let at = |s: &[f64; 10]| s[i] * (1. - f) + s[i + 1] * f;
+
+    let (r, g, b) = (rgb.x, rgb.y, rgb.z);
+    if r <= g && r <= b {
+        let base = r * at(&SMITS_WHITE);
+        if g <= b {
+            base + (g - r) * at(&SMITS_CYAN) + (b - g) * at(&SMITS_BLUE)
+        } else {
+            base + (b - r) * at(&SMITS_CYAN) + (g - b) * at(&SMITS_GREEN)
+        }
+    } else if g <= r && g <= b {
+        let base = g * at(&SMITS_WHITE);
+        if r <= b {
+            base + (r - g) * at(&SMITS_MAGENTA) + (b - r) * at(&SMITS_BLUE)
+        } else {
+            base + (b - g) * at(&SMITS_MAGENTA) + (r - b) * at(&SMITS_RED)
+        }
+    } else {
+        let base = b * at(&SMITS_WHITE);
+        if r <= g {
+            base + (r - b) * at(&SMITS_YELLOW) + (g - r) * at(&SMITS_GREEN)
+        } else {
+            base + (g - b) * at(&SMITS_YELLOW) + (r - g) * at(&SMITS_RED)
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::utility::{min_f64, random_double};
+use crate::{basic_component::sampler::Sampler, utility::min_f64};
 use std::{
     f64::consts::PI,
     ops::{
@@ -40,6 +40,11 @@ impl Vec3 {
         let s = 1e-8;
         (self.x).abs() < s && (self.y).abs() < s && (self.z).abs() < s
     }
+
+    // 把颜色看作线性的 RGB，按 Rec. 709 的权重求亮度
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
+    }
 }
 
 impl Vec3 {
@@ -71,33 +76,33 @@ impl Vec3 {
         }
     }
 
-    pub fn random(min: f64, max: f64) -> Vec3 {
+    pub fn random(min: f64, max: f64, sampler: &mut dyn Sampler) -> Vec3 {
         //随机生成向量
         Vec3 {
-            x: random_double(min, max),
-            y: random_double(min, max),
-            z: random_double(min, max),
+            x: sampler.random_double(min, max),
+            y: sampler.random_double(min, max),
+            z: sampler.random_double(min, max),
         }
     }
 
-    pub fn random_vec_in_unit_sphere() -> Vec3 {
-        //在单位球体内生成一个向量
-        loop {
-            let p = Vec3::random(-1., 1.);
-            if p.length_squared() < 1. {
-                return p;
-            }
-        }
+    pub fn random_vec_in_unit_sphere(sampler: &mut dyn Sampler) -> Vec3 {
+        //在单位球体内生成一个向量，半径按体积均匀分布
+        let dir = Vec3::random_unit_vector(sampler);
+        dir * sampler.get_1d().cbrt()
     }
 
-    pub fn random_unit_vector() -> Vec3 {
-        //随机单位向量
-        Vec3::unit_vector(Vec3::random_vec_in_unit_sphere())
+    pub fn random_unit_vector(sampler: &mut dyn Sampler) -> Vec3 {
+        //随机单位向量，z 和方位角都是均匀分布的
+        let (r1, r2) = sampler.get_2d();
+        let z = 1. - 2. * r2;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * PI * r1;
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
     }
 
-    pub fn random_in_hemisphere(normal: Vec3) -> Vec3 {
+    pub fn random_in_hemisphere(normal: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
         //半球中的随机向量
-        let in_unit_sphere = Vec3::random_vec_in_unit_sphere();
+        let in_unit_sphere = Vec3::random_vec_in_unit_sphere(sampler);
         if Vec3::dot(in_unit_sphere, normal) > 0. {
             //和法线在同一个半球
             in_unit_sphere
@@ -106,9 +111,8 @@ impl Vec3 {
         }
     }
 
-    pub fn random_cosine_direction() -> Vec3 {
-        let r1 = random_double(0., 1.);
-        let r2 = random_double(0., 1.);
+    pub fn random_cosine_direction(sampler: &mut dyn Sampler) -> Vec3 {
+        let (r1, r2) = sampler.get_2d();
         let z = (1. - r2).sqrt();
         let phi = 2. * PI * r1;
         let x = phi.cos() * r2.sqrt();
@@ -129,13 +133,19 @@ impl Vec3 {
         r_out_perp + r_out_parallel
     }
 
-    pub fn random_in_unit_disk() -> Vec3 {
-        loop {
-            let p = Vec3::new(random_double(-1., 1.), random_double(-1., 1.), 0.);
-            if p.length_squared() < 1. {
-                return p;
-            }
+    pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Vec3 {
+        // 同心圆映射：把正方形映射到圆盘上，相邻的采样点映射后仍然相邻
+        let (r1, r2) = sampler.get_2d();
+        let (a, b) = (2. * r1 - 1., 2. * r2 - 1.);
+        if a == 0. && b == 0. {
+            return Vec3::new(0., 0., 0.);
         }
+        let (r, theta) = if a.abs() > b.abs() {
+            (a, PI / 4. * (b / a))
+        } else {
+            (b, PI / 2. - PI / 4. * (a / b))
+        };
+        Vec3::new(r * theta.cos(), r * theta.sin(), 0.)
     }
 }
 
@@ -0,0 +1,649 @@
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use crate::{
+    basic_component::{
+        sampler::{self, SamplerKind},
+        vec3::Vec3,
+    },
+    film::{
+        exr::ExrCompression,
+        filter::{self, FilterKind},
+        output,
+        tonemap::{self, ToneMap, ToneMapOperator},
+    },
+    render::{
+        aov::{self, Aov},
+        debug::{self, DebugView},
+        mlt::MltSettings,
+        path::{self, MisHeuristic},
+        progressive::Adaptive,
+        IntegratorKind, INTEGRATORS,
+    },
+    scene::builtin,
+};
+
+pub enum Command {
+    Render(Box<RenderOptions>),
+    ListScenes,
+}
+
+pub struct RenderOptions {
+    pub scene: String,
+    pub scene_file: Option<String>,
+    // 以下参数为 None 时，使用场景推荐的设置
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub samples_per_pixel: Option<i32>,
+    pub max_depth: Option<i32>,
+    pub rr_depth: i32, // 从第几次碰撞开始俄罗斯轮盘赌
+    pub mis: MisHeuristic,
+    pub integrator: IntegratorKind,
+    pub spectral: bool,             // 按波长追踪，只支持路径追踪
+    pub photons: usize,             // 每张光子图发出的光子数
+    pub photon_radius: Option<f64>, // None 表示由光子的密度决定
+    pub mlt: MltSettings,
+    pub debug_view: DebugView,
+    pub ao_radius: Option<f64>, // None 表示由场景的大小决定
+    pub depth_max: Option<f64>, // 同上
+    pub bvh_cost_max: u32,
+    pub thread_number: Option<usize>, // None 表示使用全部的 CPU 核心
+    pub seed: u64,
+    pub quality: u8, // From 0 to 100
+    pub output: String,
+    pub bit_depth: u8,
+    pub ppm_ascii: bool,
+    pub exr_compression: ExrCompression,
+    pub tone_map: ToneMap,
+    pub filter: FilterKind,
+    pub filter_radius: Option<f64>, // None 表示使用滤波器默认的半径
+    pub sampler: SamplerKind,
+
+    // 渐进式渲染
+    pub samples_per_pass: u32,
+    pub adaptive: Option<Adaptive>,
+    pub heatmap: Option<String>,
+    pub aovs: Vec<Aov>,             // 额外输出的缓冲
+    pub denoise: Option<bool>,      // None 表示使用场景推荐的设置
+    pub preview_every: Option<u32>, // 每隔多少轮输出一次预览
+    pub checkpoint: Option<String>,
+    pub resume: Option<String>,
+
+    // 相机参数，None 表示使用默认值
+    pub lookfrom: Option<Vec3>,
+    pub lookat: Option<Vec3>,
+    pub vup: Option<Vec3>,
+    pub vfov: Option<f64>,
+    pub aperture: Option<f64>,
+    pub focus_dist: Option<f64>,
+}
+
+fn render_subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("render")
+        .about("Render a scene to an image file")
+        .arg(
+            Arg::with_name("scene")
+                .long("scene")
+                .short("s")
+                .value_name("NAME")
+                .help("Scene to render (see the `list` subcommand)")
+                .validator(|s| match builtin::find(&s) {
+                    Some(_) => Ok(()),
+                    None => Err(format!("unknown scene `{}`, see the `list` subcommand", s)),
+                })
+                .default_value("cornell_box"),
+        )
+        .arg(
+            Arg::with_name("scene-file")
+                .long("scene-file")
+                .short("f")
+                .value_name("PATH")
+                .help("Load the scene from a JSON scene description instead of --scene"),
+        )
+        .arg(
+            Arg::with_name("width")
+                .long("width")
+                .value_name("PIXELS")
+                .help("Image width [default: recommended by the scene]")
+                .validator(image_size),
+        )
+        .arg(
+            Arg::with_name("height")
+                .long("height")
+                .value_name("PIXELS")
+                .help("Image height [default: keep the aspect ratio of the scene]")
+                .validator(image_size),
+        )
+        .arg(
+            Arg::with_name("spp")
+                .long("spp")
+                .value_name("N")
+                .help("Samples per pixel [default: recommended by the scene]")
+                .validator(positive_integer),
+        )
+        .arg(
+            Arg::with_name("depth")
+                .long("depth")
+                .short("d")
+                .value_name("N")
+                .help("Maximum number of bounces per path [default: recommended by the scene]")
+                .validator(positive_integer),
+        )
+        .arg(
+            Arg::with_name("rr-depth")
+                .long("rr-depth")
+                .value_name("N")
+                .help("Number of bounces before paths may be terminated by Russian roulette")
+                .validator(positive_integer)
+                .default_value("3"),
+        )
+        .arg(
+            Arg::with_name("mis")
+                .long("mis")
+                .value_name("HEURISTIC")
+                .help("Weighting of light and BSDF samples in multiple importance sampling")
+                .possible_values(&path::MIS_HEURISTICS)
+                .default_value("power"),
+        )
+        .arg(
+            Arg::with_name("integrator")
+                .long("integrator")
+                .value_name("NAME")
+                .help("Light transport algorithm: path tracing, bidirectional path tracing, path tracing with progressive photon mapping for caustics, primary sample space Metropolis light transport, or a debug view chosen by --debug-view")
+                .possible_values(&INTEGRATORS)
+                .default_value("path"),
+        )
+        .arg(
+            Arg::with_name("spectral")
+                .long("spectral")
+                .help("Trace hero wavelengths instead of RGB, so dispersive glass splits light into colors (path integrator only)"),
+        )
+        .arg(
+            Arg::with_name("photons")
+                .long("photons")
+                .value_name("N")
+                .help("Photons emitted for each photon map of --integrator ppm; a new map is traced every pass")
+                .validator(positive_integer)
+                .default_value("100000"),
+        )
+        .arg(
+            Arg::with_name("photon-radius")
+                .long("photon-radius")
+                .value_name("R")
+                .help("Initial photon gathering radius, shrinking every pass [default: chosen from the photon density]")
+                .validator(|s| match s.parse::<f64>() {
+                    Ok(x) if x > 0. && x.is_finite() => Ok(()),
+                    _ => Err(format!("`{}` is not a positive number", s)),
+                }),
+        )
+        .arg(
+            Arg::with_name("mlt-bootstrap")
+                .long("mlt-bootstrap")
+                .value_name("N")
+                .help("Paths traced by --integrator mlt to estimate the image brightness and seed the Markov chains")
+                .validator(positive_integer)
+                .default_value("100000"),
+        )
+        .arg(
+            Arg::with_name("mlt-chain")
+                .long("mlt-chain")
+                .value_name("N")
+                .help("Mutations of each Markov chain of --integrator mlt")
+                .validator(positive_integer)
+                .default_value("1000"),
+        )
+        .arg(
+            Arg::with_name("mlt-large-step")
+                .long("mlt-large-step")
+                .value_name("P")
+                .help("Probability that an MLT mutation resamples the whole path instead of perturbing it")
+                .validator(|s| match s.parse::<f64>() {
+                    Ok(x) if (0. ..=1.).contains(&x) => Ok(()),
+                    _ => Err(format!("`{}` is not a number in [0, 1]", s)),
+                })
+                .default_value("0.3"),
+        )
+        .arg(
+            Arg::with_name("debug-view")
+                .long("debug-view")
+                .value_name("VIEW")
+                .help("What --integrator debug shows for the first surface each camera ray hits")
+                .possible_values(&debug::DEBUG_VIEWS)
+                .default_value("normal"),
+        )
+        .arg(
+            Arg::with_name("ao-radius")
+                .long("ao-radius")
+                .value_name("R")
+                .help("Distance within which geometry occludes in --debug-view ao [default: a tenth of the scene's bounding box diagonal]")
+                .validator(|s| match s.parse::<f64>() {
+                    Ok(x) if x > 0. && x.is_finite() => Ok(()),
+                    _ => Err(format!("`{}` is not a positive number", s)),
+                }),
+        )
+        .arg(
+            Arg::with_name("depth-max")
+                .long("depth-max")
+                .value_name("D")
+                .help("Depth that maps to white in --debug-view depth [default: the largest depth of the scene's bounding box]")
+                .validator(|s| match s.parse::<f64>() {
+                    Ok(x) if x > 0. && x.is_finite() => Ok(()),
+                    _ => Err(format!("`{}` is not a positive number", s)),
+                }),
+        )
+        .arg(
+            Arg::with_name("bvh-cost-max")
+                .long("bvh-cost-max")
+                .value_name("N")
+                .help("BVH nodes visited plus primitives tested by a camera ray that map to white in --debug-view bvh-cost")
+                .validator(positive_integer)
+                .default_value("100"),
+        )
+        .arg(
+            Arg::with_name("threads")
+                .long("threads")
+                .short("j")
+                .value_name("N")
+                .help("Number of worker threads [default: number of CPU cores]")
+                .validator(positive_integer),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("N")
+                .help("Random seed; the same seed always gives the same image")
+                .validator(|s| {
+                    s.parse::<u64>()
+                        .map(|_| ())
+                        .map_err(|_| format!("`{}` is not a valid seed", s))
+                })
+                .default_value("19260817"),
+        )
+        .arg(
+            Arg::with_name("lookfrom")
+                .long("lookfrom")
+                .value_name("X,Y,Z")
+                .help("Camera position")
+                .allow_hyphen_values(true)
+                .validator(vec3_value),
+        )
+        .arg(
+            Arg::with_name("lookat")
+                .long("lookat")
+                .value_name("X,Y,Z")
+                .help("Point the camera looks at")
+                .allow_hyphen_values(true)
+                .validator(vec3_value),
+        )
+        .arg(
+            Arg::with_name("vup")
+                .long("vup")
+                .value_name("X,Y,Z")
+                .help("Camera up vector")
+                .allow_hyphen_values(true)
+                .validator(vec3_value),
+        )
+        .arg(
+            Arg::with_name("vfov")
+                .long("vfov")
+                .value_name("DEGREES")
+                .help("Vertical field of view, in (0, 180)")
+                .validator(|s| match s.parse::<f64>() {
+                    Ok(x) if x > 0. && x < 180. => Ok(()),
+                    _ => Err(format!("`{}` is not an angle in (0, 180)", s)),
+                }),
+        )
+        .arg(
+            Arg::with_name("aperture")
+                .long("aperture")
+                .value_name("F")
+                .help("Lens aperture, 0 disables depth of field")
+                .validator(non_negative_float),
+        )
+        .arg(
+            Arg::with_name("focus-dist")
+                .long("focus-dist")
+                .value_name("F")
+                .help("Distance to the focus plane")
+                .validator(|s| match s.parse::<f64>() {
+                    Ok(x) if x > 0. => Ok(()),
+                    _ => Err(format!("`{}` is not a positive number", s)),
+                }),
+        )
+        .arg(
+            Arg::with_name("quality")
+                .long("quality")
+                .short("q")
+                .value_name("N")
+                .help("JPEG quality, from 1 to 100")
+                .validator(|s| match s.parse::<u8>() {
+                    Ok(x) if (1..=100).contains(&x) => Ok(()),
+                    _ => Err(format!("`{}` is not in 1..=100", s)),
+                })
+                .default_value("100"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .value_name("PATH")
+                .help("Output image path; the format is chosen by the extension")
+                .validator(|s| match output::extension(&s) {
+                    Some(e) if output::EXTENSIONS.contains(&e.as_str()) => Ok(()),
+                    _ => Err(format!(
+                        "`{}` must end with one of .{}",
+                        s,
+                        output::EXTENSIONS.join(", .")
+                    )),
+                })
+                .default_value("output/output.png"),
+        )
+        .arg(
+            Arg::with_name("bit-depth")
+                .long("bit-depth")
+                .value_name("BITS")
+                .help("Bits per channel of PNG and PPM output")
+                .possible_values(&["8", "16"])
+                .default_value("8"),
+        )
+        .arg(
+            Arg::with_name("ppm-ascii")
+                .long("ppm-ascii")
+                .help("Write PPM output as text instead of binary"),
+        )
+        .arg(
+            Arg::with_name("exr-compression")
+                .long("exr-compression")
+                .value_name("METHOD")
+                .help("Compression of OpenEXR output")
+                .possible_values(&["none", "zip"])
+                .default_value("zip"),
+        )
+        .arg(
+            Arg::with_name("tonemap")
+                .long("tonemap")
+                .value_name("OPERATOR")
+                .help("Tone mapping operator for 8-bit and 16-bit output")
+                .possible_values(&tonemap::OPERATORS)
+                .default_value("clamp"),
+        )
+        .arg(
+            Arg::with_name("exposure")
+                .long("exposure")
+                .value_name("EV")
+                .help("Exposure compensation in stops, applied before tone mapping")
+                .allow_hyphen_values(true)
+                .validator(|s| match s.parse::<f64>() {
+                    Ok(x) if x.is_finite() => Ok(()),
+                    _ => Err(format!("`{}` is not a number", s)),
+                })
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("white-point")
+                .long("white-point")
+                .value_name("F")
+                .help(
+                    "Radiance mapped to pure white by reinhard-extended and hable \
+                     [default: 4 for reinhard-extended, 11.2 for hable]",
+                )
+                .validator(|s| match s.parse::<f64>() {
+                    Ok(x) if x > 0. && x.is_finite() => Ok(()),
+                    _ => Err(format!("`{}` is not a positive number", s)),
+                }),
+        )
+        .arg(
+            Arg::with_name("filter")
+                .long("filter")
+                .value_name("NAME")
+                .help("Pixel reconstruction filter")
+                .possible_values(&filter::FILTERS)
+                .default_value("box"),
+        )
+        .arg(
+            Arg::with_name("filter-radius")
+                .long("filter-radius")
+                .value_name("PIXELS")
+                .help("Radius of the filter [default: 0.5 for box, 1 for tent, 1.5 for gaussian, 2 for mitchell and lanczos]")
+                .validator(|s| match s.parse::<f64>() {
+                    Ok(x) if (0.5..=8.).contains(&x) => Ok(()),
+                    _ => Err(format!("`{}` is not a radius in [0.5, 8]", s)),
+                }),
+        )
+        .arg(
+            Arg::with_name("sampler")
+                .long("sampler")
+                .value_name("NAME")
+                .help("Sample generator for pixel, lens, light and scattering samples")
+                .possible_values(&sampler::SAMPLERS)
+                .default_value("sobol"),
+        )
+        .arg(
+            Arg::with_name("pass-spp")
+                .long("pass-spp")
+                .value_name("N")
+                .help("Samples per pixel added in each progressive pass")
+                .validator(positive_integer)
+                .default_value("8"),
+        )
+        .arg(
+            Arg::with_name("adaptive")
+                .long("adaptive")
+                .help("Stop sampling a pixel once its relative error is below --adaptive-threshold; --spp becomes the upper bound"),
+        )
+        .arg(
+            Arg::with_name("adaptive-threshold")
+                .long("adaptive-threshold")
+                .value_name("F")
+                .help("Relative standard error at which a pixel counts as converged with --adaptive")
+                .validator(|s| match s.parse::<f64>() {
+                    Ok(x) if x > 0. && x.is_finite() => Ok(()),
+                    _ => Err(format!("`{}` is not a positive number", s)),
+                })
+                .default_value("0.02"),
+        )
+        .arg(
+            Arg::with_name("min-spp")
+                .long("min-spp")
+                .value_name("N")
+                .help("Samples per pixel before --adaptive may stop a pixel")
+                .validator(positive_integer)
+                .default_value("16"),
+        )
+        .arg(
+            Arg::with_name("heatmap")
+                .long("heatmap")
+                .value_name("PATH")
+                .help("Write a PNG heatmap of the samples taken by each pixel")
+                .validator(|s| match output::extension(&s).as_deref() {
+                    Some("png") => Ok(()),
+                    _ => Err(format!("`{}` must end with .png", s)),
+                }),
+        )
+        .arg(
+            Arg::with_name("aov")
+                .long("aov")
+                .value_name("NAMES")
+                .help("Also write these first-hit buffers next to the output, e.g. output/output.normal.exr")
+                .possible_values(&aov::AOV_NAMES)
+                .use_delimiter(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("denoise")
+                .long("denoise")
+                .help("Also write a denoised image, e.g. output/output.denoised.png [default: recommended by the scene]"),
+        )
+        .arg(
+            Arg::with_name("no-denoise")
+                .long("no-denoise")
+                .help("Do not write a denoised image")
+                .conflicts_with("denoise"),
+        )
+        .arg(
+            Arg::with_name("preview-every")
+                .long("preview-every")
+                .value_name("N")
+                .help("Write the current image to the output path every N passes")
+                .validator(positive_integer),
+        )
+        .arg(
+            Arg::with_name("checkpoint")
+                .long("checkpoint")
+                .value_name("PATH")
+                .help("Save the accumulated samples to this file after every pass"),
+        )
+        .arg(
+            Arg::with_name("resume")
+                .long("resume")
+                .value_name("PATH")
+                .help("Continue from a checkpoint file, adding samples until --spp is reached"),
+        )
+}
+
+pub fn parse_args() -> Command {
+    let matches = App::new("raytracer")
+        .about("PPCA ray tracer")
+        .setting(AppSettings::VersionlessSubcommands)
+        .subcommand(render_subcommand())
+        .subcommand(SubCommand::with_name("list").about("List the built-in scenes"))
+        .get_matches();
+
+    match matches.subcommand() {
+        ("list", _) => Command::ListScenes,
+        ("render", Some(sub)) => Command::Render(Box::new(RenderOptions::from_matches(sub))),
+        // 不带子命令时，按默认参数渲染
+        _ => {
+            let sub = render_subcommand().get_matches_from(vec!["render"]);
+            Command::Render(Box::new(RenderOptions::from_matches(&sub)))
+        }
+    }
+}
+
+impl RenderOptions {
+    fn from_matches(m: &ArgMatches) -> Self {
+        // 参数已经通过 validator 检查，这里可以直接 unwrap
+        let int = |name: &str| m.value_of(name).map(|s| s.parse::<i32>().unwrap());
+        let float = |name: &str| m.value_of(name).map(|s| s.parse::<f64>().unwrap());
+        let vec3 = |name: &str| m.value_of(name).map(|s| parse_vec3(s).unwrap());
+        let operator = ToneMapOperator::from_name(m.value_of("tonemap").unwrap()).unwrap();
+
+        Self {
+            scene: m.value_of("scene").unwrap().to_string(),
+            scene_file: m.value_of("scene-file").map(|s| s.to_string()),
+            width: int("width").map(|x| x as u32),
+            height: int("height").map(|x| x as u32),
+            samples_per_pixel: int("spp"),
+            max_depth: int("depth"),
+            rr_depth: int("rr-depth").unwrap(),
+            mis: MisHeuristic::from_name(m.value_of("mis").unwrap()).unwrap(),
+            integrator: IntegratorKind::from_name(m.value_of("integrator").unwrap()).unwrap(),
+            spectral: m.is_present("spectral"),
+            photons: int("photons").unwrap() as usize,
+            photon_radius: float("photon-radius"),
+            mlt: MltSettings {
+                bootstrap: int("mlt-bootstrap").unwrap() as usize,
+                chain_length: int("mlt-chain").unwrap() as u32,
+                large_step_probability: float("mlt-large-step").unwrap(),
+                ..MltSettings::default()
+            },
+            debug_view: DebugView::from_name(m.value_of("debug-view").unwrap()).unwrap(),
+            ao_radius: float("ao-radius"),
+            depth_max: float("depth-max"),
+            bvh_cost_max: int("bvh-cost-max").unwrap() as u32,
+            thread_number: int("threads").map(|x| x as usize),
+            seed: m.value_of("seed").unwrap().parse().unwrap(),
+            quality: m.value_of("quality").unwrap().parse().unwrap(),
+            output: m.value_of("output").unwrap().to_string(),
+            bit_depth: m.value_of("bit-depth").unwrap().parse().unwrap(),
+            ppm_ascii: m.is_present("ppm-ascii"),
+            exr_compression: match m.value_of("exr-compression").unwrap() {
+                "none" => ExrCompression::None,
+                _ => ExrCompression::Zip,
+            },
+            tone_map: ToneMap {
+                operator,
+                exposure: float("exposure").unwrap(),
+                white_point: float("white-point").unwrap_or_else(|| operator.default_white_point()),
+                encode_srgb: true,
+            },
+            filter: FilterKind::from_name(m.value_of("filter").unwrap()).unwrap(),
+            filter_radius: float("filter-radius"),
+            sampler: SamplerKind::from_name(m.value_of("sampler").unwrap()).unwrap(),
+            samples_per_pass: int("pass-spp").unwrap() as u32,
+            adaptive: if m.is_present("adaptive") {
+                Some(Adaptive {
+                    min_samples: int("min-spp").unwrap() as u32,
+                    threshold: float("adaptive-threshold").unwrap(),
+                })
+            } else {
+                None
+            },
+            heatmap: m.value_of("heatmap").map(|s| s.to_string()),
+            aovs: m
+                .values_of("aov")
+                .map(|names| names.map(|n| Aov::from_name(n).unwrap()).collect())
+                .unwrap_or_default(),
+            denoise: if m.is_present("denoise") {
+                Some(true)
+            } else if m.is_present("no-denoise") {
+                Some(false)
+            } else {
+                None
+            },
+            preview_every: int("preview-every").map(|x| x as u32),
+            checkpoint: m.value_of("checkpoint").map(|s| s.to_string()),
+            resume: m.value_of("resume").map(|s| s.to_string()),
+            lookfrom: vec3("lookfrom"),
+            lookat: vec3("lookat"),
+            vup: vec3("vup"),
+            vfov: float("vfov"),
+            aperture: float("aperture"),
+            focus_dist: float("focus-dist"),
+        }
+    }
+}
+
+pub fn parse_vec3(s: &str) -> Option<Vec3> {
+    // 格式为 "x,y,z"
+    let v: Vec<f64> = s
+        .split(',')
+        .map(|t| t.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    if v.len() == 3 && v.iter().all(|x| x.is_finite()) {
+        Some(Vec3::new(v[0], v[1], v[2]))
+    } else {
+        None
+    }
+}
+
+fn vec3_value(s: String) -> Result<(), String> {
+    match parse_vec3(&s) {
+        Some(_) => Ok(()),
+        None => Err(format!("`{}` is not a vector of the form X,Y,Z", s)),
+    }
+}
+
+// i32 范围内的正整数，保证能直接转成 samples_per_pixel 等变量
+fn positive_integer(s: String) -> Result<(), String> {
+    match s.parse::<i32>() {
+        Ok(x) if x > 0 => Ok(()),
+        _ => Err(format!("`{}` is not a positive integer", s)),
+    }
+}
+
+// 相机按 x / (width - 1) 把像素映射到胶片上，至少需要两个像素
+fn image_size(s: String) -> Result<(), String> {
+    match s.parse::<u32>() {
+        Ok(x) if x >= 2 => Ok(()),
+        _ => Err(format!("`{}` is not an integer of at least 2", s)),
+    }
+}
+
+fn non_negative_float(s: String) -> Result<(), String> {
+    match s.parse::<f64>() {
+        Ok(x) if x >= 0. && x.is_finite() => Ok(()),
+        _ => Err(format!("`{}` is not a non-negative number", s)),
+    }
+}
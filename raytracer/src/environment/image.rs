@@ -0,0 +1,188 @@
+use std::{f64::consts::PI, fs::File, io::BufReader, path::Path};
+
+use image::codecs::hdr::HdrDecoder;
+
+use super::Environment;
+use crate::{
+    basic_component::{sampler::Sampler, vec3::Vec3},
+    film::tonemap::srgb_eotf,
+    optimization::distribution::Distribution2D,
+    utility::{clamp, degree_to_radian},
+};
+
+// 等距柱状投影（equirectangular）的环境贴图：横坐标为经度，纵坐标为纬度
+// 与球面的纹理坐标一致，图像的上边缘对应 +y，中间一列朝向 +x
+// 按 亮度 * sinθ 建立二维的分段常数分布，太阳这样又小又亮的区域也能被采样到
+pub struct ImageEnvironment {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3>, // 按行存储，第 0 行为图像顶部
+    intensity: f64,
+    sin_rotation: f64, // 绕 y 轴旋转
+    cos_rotation: f64,
+    distribution: Distribution2D,
+}
+
+impl ImageEnvironment {
+    // rotation 为绕 y 轴旋转的角度（度），intensity 为亮度的倍数
+    pub fn new(
+        width: usize,
+        height: usize,
+        pixels: Vec<Vec3>,
+        rotation: f64,
+        intensity: f64,
+    ) -> Self {
+        let func: Vec<f64> = (0..height)
+            .flat_map(|y| {
+                // 靠近两极的像素覆盖的立体角小，按这一行中心的 sinθ 缩小
+                let sin_theta = ((y as f64 + 0.5) / height as f64 * PI).sin();
+                pixels[y * width..(y + 1) * width]
+                    .iter()
+                    .map(move |p| p.luminance() * sin_theta)
+            })
+            .collect();
+        let distribution = Distribution2D::new(&func, width, height);
+        let rotation = degree_to_radian(rotation);
+        Self {
+            width,
+            height,
+            pixels,
+            intensity,
+            sin_rotation: rotation.sin(),
+            cos_rotation: rotation.cos(),
+            distribution,
+        }
+    }
+
+    // 在每个像素的中心对 f 取值得到的贴图，用来对解析的背景做重要性采样
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(Vec3) -> Vec3) -> Self {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let u = (x as f64 + 0.5) / width as f64;
+                let t = (y as f64 + 0.5) / height as f64;
+                f(lat_long_dir(u, t))
+            })
+            .collect();
+        Self::new(width, height, pixels, 0., 1.)
+    }
+
+    // .hdr 为 Radiance HDR 格式，其他格式按 8 位的 sRGB 图像读入
+    pub fn load(file: &str, rotation: f64, intensity: f64) -> Result<Self, String> {
+        let hdr = match Path::new(file).extension() {
+            Some(e) => e.eq_ignore_ascii_case("hdr"),
+            None => false,
+        };
+        let (width, height, pixels) = if hdr {
+            read_hdr(file)?
+        } else {
+            read_ldr(file)?
+        };
+        if width == 0 || height == 0 {
+            return Err(format!("environment map `{}` is empty", file));
+        }
+        Ok(Self::new(width, height, pixels, rotation, intensity))
+    }
+
+    // 整个球面上亮度的积分（按像素近似）
+    pub fn power(&self) -> f64 {
+        self.distribution.integral() * 2. * PI * PI * self.intensity
+    }
+
+    // 世界坐标中的方向对应贴图上的 (u, t)，t = 0 为图像顶部
+    fn dir_to_map(&self, dir: Vec3) -> (f64, f64) {
+        let d = Vec3::unit_vector(dir);
+        let x = self.cos_rotation * d.x - self.sin_rotation * d.z;
+        let z = self.sin_rotation * d.x + self.cos_rotation * d.z;
+        let theta = clamp(-d.y, -1., 1.).acos();
+        let phi = (-z).atan2(x) + PI;
+        (phi / (2. * PI), 1. - theta / PI)
+    }
+
+    fn map_to_dir(&self, u: f64, t: f64) -> Vec3 {
+        let d = lat_long_dir(u, t);
+        Vec3::new(
+            self.cos_rotation * d.x + self.sin_rotation * d.z,
+            d.y,
+            -self.sin_rotation * d.x + self.cos_rotation * d.z,
+        )
+    }
+
+    // 从 (u, t) 上的 pdf 换算到立体角上：dω = 2π² sinθ du dt
+    fn solid_angle_pdf(pdf: f64, t: f64) -> f64 {
+        let sin_theta = (t * PI).sin();
+        if sin_theta <= 0. {
+            0.
+        } else {
+            pdf / (2. * PI * PI * sin_theta)
+        }
+    }
+}
+
+impl Environment for ImageEnvironment {
+    fn radiance(&self, dir: Vec3) -> Vec3 {
+        let (u, t) = self.dir_to_map(dir);
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((t * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[j * self.width + i] * self.intensity
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, f64)> {
+        // 全黑的贴图没有需要采样的地方
+        if self.distribution.integral() <= 0. {
+            return None;
+        }
+        let ((u, t), pdf) = self.distribution.sample(sampler.get_2d());
+        let pdf = Self::solid_angle_pdf(pdf, t);
+        if pdf <= 0. {
+            return None;
+        }
+        Some((self.map_to_dir(u, t), pdf))
+    }
+
+    fn pdf(&self, dir: Vec3) -> f64 {
+        if self.distribution.integral() <= 0. {
+            return 0.;
+        }
+        let (u, t) = self.dir_to_map(dir);
+        Self::solid_angle_pdf(self.distribution.pdf(u, t), t)
+    }
+}
+
+// 贴图上 (u, t) 处对应的方向（不考虑旋转）
+fn lat_long_dir(u: f64, t: f64) -> Vec3 {
+    let phi = 2. * PI * u;
+    let theta = (1. - t) * PI;
+    Vec3::new(
+        -phi.cos() * theta.sin(),
+        -theta.cos(),
+        phi.sin() * theta.sin(),
+    )
+}
+
+fn read_hdr(file: &str) -> Result<(usize, usize, Vec<Vec3>), String> {
+    let f = File::open(file).map_err(|e| format!("cannot open `{}`: {}", file, e))?;
+    let decoder = HdrDecoder::new(BufReader::new(f))
+        .map_err(|e| format!("cannot decode `{}`: {}", file, e))?;
+    let meta = decoder.metadata();
+    let pixels = decoder
+        .read_image_hdr()
+        .map_err(|e| format!("cannot decode `{}`: {}", file, e))?;
+    let pixels = pixels
+        .iter()
+        .map(|p| Vec3::new(p.0[0] as f64, p.0[1] as f64, p.0[2] as f64))
+        .collect();
+    Ok((meta.width as usize, meta.height as usize, pixels))
+}
+
+fn read_ldr(file: &str) -> Result<(usize, usize, Vec<Vec3>), String> {
+    let image = image::open(file)
+        .map_err(|e| format!("cannot decode `{}`: {}", file, e))?
+        .into_rgb8();
+    let linear = |c: u8| srgb_eotf(c as f64 / 255.);
+    let pixels = image
+        .pixels()
+        .map(|p| Vec3::new(linear(p.0[0]), linear(p.0[1]), linear(p.0[2])))
+        .collect();
+    Ok((image.width() as usize, image.height() as usize, pixels))
+}
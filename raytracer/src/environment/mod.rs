@@ -0,0 +1,37 @@
+use crate::basic_component::{sampler::Sampler, vec3::Vec3};
+
+pub mod image;
+pub mod sky;
+
+// 光线没有碰到任何物体时看到的背景，可以看作包围整个场景、无限远处的光源
+pub trait Environment: Send + Sync {
+    // 沿 dir 方向看到的亮度
+    fn radiance(&self, dir: Vec3) -> Vec3;
+
+    // 按亮度采样一个方向，返回方向和它在立体角上的 pdf
+    // 返回 None 表示不对背景做重要性采样，此时 pdf 恒为 0
+    fn sample(&self, _sampler: &mut dyn Sampler) -> Option<(Vec3, f64)> {
+        None
+    }
+
+    fn pdf(&self, _dir: Vec3) -> f64 {
+        0.
+    }
+}
+
+// 单一颜色的背景，亮度处处相同，不需要重要性采样
+pub struct ConstantEnvironment {
+    pub color: Vec3,
+}
+
+impl ConstantEnvironment {
+    pub fn new(color: Vec3) -> Self {
+        Self { color }
+    }
+}
+
+impl Environment for ConstantEnvironment {
+    fn radiance(&self, _dir: Vec3) -> Vec3 {
+        self.color
+    }
+}
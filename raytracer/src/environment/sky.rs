@@ -0,0 +1,205 @@
+use std::f64::consts::PI;
+
+use super::{image::ImageEnvironment, Environment};
+use crate::{
+    basic_component::{onb::ONB, sampler::Sampler, vec3::Vec3},
+    utility::{clamp, degree_to_radian},
+};
+
+// 太阳的视直径约为 0.53°
+const SUN_ANGULAR_RADIUS: f64 = 0.2665 * PI / 180.;
+// 1 kcd/m² 对应的亮度，天顶通常为几 kcd/m²，与原先 0.7 左右的天空颜色相当
+const KCD: f64 = 0.1;
+// 大气层外太阳的亮度（kcd/m²）
+const SUN_LUMINANCE: f64 = 2.0e6;
+
+// Preetham 等人（1999）的解析天空模型，加上一个视直径正确的太阳
+// 天空的颜色由太阳的高度和大气的浑浊度（turbidity，2 为晴朗，10 为雾霾）决定，地平线以下为黑色
+// 天空按一张预先算好的亮度表采样，太阳在它的圆盘内均匀采样，按两者的功率分配采样次数
+pub struct SkyEnvironment {
+    sky: Preetham,
+    sun_dir: Vec3,
+    sun_radiance: Vec3,
+    cos_sun: f64, // 太阳圆盘边缘与中心夹角的余弦
+    intensity: f64,
+    table: ImageEnvironment,
+    sun_probability: f64, // 采样时选中太阳的概率
+}
+
+impl SkyEnvironment {
+    // elevation、azimuth 为太阳的高度角和方位角（度），方位角从 +z 轴转向 +x 轴
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64, intensity: f64) -> Self {
+        let (e, a) = (degree_to_radian(elevation), degree_to_radian(azimuth));
+        let sun_dir = Vec3::new(e.cos() * a.sin(), e.sin(), e.cos() * a.cos());
+        let theta_s = PI / 2. - e;
+        let sky = Preetham::new(sun_dir, theta_s, turbidity);
+        let table = ImageEnvironment::from_fn(128, 64, |d| sky.radiance(d));
+        let sun_radiance = sun_radiance(theta_s, turbidity);
+        let cos_sun = SUN_ANGULAR_RADIUS.cos();
+
+        let sun_power = sun_radiance.luminance() * 2. * PI * (1. - cos_sun);
+        let sun_probability = sun_power / (sun_power + table.power());
+        Self {
+            sky,
+            sun_dir,
+            sun_radiance,
+            cos_sun,
+            intensity,
+            table,
+            sun_probability,
+        }
+    }
+
+    fn sun_pdf(&self, dir: Vec3) -> f64 {
+        if Vec3::dot(Vec3::unit_vector(dir), self.sun_dir) >= self.cos_sun {
+            1. / (2. * PI * (1. - self.cos_sun))
+        } else {
+            0.
+        }
+    }
+}
+
+impl Environment for SkyEnvironment {
+    fn radiance(&self, dir: Vec3) -> Vec3 {
+        let d = Vec3::unit_vector(dir);
+        let mut radiance = self.sky.radiance(d);
+        if Vec3::dot(d, self.sun_dir) >= self.cos_sun {
+            radiance += self.sun_radiance;
+        }
+        radiance * self.intensity
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, f64)> {
+        let dir = if sampler.get_1d() < self.sun_probability {
+            // 在太阳圆盘对应的圆锥内均匀采样
+            let (u, v) = sampler.get_2d();
+            let cos_theta = 1. - u * (1. - self.cos_sun);
+            let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+            let phi = 2. * PI * v;
+            ONB::build_from_w(self.sun_dir).local(
+                phi.cos() * sin_theta,
+                phi.sin() * sin_theta,
+                cos_theta,
+            )
+        } else {
+            self.table.sample(sampler)?.0
+        };
+        let pdf = self.pdf(dir);
+        if pdf > 0. {
+            Some((dir, pdf))
+        } else {
+            None
+        }
+    }
+
+    fn pdf(&self, dir: Vec3) -> f64 {
+        self.sun_probability * self.sun_pdf(dir) + (1. - self.sun_probability) * self.table.pdf(dir)
+    }
+}
+
+// Perez 等人的亮度分布 F(θ, γ) = (1 + A e^(B / cosθ)) (1 + C e^(Dγ) + E cos²γ)
+// θ 为与天顶的夹角，γ 为与太阳的夹角
+fn perez(c: &[f64; 5], cos_theta: f64, gamma: f64, cos_gamma: f64) -> f64 {
+    (1. + c[0] * (c[1] / cos_theta).exp())
+        * (1. + c[2] * (c[3] * gamma).exp() + c[4] * cos_gamma * cos_gamma)
+}
+
+struct Preetham {
+    sun_dir: Vec3,
+    coefficients: [[f64; 5]; 3], // 亮度 Y 和色度 x、y 各自的 A ~ E
+    zenith: [f64; 3],            // 天顶处的 Y、x、y 除以 F(0, θs)
+}
+
+impl Preetham {
+    fn new(sun_dir: Vec3, theta_s: f64, t: f64) -> Self {
+        let coefficients = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        // 天顶的亮度（kcd/m²）和色度
+        let chi = (4. / 9. - t / 120.) * (PI - 2. * theta_s);
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let powers = [theta_s.powi(3), theta_s * theta_s, theta_s, 1.];
+        let poly = |m: [[f64; 4]; 3]| {
+            let dot = |row: [f64; 4]| {
+                row.iter()
+                    .zip(powers.iter())
+                    .map(|(a, b)| a * b)
+                    .sum::<f64>()
+            };
+            t * t * dot(m[0]) + t * dot(m[1]) + dot(m[2])
+        };
+        let x = poly([
+            [0.00166, -0.00375, 0.00209, 0.],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let y = poly([
+            [0.00275, -0.00610, 0.00317, 0.],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let mut zenith = [luminance, x, y];
+        for (z, c) in zenith.iter_mut().zip(coefficients.iter()) {
+            *z /= perez(c, 1., theta_s, theta_s.cos());
+        }
+        Self {
+            sun_dir,
+            coefficients,
+            zenith,
+        }
+    }
+
+    // 单位方向 d 上天空的颜色（线性 sRGB）
+    fn radiance(&self, d: Vec3) -> Vec3 {
+        if d.y <= 0. {
+            return Vec3::new(0., 0., 0.);
+        }
+        let cos_gamma = clamp(Vec3::dot(d, self.sun_dir), -1., 1.);
+        let gamma = cos_gamma.acos();
+        let value = |i: usize| self.zenith[i] * perez(&self.coefficients[i], d.y, gamma, cos_gamma);
+        let (luminance, x, y) = (value(0) * KCD, value(1), value(2));
+
+        // xyY -> XYZ -> 线性 sRGB
+        let cx = x / y * luminance;
+        let cz = (1. - x - y) / y * luminance;
+        Vec3::new(
+            3.2406 * cx - 1.5372 * luminance - 0.4986 * cz,
+            -0.9689 * cx + 1.8758 * luminance + 0.0415 * cz,
+            0.0557 * cx - 0.2040 * luminance + 1.0570 * cz,
+        )
+    }
+}
+
+// 大气层外的太阳亮度乘上 Rayleigh 散射和气溶胶的透过率（Preetham 等人的附录），
+// 红、绿、蓝分别取 0.68、0.55、0.44 μm 处的值；太阳越低，穿过的大气越厚，颜色越红
+fn sun_radiance(theta_s: f64, t: f64) -> Vec3 {
+    // 相对光学质量，天顶处为 1
+    let m = 1. / (theta_s.cos() + 0.15 * (93.885 - theta_s.to_degrees()).powf(-1.253));
+    let beta = 0.04608 * t - 0.04586; // Ångström 浑浊系数
+    let tau = |lambda: f64| {
+        (-0.008735 * lambda.powf(-4.08) * m).exp() * (-beta * lambda.powf(-1.3) * m).exp()
+    };
+    Vec3::new(tau(0.68), tau(0.55), tau(0.44)) * (SUN_LUMINANCE * KCD)
+}
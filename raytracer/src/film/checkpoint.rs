@@ -0,0 +1,256 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+};
+
+use crate::{basic_component::vec3::Vec3, film::Film};
+
+// 断点文件：保存累加的颜色和每个像素的采样次数，之后可以继续渲染
+// 格式（小端序）：
+//   magic "RTCKPT07" | width u32 | height u32 | seed u64 | 场景文件的哈希 u64
+//   | 场景名长度 u32 | 场景名 | 滤波器长度 u32 | 滤波器（名字和半径）| 采样器长度 u32 | 采样器名
+//   | 积分器长度 u32 | 积分器名 | 渲染参数长度 u32 | 渲染参数（每行一个 key=value）
+//   每个像素依次为加权的 r, g, b、权重、亮度之和、亮度的平方和 (f64)、发出的采样次数、亮度有限的采样次数 (u32)
+//   和 splat 的 r, g, b (f64)
+const MAGIC: &[u8; 8] = b"RTCKPT07";
+const PIXEL_LEN: u64 = 8 * 6 + 4 * 2 + 8 * 3;
+
+// 决定每次采样结果的全部设置，恢复时必须完全相同，否则新旧采样不能累加在一起
+#[derive(Clone, PartialEq)]
+pub struct RenderInfo {
+    pub scene: String,   // 场景名或场景文件的路径
+    pub scene_hash: u64, // 场景文件内容的哈希，内置场景为 0；背景也写在场景中
+    pub seed: u64,
+    pub filter: String, // 换了滤波器的话，之前累加的结果就不能用了
+    pub sampler: String,
+    pub integrator: String,              // 不同积分器的 splat 不能混在一起
+    pub settings: Vec<(String, String)>, // 相机、路径深度、MIS、光子数等其他参数
+}
+
+impl RenderInfo {
+    // 与 current 不同时，返回断点是用什么设置渲染的
+    pub fn mismatch(&self, current: &RenderInfo) -> Option<String> {
+        if self.scene != current.scene {
+            return Some(format!(
+                "was rendered from `{}`, not `{}`",
+                self.scene, current.scene
+            ));
+        }
+        if self.scene_hash != current.scene_hash {
+            return Some(format!("was rendered before `{}` was modified", self.scene));
+        }
+        if self.seed != current.seed {
+            return Some(format!("was rendered with --seed {}", self.seed));
+        }
+        if self.filter != current.filter {
+            return Some(format!("was rendered with the {} filter", self.filter));
+        }
+        if self.sampler != current.sampler {
+            return Some(format!("was rendered with --sampler {}", self.sampler));
+        }
+        if self.integrator != current.integrator {
+            return Some(format!(
+                "was rendered with the {} integrator",
+                self.integrator
+            ));
+        }
+        for (key, value) in current.settings.iter() {
+            let old = self.settings.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+            if old != Some(value) {
+                return Some(format!(
+                    "was rendered with {} = {}, not {}",
+                    key,
+                    old.map_or("(none)", |v| v.as_str()),
+                    value
+                ));
+            }
+        }
+        if self.settings.len() != current.settings.len() {
+            return Some("was rendered with different settings".to_string());
+        }
+        None
+    }
+
+    fn settings_text(&self) -> String {
+        self.settings
+            .iter()
+            .map(|(k, v)| format!("{}={}\n", k, v))
+            .collect()
+    }
+}
+
+// 场景文件内容的哈希（FNV-1a），文件改动后旧的断点不能再用
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |h, &b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+pub struct Checkpoint {
+    pub info: RenderInfo,
+    pub film: Film,
+}
+
+pub fn save(path: &str, info: &RenderInfo, film: &Film) -> Result<(), String> {
+    // 先写入临时文件再改名，写到一半被打断也不会破坏原有的断点
+    let tmp_path = format!("{}.tmp", path);
+    write_file(&tmp_path, info, film)
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .map_err(|e| format!("cannot write checkpoint `{}`: {}", path, e))
+}
+
+fn write_file(path: &str, info: &RenderInfo, film: &Film) -> std::io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    w.write_all(MAGIC)?;
+    w.write_all(&film.width.to_le_bytes())?;
+    w.write_all(&film.height.to_le_bytes())?;
+    w.write_all(&info.seed.to_le_bytes())?;
+    w.write_all(&info.scene_hash.to_le_bytes())?;
+    let settings = info.settings_text();
+    for text in [
+        info.scene.as_str(),
+        info.filter.as_str(),
+        info.sampler.as_str(),
+        info.integrator.as_str(),
+        settings.as_str(),
+    ]
+    .iter()
+    {
+        w.write_all(&(text.len() as u32).to_le_bytes())?;
+        w.write_all(text.as_bytes())?;
+    }
+    for id in 0..film.sum.len() {
+        let color = film.sum[id];
+        w.write_all(&color.x.to_le_bytes())?;
+        w.write_all(&color.y.to_le_bytes())?;
+        w.write_all(&color.z.to_le_bytes())?;
+        w.write_all(&film.weight[id].to_le_bytes())?;
+        w.write_all(&film.sum_lum[id].to_le_bytes())?;
+        w.write_all(&film.sum_sq[id].to_le_bytes())?;
+        w.write_all(&film.samples[id].to_le_bytes())?;
+        w.write_all(&film.accepted[id].to_le_bytes())?;
+        let splat = film.splat[id];
+        w.write_all(&splat.x.to_le_bytes())?;
+        w.write_all(&splat.y.to_le_bytes())?;
+        w.write_all(&splat.z.to_le_bytes())?;
+    }
+    w.flush()
+}
+
+pub fn load(path: &str) -> Result<Checkpoint, String> {
+    let file = File::open(path).map_err(|e| format!("cannot open checkpoint `{}`: {}", path, e))?;
+    let file_len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut reader = Reader {
+        inner: BufReader::new(file),
+        path,
+    };
+
+    let mut magic = [0u8; 8];
+    reader.read(&mut magic)?;
+    if &magic[..6] != b"RTCKPT" {
+        return Err(format!("`{}` is not a checkpoint file", path));
+    }
+    if &magic != MAGIC {
+        return Err(format!(
+            "checkpoint `{}` was written by an incompatible version",
+            path
+        ));
+    }
+    let width = reader.u32()?;
+    let height = reader.u32()?;
+    let seed = reader.u64()?;
+    let scene_hash = reader.u64()?;
+    let scene = reader.string(file_len)?;
+    let filter = reader.string(file_len)?;
+    let sampler = reader.string(file_len)?;
+    let integrator = reader.string(file_len)?;
+    let settings_text = reader.string(file_len)?;
+
+    // 先检查文件长度，避免损坏的文件导致分配过大的内存
+    let strings_len =
+        4 * 5 + scene.len() + filter.len() + sampler.len() + integrator.len() + settings_text.len();
+    let header_len = (MAGIC.len() + 4 + 4 + 8 + 8 + strings_len) as u64;
+    let expected_len = width as u64 * height as u64 * PIXEL_LEN + header_len;
+    if width == 0 || height == 0 || file_len != expected_len {
+        return Err(format!("checkpoint `{}` is corrupted", path));
+    }
+
+    let mut film = Film::new(width, height);
+    for i in 0..film.sum.len() {
+        let x = reader.f64()?;
+        let y = reader.f64()?;
+        let z = reader.f64()?;
+        film.sum[i] = Vec3::new(x, y, z);
+        film.weight[i] = reader.f64()?;
+        film.sum_lum[i] = reader.f64()?;
+        film.sum_sq[i] = reader.f64()?;
+        film.samples[i] = reader.u32()?;
+        film.accepted[i] = reader.u32()?;
+        film.total_samples += film.samples[i] as u64;
+        let x = reader.f64()?;
+        let y = reader.f64()?;
+        let z = reader.f64()?;
+        film.splat[i] = Vec3::new(x, y, z);
+    }
+
+    let mut settings = Vec::new();
+    for line in settings_text.lines() {
+        match line.find('=') {
+            Some(i) => settings.push((line[..i].to_string(), line[i + 1..].to_string())),
+            None => return Err(format!("checkpoint `{}` is corrupted", path)),
+        }
+    }
+
+    Ok(Checkpoint {
+        info: RenderInfo {
+            scene,
+            scene_hash,
+            seed,
+            filter,
+            sampler,
+            integrator,
+            settings,
+        },
+        film,
+    })
+}
+
+struct Reader<'a> {
+    inner: BufReader<File>,
+    path: &'a str,
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<(), String> {
+        self.inner
+            .read_exact(buf)
+            .map_err(|_| format!("checkpoint `{}` is truncated", self.path))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let mut buf = [0u8; 4];
+        self.read(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        let mut buf = [0u8; 8];
+        self.read(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_bits(self.u64()?))
+    }
+
+    // 长度 (u32) + UTF-8 字符串，长度不可能超过文件的大小
+    fn string(&mut self, file_len: u64) -> Result<String, String> {
+        let len = self.u32()? as u64;
+        if len > file_len {
+            return Err(format!("checkpoint `{}` is corrupted", self.path));
+        }
+        let mut buf = vec![0u8; len as usize];
+        self.read(&mut buf)?;
+        String::from_utf8(buf).map_err(|_| format!("checkpoint `{}` is corrupted", self.path))
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use indicatif::ProgressBar;
+
+use crate::{basic_component::vec3::Vec3, film::Film, render::scheduler};
+
+// 边缘保持的 à-trous 小波滤波（Dammertz et al. 2010）
+// 每一轮用 5x5 的 B3 样条核做加权平均，核的间隔依次为 1, 2, 4, ...
+// 权重由颜色、法线和反照率的差异决定，跨越物体边缘、纹理边缘时权重很小
+pub struct Denoiser {
+    pub iterations: u32,
+    pub sigma_color: f64, // 每一轮减半，逐渐只平滑更小的颜色差异
+    pub sigma_normal: f64,
+    pub sigma_albedo: f64,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self {
+            iterations: 4,
+            sigma_color: 1.6,
+            sigma_normal: 0.3,
+            sigma_albedo: 0.1,
+        }
+    }
+}
+
+const KERNEL: [f64; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+const EPSILON: f64 = 1e-3;
+
+impl Denoiser {
+    // albedo、normal 为每个像素第一次击中的表面信息，与 film 的存储顺序相同
+    // 有线程失败时返回 None
+    pub fn denoise(
+        &self,
+        film: &Film,
+        albedo: &[Vec3],
+        normal: &[Vec3],
+        thread_number: usize,
+    ) -> Option<Vec<Vec3>> {
+        let (width, height) = (film.width, film.height);
+        let albedo = Arc::new(albedo.to_vec());
+        let normal = Arc::new(normal.to_vec());
+
+        // 先除以反照率，只对光照部分滤波，纹理的细节不会被抹掉
+        let mut irradiance = Vec::with_capacity(albedo.len());
+        for y in 0..height {
+            for x in 0..width {
+                let c = film.linear(x, y);
+                let a = albedo[film.index(x, y)];
+                irradiance.push(Vec3::new(
+                    demodulate(c[0] as f64, a.x),
+                    demodulate(c[1] as f64, a.y),
+                    demodulate(c[2] as f64, a.z),
+                ));
+            }
+        }
+
+        let progress_bar = ProgressBar::hidden();
+        for i in 0..self.iterations {
+            let step = 1i64 << i;
+            let sigma_color = self.sigma_color / (1 << i) as f64;
+            let (sigma_normal, sigma_albedo) = (self.sigma_normal, self.sigma_albedo);
+            let input = Arc::new(irradiance);
+            let (albedo, normal) = (albedo.clone(), normal.clone());
+            let filtered =
+                scheduler::render(width, height, thread_number, &progress_bar, move |x, y| {
+                    let p = (y * width + x) as usize;
+                    let (cp, np, ap) = (compress(input[p]), normal[p], albedo[p]);
+                    let mut sum = Vec3::new(0., 0., 0.);
+                    let mut weight_sum = 0.;
+                    for (ky, hy) in KERNEL.iter().enumerate() {
+                        let qy = y as i64 + (ky as i64 - 2) * step;
+                        if qy < 0 || qy >= height as i64 {
+                            continue;
+                        }
+                        for (kx, hx) in KERNEL.iter().enumerate() {
+                            let qx = x as i64 + (kx as i64 - 2) * step;
+                            if qx < 0 || qx >= width as i64 {
+                                continue;
+                            }
+                            let q = (qy * width as i64 + qx) as usize;
+                            let dc = (compress(input[q]) - cp).length_squared();
+                            let dn = (normal[q] - np).length_squared();
+                            let da = (albedo[q] - ap).length_squared();
+                            let w = hx
+                                * hy
+                                * (-dc / (sigma_color * sigma_color)
+                                    - dn / (sigma_normal * sigma_normal)
+                                    - da / (sigma_albedo * sigma_albedo))
+                                    .exp();
+                            sum += input[q] * w;
+                            weight_sum += w;
+                        }
+                    }
+                    // 中心像素的权重为 hx * hy > 0，不会除以 0
+                    sum / weight_sum
+                });
+            irradiance = filtered?;
+        }
+
+        // 再乘回反照率
+        let denoised = irradiance
+            .iter()
+            .zip(albedo.iter())
+            .map(|(c, a)| {
+                Vec3::new(
+                    remodulate(c.x, a.x),
+                    remodulate(c.y, a.y),
+                    remodulate(c.z, a.z),
+                )
+            })
+            .collect();
+        Some(denoised)
+    }
+}
+
+// 反照率接近 0（背景、光源）时不做处理
+fn demodulate(color: f64, albedo: f64) -> f64 {
+    if albedo > EPSILON {
+        color / albedo
+    } else {
+        color
+    }
+}
+
+fn remodulate(irradiance: f64, albedo: f64) -> f64 {
+    if albedo > EPSILON {
+        irradiance * albedo
+    } else {
+        irradiance
+    }
+}
+
+// 比较颜色时先把 HDR 的值压缩到 [0, 1)，避免光源附近的差异过大
+fn compress(c: Vec3) -> Vec3 {
+    Vec3::new(c.x / (1. + c.x), c.y / (1. + c.y), c.z / (1. + c.z))
+}
@@ -0,0 +1,301 @@
+use std::io::{self, Write};
+
+use crate::film::Film;
+
+// 最简单的 OpenEXR：单层扫描线图像，B、G、R 三个 32 位浮点通道
+// 支持不压缩和 ZIP 压缩（每 16 行一块，zlib）
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum ExrCompression {
+    None,
+    Zip,
+}
+
+impl ExrCompression {
+    fn id(self) -> u8 {
+        match self {
+            ExrCompression::None => 0,
+            ExrCompression::Zip => 3,
+        }
+    }
+
+    // 每一块包含的扫描线数
+    fn lines_per_block(self) -> u32 {
+        match self {
+            ExrCompression::None => 1,
+            ExrCompression::Zip => 16,
+        }
+    }
+}
+
+const FLOAT: i32 = 2; // 通道的像素类型
+
+pub fn write_exr<W: Write>(w: &mut W, film: &Film, compression: ExrCompression) -> io::Result<()> {
+    let (width, height) = (film.width, film.height);
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]); // magic
+    header.extend_from_slice(&2u32.to_le_bytes()); // 版本 2，单层扫描线
+
+    // 通道按名字的字母顺序排列
+    let mut channels = Vec::new();
+    for name in ["B", "G", "R"].iter() {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&FLOAT.to_le_bytes());
+        channels.extend_from_slice(&[0, 0, 0, 0]); // pLinear + 保留
+        channels.extend_from_slice(&1i32.to_le_bytes()); // xSampling
+        channels.extend_from_slice(&1i32.to_le_bytes()); // ySampling
+    }
+    channels.push(0);
+    attribute(&mut header, "channels", "chlist", &channels);
+    attribute(
+        &mut header,
+        "compression",
+        "compression",
+        &[compression.id()],
+    );
+
+    let mut window = Vec::new();
+    for v in [0, 0, width as i32 - 1, height as i32 - 1].iter() {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]); // 从上到下
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    // 逐块生成数据，最后再写入偏移表
+    let lines = compression.lines_per_block();
+    let mut chunks = Vec::new();
+    for y0 in (0..height).step_by(lines as usize) {
+        let y1 = (y0 + lines).min(height);
+        let mut raw = Vec::with_capacity(((y1 - y0) * width * 12) as usize);
+        for y in y0..y1 {
+            // EXR 的第一行是图像顶部，Film 的 y = 0 是底部
+            let row = height - 1 - y;
+            for c in (0..3).rev() {
+                for x in 0..width {
+                    raw.extend_from_slice(&film.linear(x, row)[c].to_le_bytes());
+                }
+            }
+        }
+        let data = match compression {
+            ExrCompression::None => raw,
+            ExrCompression::Zip => zip_compress(raw),
+        };
+        chunks.push((y0, data));
+    }
+
+    w.write_all(&header)?;
+    let mut offset = (header.len() + chunks.len() * 8) as u64;
+    for (_, data) in chunks.iter() {
+        w.write_all(&offset.to_le_bytes())?;
+        offset += 8 + data.len() as u64;
+    }
+    for (y, data) in chunks.iter() {
+        w.write_all(&(*y as i32).to_le_bytes())?;
+        w.write_all(&(data.len() as i32).to_le_bytes())?;
+        w.write_all(data)?;
+    }
+    w.flush()
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, ty: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(ty.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+// EXR 的 ZIP 压缩：先把奇偶字节分开，再做差分，最后用 zlib 压缩
+// 压缩后反而变大时，直接保存原始数据
+fn zip_compress(raw: Vec<u8>) -> Vec<u8> {
+    let half = raw.len() - raw.len() / 2; // 偶数位置的字节数
+    let mut tmp = vec![0u8; raw.len()];
+    for (i, b) in raw.iter().enumerate() {
+        if i % 2 == 0 {
+            tmp[i / 2] = *b;
+        } else {
+            tmp[half + i / 2] = *b;
+        }
+    }
+    let mut prev = tmp[0];
+    for t in tmp.iter_mut().skip(1) {
+        let d = t.wrapping_sub(prev).wrapping_add(128);
+        prev = *t;
+        *t = d;
+    }
+
+    let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&tmp, 6);
+    if compressed.len() < raw.len() {
+        compressed
+    } else {
+        raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+    use crate::{
+        basic_component::{
+            sampler::{Pcg32, Sampler},
+            vec3::Vec3,
+        },
+        film::pfm,
+    };
+
+    // 只读取 write_exr 写出的文件：返回宽、高，以及从顶部一行开始的 (R, G, B)
+    fn read_exr(bytes: &[u8]) -> (u32, u32, Vec<[f32; 3]>) {
+        let i32_at =
+            |p: usize| i32::from_le_bytes([bytes[p], bytes[p + 1], bytes[p + 2], bytes[p + 3]]);
+        let cstr = |p: &mut usize| {
+            let start = *p;
+            while bytes[*p] != 0 {
+                *p += 1;
+            }
+            *p += 1;
+            String::from_utf8(bytes[start..*p - 1].to_vec()).unwrap()
+        };
+        assert_eq!(bytes[..4], [0x76, 0x2f, 0x31, 0x01]);
+        assert_eq!(i32_at(4), 2);
+
+        let mut p = 8;
+        let (mut width, mut height, mut compression) = (0, 0, None);
+        while bytes[p] != 0 {
+            let name = cstr(&mut p);
+            let _ty = cstr(&mut p);
+            let size = i32_at(p) as usize;
+            p += 4;
+            match name.as_str() {
+                "dataWindow" => {
+                    width = (i32_at(p + 8) - i32_at(p) + 1) as u32;
+                    height = (i32_at(p + 12) - i32_at(p + 4) + 1) as u32;
+                }
+                "compression" => compression = Some(bytes[p]),
+                _ => (),
+            }
+            p += size;
+        }
+        p += 1;
+        let lines = match compression {
+            Some(0) => 1,
+            Some(3) => 16,
+            c => panic!("unexpected compression {:?}", c),
+        };
+
+        let mut pixels = vec![[0f32; 3]; (width * height) as usize];
+        for (b, y0) in (0..height).step_by(lines as usize).enumerate() {
+            let offset = u64::from_le_bytes(bytes[p + b * 8..p + b * 8 + 8].try_into().unwrap());
+            let q = offset as usize;
+            assert_eq!(i32_at(q) as u32, y0);
+            let size = i32_at(q + 4) as usize;
+            let data = &bytes[q + 8..q + 8 + size];
+            let y1 = (y0 + lines).min(height);
+            let raw_len = ((y1 - y0) * width * 12) as usize;
+            let raw = if size == raw_len {
+                data.to_vec()
+            } else {
+                zip_decompress(data, raw_len)
+            };
+            let mut floats = raw
+                .chunks(4)
+                .map(|c| f32::from_le_bytes(c.try_into().unwrap()));
+            for y in y0..y1 {
+                // 每一行先是 B 通道，再是 G、R
+                for c in (0..3).rev() {
+                    for x in 0..width {
+                        pixels[(y * width + x) as usize][c] = floats.next().unwrap();
+                    }
+                }
+            }
+        }
+        (width, height, pixels)
+    }
+
+    fn zip_decompress(data: &[u8], raw_len: usize) -> Vec<u8> {
+        let mut tmp = miniz_oxide::inflate::decompress_to_vec_zlib(data).unwrap();
+        assert_eq!(tmp.len(), raw_len);
+        for i in 1..tmp.len() {
+            tmp[i] = tmp[i - 1].wrapping_add(tmp[i]).wrapping_sub(128);
+        }
+        let half = raw_len - raw_len / 2;
+        (0..raw_len)
+            .map(|i| {
+                if i % 2 == 0 {
+                    tmp[i / 2]
+                } else {
+                    tmp[half + i / 2]
+                }
+            })
+            .collect()
+    }
+
+    // 高度不是 16 的整数倍；底部是平滑的渐变（压缩后变小），顶部是随机的噪声（保存原始数据）
+    fn test_film() -> Film {
+        let (width, height) = (7, 37);
+        let mut rng = Pcg32::new(7);
+        let values = (0..width * height)
+            .map(|i| {
+                if i < width * 20 {
+                    Vec3::new(i as f64 * 0.25, 1., 0.5)
+                } else {
+                    Vec3::new(rng.get_1d() * 1e3, -rng.get_1d(), rng.get_1d() * 1e-3)
+                }
+            })
+            .collect();
+        Film::from_values(width, height, values)
+    }
+
+    fn round_trip(compression: ExrCompression) {
+        let film = test_film();
+        let mut exr = Vec::new();
+        write_exr(&mut exr, &film, compression).unwrap();
+        let (width, height, pixels) = read_exr(&exr);
+        assert_eq!((width, height), (film.width, film.height));
+
+        // 与 PFM 中保存的浮点数逐位相同，PFM 从底部一行开始
+        let mut pfm = Vec::new();
+        pfm::write_pfm(&mut pfm, &film).unwrap();
+        let header = format!("PF\n{} {}\n-1.0\n", width, height).len();
+        let mut floats = pfm[header..]
+            .chunks(4)
+            .map(|c| f32::from_le_bytes(c.try_into().unwrap()));
+        for y in (0..height).rev() {
+            for x in 0..width {
+                for actual in pixels[(y * width + x) as usize].iter() {
+                    let expected = floats.next().unwrap();
+                    assert_eq!(actual.to_bits(), expected.to_bits(), "pixel ({}, {})", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn uncompressed_round_trip() {
+        round_trip(ExrCompression::None);
+    }
+
+    #[test]
+    fn zip_round_trip() {
+        round_trip(ExrCompression::Zip);
+    }
+}
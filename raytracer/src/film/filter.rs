@@ -0,0 +1,200 @@
+use std::{f64::consts::PI, sync::Arc};
+
+// 像素重建滤波器：采样按权重累加到周围的像素中，最后除以权重之和
+// x、y 为采样点相对像素中心的偏移（单位为像素），超出半径时权重为 0
+// 以下滤波器都是可分离的，二维的权重为两个方向的乘积
+pub trait Filter: Send + Sync {
+    fn radius(&self) -> f64;
+    fn evaluate(&self, x: f64, y: f64) -> f64;
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum FilterKind {
+    Box,      // 半径为 0.5 时就是对像素内的采样求平均
+    Tent,     // 线性衰减
+    Gaussian, // 截断的高斯函数，标准差为半径的 1/3
+    Mitchell, // Mitchell-Netravali，B = C = 1/3
+    Lanczos,  // 加窗的 sinc
+}
+
+pub const FILTERS: [&str; 5] = ["box", "tent", "gaussian", "mitchell", "lanczos"];
+
+impl FilterKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "box" => Some(FilterKind::Box),
+            "tent" => Some(FilterKind::Tent),
+            "gaussian" => Some(FilterKind::Gaussian),
+            "mitchell" => Some(FilterKind::Mitchell),
+            "lanczos" => Some(FilterKind::Lanczos),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FilterKind::Box => "box",
+            FilterKind::Tent => "tent",
+            FilterKind::Gaussian => "gaussian",
+            FilterKind::Mitchell => "mitchell",
+            FilterKind::Lanczos => "lanczos",
+        }
+    }
+
+    pub fn default_radius(self) -> f64 {
+        match self {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell | FilterKind::Lanczos => 2.,
+        }
+    }
+
+    pub fn build(self, radius: f64) -> Arc<dyn Filter> {
+        match self {
+            FilterKind::Box => Arc::new(BoxFilter { radius }),
+            FilterKind::Tent => Arc::new(TentFilter { radius }),
+            FilterKind::Gaussian => Arc::new(GaussianFilter::new(radius)),
+            FilterKind::Mitchell => Arc::new(MitchellFilter {
+                radius,
+                b: 1. / 3.,
+                c: 1. / 3.,
+            }),
+            FilterKind::Lanczos => Arc::new(LanczosFilter { radius }),
+        }
+    }
+}
+
+pub struct BoxFilter {
+    pub radius: f64,
+}
+
+impl Filter for BoxFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        // 恰好落在边界上的采样只属于一个像素
+        if x.abs() < self.radius && y.abs() < self.radius {
+            1.
+        } else {
+            0.
+        }
+    }
+}
+
+pub struct TentFilter {
+    pub radius: f64,
+}
+
+impl Filter for TentFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        let tent = |t: f64| (self.radius - t.abs()).max(0.);
+        tent(x) * tent(y)
+    }
+}
+
+pub struct GaussianFilter {
+    pub radius: f64,
+    sigma: f64,
+    edge: f64, // 半径处的值，减去它使权重在边界上连续地降为 0
+}
+
+impl GaussianFilter {
+    pub fn new(radius: f64) -> Self {
+        let sigma = radius / 3.;
+        Self {
+            radius,
+            sigma,
+            edge: gaussian(radius, sigma),
+        }
+    }
+}
+
+fn gaussian(x: f64, sigma: f64) -> f64 {
+    (-x * x / (2. * sigma * sigma)).exp()
+}
+
+impl Filter for GaussianFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        let g = |t: f64| (gaussian(t, self.sigma) - self.edge).max(0.);
+        g(x) * g(y)
+    }
+}
+
+pub struct MitchellFilter {
+    pub radius: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl MitchellFilter {
+    // 原始的定义在 [-2, 2] 上，x 已经缩放到这个区间
+    fn mitchell_1d(&self, x: f64) -> f64 {
+        let (b, c) = (self.b, self.c);
+        let x = x.abs();
+        if x < 1. {
+            ((12. - 9. * b - 6. * c) * x * x * x
+                + (-18. + 12. * b + 6. * c) * x * x
+                + (6. - 2. * b))
+                / 6.
+        } else if x < 2. {
+            ((-b - 6. * c) * x * x * x
+                + (6. * b + 30. * c) * x * x
+                + (-12. * b - 48. * c) * x
+                + (8. * b + 24. * c))
+                / 6.
+        } else {
+            0.
+        }
+    }
+}
+
+impl Filter for MitchellFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        let scale = 2. / self.radius;
+        self.mitchell_1d(x * scale) * self.mitchell_1d(y * scale)
+    }
+}
+
+pub struct LanczosFilter {
+    pub radius: f64,
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        1.
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+impl Filter for LanczosFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        let lanczos = |t: f64| {
+            if t.abs() >= self.radius {
+                0.
+            } else {
+                sinc(t) * sinc(t / self.radius)
+            }
+        };
+        lanczos(x) * lanczos(y)
+    }
+}
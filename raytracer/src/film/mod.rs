@@ -0,0 +1,268 @@
+pub mod checkpoint;
+pub mod denoise;
+pub mod exr;
+pub mod filter;
+pub mod output;
+pub mod pfm;
+pub mod tonemap;
+
+use std::f64::INFINITY;
+
+use image::{ImageBuffer, Rgb, RgbImage};
+
+use crate::{
+    basic_component::vec3::Vec3,
+    film::{filter::Filter, tonemap::ToneMap},
+    render::scheduler::Tile,
+};
+
+// 浮点数的帧缓冲，按行存储，y = 0 为图像底部
+// 采样经过重建滤波器加权后累加到周围的像素中，像素的颜色为加权平均
+// 另外记录每个像素自己发出的采样的次数和亮度，用来估计方差
+// 从光源出发的路径直接加到 splat 中，它们来自所有像素的采样，按平均每个像素的采样次数归一化
+pub struct Film {
+    pub width: u32,
+    pub height: u32,
+    pub sum: Vec<Vec3>,     // 加权的颜色之和
+    pub weight: Vec<f64>,   // 权重之和
+    pub sum_lum: Vec<f64>,  // 每次采样亮度之和
+    pub sum_sq: Vec<f64>,   // 每次采样亮度的平方和
+    pub samples: Vec<u32>,  // 发出的采样次数，也是下一次采样的序号
+    pub accepted: Vec<u32>, // 其中亮度有限、计入 sum_lum 和 sum_sq 的次数
+    pub splat: Vec<Vec3>,
+    pub total_samples: u64, // 所有像素的采样次数之和
+}
+
+impl Film {
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = (width * height) as usize;
+        Self {
+            width,
+            height,
+            sum: vec![Vec3::new(0., 0., 0.); size],
+            weight: vec![0.; size],
+            sum_lum: vec![0.; size],
+            sum_sq: vec![0.; size],
+            samples: vec![0; size],
+            accepted: vec![0; size],
+            splat: vec![Vec3::new(0., 0., 0.); size],
+            total_samples: 0,
+        }
+    }
+
+    // 每个像素只有一个给定的值，用于把 AOV 等数据交给图像输出
+    pub fn from_values(width: u32, height: u32, values: Vec<Vec3>) -> Self {
+        let size = values.len();
+        Self {
+            width,
+            height,
+            sum: values,
+            weight: vec![1.; size],
+            sum_lum: vec![0.; size],
+            sum_sq: vec![0.; size],
+            samples: vec![1; size],
+            accepted: vec![1; size],
+            splat: vec![Vec3::new(0., 0., 0.); size],
+            total_samples: size as u64,
+        }
+    }
+
+    pub fn index(&self, x: u32, y: u32) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    // 像素 id 又发出了 samples 次采样，其中 accepted 次的亮度有限
+    // luminance、luminance_sq 为这 accepted 次采样的亮度之和、平方和
+    pub fn add_stats(
+        &mut self,
+        id: usize,
+        luminance: f64,
+        luminance_sq: f64,
+        samples: u32,
+        accepted: u32,
+    ) {
+        self.sum_lum[id] += luminance;
+        self.sum_sq[id] += luminance_sq;
+        self.samples[id] += samples;
+        self.accepted[id] += accepted;
+        self.total_samples += samples as u64;
+    }
+
+    // (px, py) 为整张图像中的连续坐标，落在图像外的贡献丢弃
+    pub fn add_splat(&mut self, px: f64, py: f64, color: Vec3) {
+        if px < 0. || py < 0. || px >= self.width as f64 || py >= self.height as f64 {
+            return;
+        }
+        let id = self.index(px as u32, py as u32);
+        self.splat[id] += color;
+    }
+
+    // 把一块的局部缓冲加到对应的位置，超出图像的部分丢弃
+    pub fn merge_tile(&mut self, tile: &FilmTile) {
+        for ly in 0..tile.height {
+            let y = tile.y0 + ly as i64;
+            if y < 0 || y >= self.height as i64 {
+                continue;
+            }
+            for lx in 0..tile.width {
+                let x = tile.x0 + lx as i64;
+                if x < 0 || x >= self.width as i64 {
+                    continue;
+                }
+                let id = self.index(x as u32, y as u32);
+                let local = (ly * tile.width + lx) as usize;
+                self.sum[id] += tile.sum[local];
+                self.weight[id] += tile.weight[local];
+            }
+        }
+    }
+
+    // 像素颜色的加权平均再加上 splat，没有采样的像素为黑色
+    pub fn average(&self, x: u32, y: u32) -> Vec3 {
+        let id = self.index(x, y);
+        // 带负值的滤波器在图像边缘可能使权重之和接近 0
+        let color = if self.weight[id].abs() < 1e-8 {
+            Vec3::new(0., 0., 0.)
+        } else {
+            self.sum[id] / self.weight[id]
+        };
+        if self.total_samples == 0 {
+            return color;
+        }
+        let pixel_count = self.splat.len() as f64;
+        color + self.splat[id] * (pixel_count / self.total_samples as f64)
+    }
+
+    // 线性的辐射度，用于 HDR 输出；NaN 与无穷大记为 0
+    pub fn linear(&self, x: u32, y: u32) -> [f32; 3] {
+        let c = self.average(x, y);
+        let clean = |v: f64| if v.is_finite() { v as f32 } else { 0. };
+        [clean(c.x), clean(c.y), clean(c.z)]
+    }
+
+    // 像素平均值的相对误差：标准误差除以亮度的平均值
+    // 采样少于两次时无法估计，返回无穷大
+    pub fn relative_error(&self, id: usize) -> f64 {
+        let n = self.accepted[id] as f64;
+        if n < 2. {
+            return INFINITY;
+        }
+        let mean = self.sum_lum[id] / n;
+        let variance = ((self.sum_sq[id] - n * mean * mean) / (n - 1.)).max(0.);
+        // 加上一个小量，纯黑的像素方差为 0，也能判定为收敛
+        (variance / n).sqrt() / (mean.abs() + 1e-3)
+    }
+
+    pub fn min_samples(&self) -> u32 {
+        self.samples.iter().copied().min().unwrap_or(0)
+    }
+
+    // 经过色调映射，转成 8 位的图像，图像的第一行是画面的顶部
+    pub fn to_rgb_image(&self, tone_map: &ToneMap) -> RgbImage {
+        let mut img: RgbImage = ImageBuffer::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let c = tone_map.apply(self.average(x, y));
+                let to_u8 = |v: f64| (v * 255.).round() as u8;
+                *img.get_pixel_mut(x, self.height - y - 1) =
+                    Rgb([to_u8(c[0]), to_u8(c[1]), to_u8(c[2])]);
+            }
+        }
+        img
+    }
+
+    // 16 位的图像，同样经过色调映射
+    pub fn to_rgb16_image(&self, tone_map: &ToneMap) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+        let mut img = ImageBuffer::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let c = tone_map.apply(self.average(x, y));
+                let to_u16 = |v: f64| (v * 65535.).round() as u16;
+                *img.get_pixel_mut(x, self.height - y - 1) =
+                    Rgb([to_u16(c[0]), to_u16(c[1]), to_u16(c[2])]);
+            }
+        }
+        img
+    }
+
+    // 采样次数的热力图，从黑色（0 次）经过蓝、红、黄到白色（max_samples 次）
+    pub fn to_heatmap(&self, max_samples: u32) -> RgbImage {
+        let mut img: RgbImage = ImageBuffer::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let t = self.samples[self.index(x, y)] as f64 / max_samples.max(1) as f64;
+                let heat = heat_color(t);
+                let mut color = [0u8; 3];
+                for (c, v) in color.iter_mut().enumerate() {
+                    *v = (heat[c] * 255.).round() as u8;
+                }
+                *img.get_pixel_mut(x, self.height - y - 1) = Rgb(color);
+            }
+        }
+        img
+    }
+}
+
+// 热力图的颜色，t 从 0 到 1，超出范围的截断
+pub fn heat_color(t: f64) -> Vec3 {
+    const STOPS: [[f64; 3]; 5] = [
+        [0., 0., 0.],
+        [0., 0., 1.],
+        [1., 0., 0.],
+        [1., 1., 0.],
+        [1., 1., 1.],
+    ];
+    let t = t.max(0.).min(1.) * (STOPS.len() - 1) as f64;
+    let i = (t.floor() as usize).min(STOPS.len() - 2);
+    let f = t - i as f64;
+    let at = |c: usize| STOPS[i][c] * (1. - f) + STOPS[i + 1][c] * f;
+    Vec3::new(at(0), at(1), at(2))
+}
+
+// 一块图像的局部缓冲，每个线程先累加到这里，再由主线程合并到 Film 中
+// 范围比图像块向外扩出滤波器的半径，包含块内的采样会影响到的所有像素
+pub struct FilmTile {
+    x0: i64,
+    y0: i64,
+    width: u32,
+    height: u32,
+    sum: Vec<Vec3>,
+    weight: Vec<f64>,
+}
+
+impl FilmTile {
+    pub fn new(tile: Tile, radius: f64) -> Self {
+        let margin = (radius + 0.5).ceil() as u32;
+        let width = tile.x1 - tile.x0 + 2 * margin;
+        let height = tile.y1 - tile.y0 + 2 * margin;
+        Self {
+            x0: tile.x0 as i64 - margin as i64,
+            y0: tile.y0 as i64 - margin as i64,
+            width,
+            height,
+            sum: vec![Vec3::new(0., 0., 0.); (width * height) as usize],
+            weight: vec![0.; (width * height) as usize],
+        }
+    }
+
+    // (px, py) 为采样点在整张图像中的连续坐标，像素 (x, y) 的中心为 (x + 0.5, y + 0.5)
+    pub fn add_sample(&mut self, px: f64, py: f64, color: Vec3, filter: &dyn Filter) {
+        let r = filter.radius();
+        // 只有中心与采样点的距离小于半径的像素才会受到影响
+        let x_min = ((px - 0.5 - r).ceil() as i64).max(self.x0);
+        let x_max = ((px - 0.5 + r).floor() as i64).min(self.x0 + self.width as i64 - 1);
+        let y_min = ((py - 0.5 - r).ceil() as i64).max(self.y0);
+        let y_max = ((py - 0.5 + r).floor() as i64).min(self.y0 + self.height as i64 - 1);
+        for y in y_min..=y_max {
+            for x in x_min..=x_max {
+                let w = filter.evaluate(px - x as f64 - 0.5, py - y as f64 - 0.5);
+                if w == 0. {
+                    continue;
+                }
+                let local = ((y - self.y0) * self.width as i64 + (x - self.x0)) as usize;
+                self.sum[local] += color * w;
+                self.weight[local] += w;
+            }
+        }
+    }
+}
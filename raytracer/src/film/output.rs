@@ -0,0 +1,199 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use image::{codecs::hdr::HdrEncoder, DynamicImage, ImageOutputFormat, Rgb};
+
+use crate::film::{
+    exr::{self, ExrCompression},
+    pfm,
+    tonemap::ToneMap,
+    Film,
+};
+
+// 支持的输出格式，由文件的扩展名决定
+pub const EXTENSIONS: [&str; 9] = [
+    "png", "ppm", "bmp", "tga", "jpg", "jpeg", "hdr", "pfm", "exr",
+];
+
+pub struct OutputOptions {
+    pub quality: u8,     // JPEG 质量
+    pub bit_depth: u8,   // PNG、PPM 每个通道的位数，8 或 16
+    pub ppm_ascii: bool, // PPM 用文本而不是二进制保存
+    pub exr_compression: ExrCompression,
+    pub tone_map: ToneMap, // 只用于 8 位、16 位的格式
+}
+
+// 在渲染之前检查输出的设置，避免渲染完才发现无法保存
+pub fn check_options(path: &str, options: &OutputOptions) -> Result<(), String> {
+    let ext = extension(path).unwrap_or_default();
+    if options.bit_depth == 16 && ext != "png" && ext != "ppm" {
+        return Err(format!(
+            "16-bit output is only supported for .png and .ppm, not `{}`",
+            path
+        ));
+    }
+    Ok(())
+}
+
+// 输出的文件必须是 extensions 中的格式，并且所在的目录已经存在
+pub fn check_path(path: &str, extensions: &[&str]) -> Result<(), String> {
+    match extension(path) {
+        Some(e) if extensions.contains(&e.as_str()) => {}
+        _ => {
+            return Err(format!(
+                "`{}` must end with one of .{}",
+                path,
+                extensions.join(", .")
+            ))
+        }
+    }
+    let dir = match Path::new(path).parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    if !dir.is_dir() {
+        return Err(format!(
+            "cannot write `{}`: directory `{}` does not exist",
+            path,
+            dir.display()
+        ));
+    }
+    Ok(())
+}
+
+pub fn extension(path: &str) -> Option<String> {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+}
+
+// AOV 与主图像放在同一目录下，例如 output/output.png 的法线为 output/output.normal.exr
+// 主图像是 .pfm 或 .exr 时沿用它的格式，否则使用 .exr，保证保存的是原始的浮点数
+pub fn aov_path(path: &str, aov: &str) -> String {
+    let ext = match extension(path).as_deref() {
+        Some("pfm") => "pfm",
+        _ => "exr",
+    };
+    Path::new(path)
+        .with_extension(format!("{}.{}", aov, ext))
+        .to_string_lossy()
+        .into_owned()
+}
+
+// 降噪后的图像，例如 output/output.png 对应 output/output.denoised.png
+pub fn denoised_path(path: &str) -> String {
+    let ext = extension(path).unwrap_or_default();
+    Path::new(path)
+        .with_extension(format!("denoised.{}", ext))
+        .to_string_lossy()
+        .into_owned()
+}
+
+pub fn save_image(film: &Film, path: &str, options: &OutputOptions) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("cannot create `{}`: {}", path, e))?;
+    let mut w = BufWriter::new(file);
+    let result = match extension(path).as_deref() {
+        // 以下格式经过色调映射和 sRGB 编码
+        Some("png") => write_ldr(
+            &mut w,
+            film,
+            ImageOutputFormat::Png,
+            options.bit_depth,
+            &options.tone_map,
+        ),
+        Some("ppm") => write_ppm(&mut w, film, options).map_err(|e| e.to_string()),
+        Some("bmp") => write_ldr(&mut w, film, ImageOutputFormat::Bmp, 8, &options.tone_map),
+        Some("tga") => write_ldr(&mut w, film, ImageOutputFormat::Tga, 8, &options.tone_map),
+        Some("jpg") | Some("jpeg") => write_ldr(
+            &mut w,
+            film,
+            ImageOutputFormat::Jpeg(options.quality),
+            8,
+            &options.tone_map,
+        ),
+        // 以下格式保存线性的浮点数，不做色调映射，也不截断大于 1 的部分
+        Some("hdr") => write_hdr(&mut w, film),
+        Some("pfm") => pfm::write_pfm(&mut w, film).map_err(|e| e.to_string()),
+        Some("exr") => {
+            exr::write_exr(&mut w, film, options.exr_compression).map_err(|e| e.to_string())
+        }
+        _ => Err("unsupported image format".to_string()),
+    };
+    result
+        .and_then(|_| w.flush().map_err(|e| e.to_string()))
+        .map_err(|e| format!("Outputting image `{}` fails: {}", path, e))
+}
+
+// 每个像素采样次数的热力图，max_samples 对应白色
+pub fn save_heatmap(film: &Film, path: &str, max_samples: u32) -> Result<(), String> {
+    film.to_heatmap(max_samples)
+        .save(path)
+        .map_err(|e| format!("Outputting heatmap `{}` fails: {}", path, e))
+}
+
+fn write_ldr<W: Write>(
+    w: &mut W,
+    film: &Film,
+    format: ImageOutputFormat,
+    bit_depth: u8,
+    tone_map: &ToneMap,
+) -> Result<(), String> {
+    let img = if bit_depth == 16 {
+        DynamicImage::ImageRgb16(film.to_rgb16_image(tone_map))
+    } else {
+        DynamicImage::ImageRgb8(film.to_rgb_image(tone_map))
+    };
+    img.write_to(w, format).map_err(|e| e.to_string())
+}
+
+// PPM：P6 为二进制，P3 为文本，16 位时每个值按大端序占两个字节
+fn write_ppm<W: Write>(w: &mut W, film: &Film, options: &OutputOptions) -> io::Result<()> {
+    let magic = if options.ppm_ascii { "P3" } else { "P6" };
+    let max_value = if options.bit_depth == 16 { 65535 } else { 255 };
+    write!(
+        w,
+        "{}\n{} {}\n{}\n",
+        magic, film.width, film.height, max_value
+    )?;
+
+    let values: Vec<u16> = if options.bit_depth == 16 {
+        film.to_rgb16_image(&options.tone_map).into_raw()
+    } else {
+        film.to_rgb_image(&options.tone_map)
+            .into_raw()
+            .into_iter()
+            .map(u16::from)
+            .collect()
+    };
+    if options.ppm_ascii {
+        // 每行一个像素
+        for pixel in values.chunks(3) {
+            writeln!(w, "{} {} {}", pixel[0], pixel[1], pixel[2])?;
+        }
+    } else if options.bit_depth == 16 {
+        for v in values {
+            w.write_all(&v.to_be_bytes())?;
+        }
+    } else {
+        let bytes: Vec<u8> = values.into_iter().map(|v| v as u8).collect();
+        w.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+// Radiance RGBE
+fn write_hdr<W: Write>(w: &mut W, film: &Film) -> Result<(), String> {
+    let mut data = Vec::with_capacity((film.width * film.height) as usize);
+    for y in (0..film.height).rev() {
+        for x in 0..film.width {
+            data.push(Rgb(film.linear(x, y)));
+        }
+    }
+    HdrEncoder::new(w)
+        .encode(&data, film.width as usize, film.height as usize)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,17 @@
+use std::io::{self, Write};
+
+use crate::film::Film;
+
+// Portable Float Map：文本头 + 32 位浮点数的 RGB
+// 比例因子为负数表示小端序；数据从图像的底部一行开始，与 Film 的存储顺序相同
+pub fn write_pfm<W: Write>(w: &mut W, film: &Film) -> io::Result<()> {
+    write!(w, "PF\n{} {}\n-1.0\n", film.width, film.height)?;
+    for y in 0..film.height {
+        for x in 0..film.width {
+            for c in film.linear(x, y).iter() {
+                w.write_all(&c.to_le_bytes())?;
+            }
+        }
+    }
+    w.flush()
+}
@@ -0,0 +1,113 @@
+use crate::basic_component::vec3::Vec3;
+
+// 把线性的辐射度压缩到 [0, 1]，再经过 sRGB 的传递函数转成显示用的值
+// 只用于 8 位、16 位的图像，HDR 格式保存的仍然是原始的辐射度
+#[derive(Copy, Clone, PartialEq)]
+pub enum ToneMapOperator {
+    Clamp,            // 直接截断
+    Reinhard,         // x / (1 + x)
+    ReinhardExtended, // 亮度达到白点时为 1
+    Aces,             // ACES filmic 曲线的拟合
+    Hable,            // Uncharted 2 的 filmic 曲线
+}
+
+pub const OPERATORS: [&str; 5] = ["clamp", "reinhard", "reinhard-extended", "aces", "hable"];
+
+impl ToneMapOperator {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clamp" => Some(ToneMapOperator::Clamp),
+            "reinhard" => Some(ToneMapOperator::Reinhard),
+            "reinhard-extended" => Some(ToneMapOperator::ReinhardExtended),
+            "aces" => Some(ToneMapOperator::Aces),
+            "hable" => Some(ToneMapOperator::Hable),
+            _ => None,
+        }
+    }
+
+    // 没有指定白点时使用的值，只对 reinhard-extended 和 hable 有意义
+    pub fn default_white_point(self) -> f64 {
+        match self {
+            ToneMapOperator::Hable => 11.2,
+            _ => 4.,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct ToneMap {
+    pub operator: ToneMapOperator,
+    pub exposure: f64,     // 曝光补偿（EV），颜色乘以 2^exposure
+    pub white_point: f64,  // 映射为纯白的辐射度
+    pub encode_srgb: bool, // 为 false 时不做 sRGB 编码，颜色本身就是要显示的值
+}
+
+impl ToneMap {
+    // 不做色调映射和 sRGB 编码，截断到 [0, 1] 后直接写入，用于调试视图
+    pub fn identity() -> Self {
+        Self {
+            operator: ToneMapOperator::Clamp,
+            exposure: 0.,
+            white_point: 1.,
+            encode_srgb: false,
+        }
+    }
+
+    // 线性颜色 -> sRGB 编码的 [0, 1]
+    pub fn apply(&self, color: Vec3) -> [f64; 3] {
+        let scale = 2f64.powf(self.exposure);
+        let map = |v: f64| {
+            // NaN 与负数记为 0，无穷大按白色处理
+            let v = if v.is_nan() { 0. } else { (v * scale).max(0.) };
+            let mapped = if v.is_infinite() { 1. } else { self.curve(v) };
+            if self.encode_srgb {
+                srgb_oetf(mapped.min(1.))
+            } else {
+                mapped.min(1.)
+            }
+        };
+        [map(color.x), map(color.y), map(color.z)]
+    }
+
+    // 对单个通道应用色调映射曲线，结果为线性值
+    fn curve(&self, x: f64) -> f64 {
+        let w = self.white_point;
+        match self.operator {
+            ToneMapOperator::Clamp => x,
+            ToneMapOperator::Reinhard => x / (1. + x),
+            ToneMapOperator::ReinhardExtended => x * (1. + x / (w * w)) / (1. + x),
+            ToneMapOperator::Aces => {
+                // Krzysztof Narkowicz 的拟合
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                x * (a * x + b) / (x * (c * x + d) + e)
+            }
+            ToneMapOperator::Hable => {
+                // 原文中先乘以 2 的曝光偏移
+                hable(2. * x) / hable(w)
+            }
+        }
+    }
+}
+
+fn hable(x: f64) -> f64 {
+    let (a, b, c, d, e, f) = (0.15, 0.5, 0.1, 0.2, 0.02, 0.3);
+    (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f
+}
+
+// sRGB 标准中的传递函数（OETF），暗部为线性段
+pub fn srgb_oetf(x: f64) -> f64 {
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1. / 2.4) - 0.055
+    }
+}
+
+// srgb_oetf 的逆，把 8 位图像中的值还原为线性的亮度
+pub fn srgb_eotf(x: f64) -> f64 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -2,12 +2,15 @@ use std::f64::consts::E;
 use std::f64::INFINITY;
 
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
+    basic_component::{
+        ray::Ray,
+        sampler::{self, Pcg32, Sampler},
+        vec3::Vec3,
+    },
     hittable::{HitRecord, Hittable},
-    material::{isotropic::Isotropic, Material},
+    material::{isotropic::Isotropic, phase::PhaseFunction, volume::Volume, Material},
     optimization::aabb::AABB,
     texture::{solid::SolidColor, Texture},
-    utility::random_double,
 };
 
 pub struct ConstantMedium<TA, TB>
@@ -21,43 +24,29 @@ where
 }
 
 impl<TA: Hittable, TB: Material> Hittable for ConstantMedium<TA, TB> {
+    // 没有采样器时（AOV、调试视图等）用光线本身生成随机数，同一条光线的结果总是相同的
     fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let mut rec1;
-        let mut rec2;
-        if let Some(rec) = self.boundary.hit(r, -INFINITY, INFINITY) {
-            rec1 = rec;
-        } else {
-            return None;
-        }
-        if let Some(rec) = self.boundary.hit(r, rec1.t + 0.0001, INFINITY) {
-            rec2 = rec;
-        } else {
-            return None;
-        }
-
-        if rec1.t < t_min {
-            rec1.t = t_min;
-        }
-        if rec2.t > t_max {
-            rec2.t = t_max;
-        }
-        if rec1.t >= rec2.t {
-            return None;
-        }
+        self.hit_sampled(r, t_min, t_max, &mut Pcg32::new(ray_hash(r)))
+    }
 
-        if rec1.t < 0. {
-            rec1.t = 0.;
-        }
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        let (t0, t1) = medium_interval(&self.boundary, r, t_min, t_max)?;
 
         let ray_length = r.dir.length();
-        let dis_inside_boundary = (rec2.t - rec1.t) * ray_length;
-        let hit_dis = self.neg_inv_density * random_double(0., 1.).log(E);
+        let dis_inside_boundary = (t1 - t0) * ray_length;
+        let hit_dis = self.neg_inv_density * (1. - sampler.get_1d()).log(E);
 
         if hit_dis > dis_inside_boundary {
             return None;
         }
 
-        let _t = rec1.t + hit_dis / ray_length;
+        let _t = t0 + hit_dis / ray_length;
         let _p = r.at(_t);
 
         Some(HitRecord {
@@ -75,6 +64,18 @@ impl<TA: Hittable, TB: Material> Hittable for ConstantMedium<TA, TB> {
     fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
         self.boundary.bounding_box(_time0, _time1)
     }
+
+    fn hit_surface(&self, _r: Ray, _t_min: f64, _t_max: f64) -> Option<HitRecord> {
+        None
+    }
+
+    // 均匀介质的透射率有解析解 exp(-密度 * 距离)
+    fn transmittance(&self, r: Ray, t_min: f64, t_max: f64, _sampler: &mut dyn Sampler) -> f64 {
+        match medium_interval(&self.boundary, r, t_min, t_max) {
+            Some((t0, t1)) => ((t1 - t0) * r.dir.length() / self.neg_inv_density).exp(),
+            None => 1.,
+        }
+    }
 }
 
 impl<TA: Hittable, TC: Texture> ConstantMedium<TA, Isotropic<TC>> {
@@ -88,6 +89,17 @@ impl<TA: Hittable, TC: Texture> ConstantMedium<TA, Isotropic<TC>> {
     }
 }
 
+impl<TA: Hittable, TC: Texture, P: PhaseFunction> ConstantMedium<TA, Volume<TC, P>> {
+    // 指定相函数的构造函数
+    pub fn new_with_phase(b: TA, d: f64, txt: TC, phase: P) -> Self {
+        Self {
+            boundary: b,
+            neg_inv_density: (-1. / d),
+            phase_function: Volume::new(txt, phase),
+        }
+    }
+}
+
 impl<TA: Hittable> ConstantMedium<TA, Isotropic<SolidColor>> {
     // 指定为Isotropic，纹理为 SolidColor的构造函数
     pub fn new_from_color(b: TA, d: f64, c: Vec3) -> Self {
@@ -98,3 +110,28 @@ impl<TA: Hittable> ConstantMedium<TA, Isotropic<SolidColor>> {
         }
     }
 }
+
+// 光线在 [t_min, t_max] 内位于边界里面的一段，边界需要是凸的
+pub fn medium_interval<T: Hittable>(
+    boundary: &T,
+    r: Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64)> {
+    let rec1 = boundary.hit(r, -INFINITY, INFINITY)?;
+    let rec2 = boundary.hit(r, rec1.t + 0.0001, INFINITY)?;
+
+    let t0 = rec1.t.max(t_min).max(0.);
+    let t1 = rec2.t.min(t_max);
+    if t0 >= t1 {
+        return None;
+    }
+    Some((t0, t1))
+}
+
+pub fn ray_hash(r: Ray) -> u64 {
+    let values = [
+        r.orig.x, r.orig.y, r.orig.z, r.dir.x, r.dir.y, r.dir.z, r.tm,
+    ];
+    values.iter().fold(0, |h, x| sampler::mix(h ^ x.to_bits()))
+}
@@ -1,6 +1,6 @@
 use crate::{
-    basic_component::ray::Ray,
-    hittable::{HitRecord, Hittable},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
+    hittable::{HitRecord, Hittable, TraversalCost},
     optimization::aabb::AABB,
 };
 
@@ -25,6 +25,48 @@ impl<T: Hittable> Hittable for Flipface<T> {
             None
         }
     }
+
+    fn hit_surface(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut rec = self.ptr.hit_surface(r, t_min, t_max)?;
+        rec.front_face = !rec.front_face;
+        Some(rec)
+    }
+
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        let mut rec = self.ptr.hit_sampled(r, t_min, t_max, sampler)?;
+        rec.front_face = !rec.front_face;
+        Some(rec)
+    }
+
+    fn transmittance(&self, r: Ray, t_min: f64, t_max: f64, sampler: &mut dyn Sampler) -> f64 {
+        self.ptr.transmittance(r, t_min, t_max, sampler)
+    }
+
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        let mut rec = self.ptr.hit_counted(r, t_min, t_max, cost)?;
+        rec.front_face = !rec.front_face;
+        Some(rec)
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        self.ptr.sample_surface(sampler)
+    }
+
+    fn surface_pdf(&self, p: Vec3, normal: Vec3) -> f64 {
+        self.ptr.surface_pdf(p, normal)
+    }
 }
 
 impl<T: Hittable> Flipface<T> {
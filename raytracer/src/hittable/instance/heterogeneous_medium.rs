@@ -0,0 +1,138 @@
+use crate::{
+    basic_component::{
+        ray::Ray,
+        sampler::{Pcg32, Sampler},
+        vec3::Vec3,
+    },
+    hittable::{
+        instance::constant_medium::{medium_interval, ray_hash},
+        HitRecord, Hittable,
+    },
+    material::{isotropic::Isotropic, phase::PhaseFunction, volume::Volume, Material},
+    optimization::aabb::AABB,
+    texture::Texture,
+};
+
+// 非均匀介质：密度随位置变化，比如烟、云、雾
+// 密度场可以是任意纹理（Perlin 湍流、体素网格等），在 p 处取值的亮度截断到 [0, 1]，再乘以 max_density
+// max_density 同时作为整个介质的密度上界（majorant），先按上界采样自由程，再按真实密度接受或拒绝
+pub struct HeterogeneousMedium<TA, TB, TD>
+where
+    TA: Hittable,
+    TB: Material,
+    TD: Texture,
+{
+    pub boundary: TA,
+    pub phase_function: TB,
+    pub density: TD,
+    pub max_density: f64,
+}
+
+impl<TA: Hittable, TB: Material, TD: Texture> Hittable for HeterogeneousMedium<TA, TB, TD> {
+    // 与 ConstantMedium 相同，没有采样器时用光线本身生成随机数
+    fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.hit_sampled(r, t_min, t_max, &mut Pcg32::new(ray_hash(r)))
+    }
+
+    // delta tracking：按上界密度采样碰撞点，以 真实密度 / 上界 的概率发生真实的散射，
+    // 否则是虚拟的碰撞，继续向前走
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        let (t0, t1) = medium_interval(&self.boundary, r, t_min, t_max)?;
+        let ray_length = r.dir.length();
+
+        let mut t = t0;
+        loop {
+            t -= (1. - sampler.get_1d()).ln() / (self.max_density * ray_length);
+            if t >= t1 {
+                return None;
+            }
+            let p = r.at(t);
+            if sampler.get_1d() * self.max_density < self.density_at(p) {
+                return Some(HitRecord {
+                    t,
+                    p,
+                    normal: Vec3::new(1., 0., 0.),
+                    front_face: true,
+                    mat: &self.phase_function,
+                    u: 0.,
+                    v: 0.,
+                });
+            }
+        }
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        self.boundary.bounding_box(time0, time1)
+    }
+
+    fn hit_surface(&self, _r: Ray, _t_min: f64, _t_max: f64) -> Option<HitRecord> {
+        None
+    }
+
+    // ratio tracking：同样按上界密度走过虚拟的碰撞，但不做选择，每一步都乘上 1 - 真实密度 / 上界
+    // 结果是透射率的无偏估计，比 delta tracking 只给出 0 或 1 的噪声小得多
+    fn transmittance(&self, r: Ray, t_min: f64, t_max: f64, sampler: &mut dyn Sampler) -> f64 {
+        let (t0, t1) = match medium_interval(&self.boundary, r, t_min, t_max) {
+            Some(interval) => interval,
+            None => return 1.,
+        };
+        let ray_length = r.dir.length();
+
+        let mut tr = 1.;
+        let mut t = t0;
+        loop {
+            t -= (1. - sampler.get_1d()).ln() / (self.max_density * ray_length);
+            if t >= t1 {
+                return tr;
+            }
+            tr *= 1. - self.density_at(r.at(t)) / self.max_density;
+            // 透射率很小时用俄罗斯轮盘赌提前结束
+            if tr < 0.1 {
+                if sampler.get_1d() >= 0.5 {
+                    return 0.;
+                }
+                tr *= 2.;
+            }
+        }
+    }
+}
+
+impl<TA: Hittable, TB: Material, TD: Texture> HeterogeneousMedium<TA, TB, TD> {
+    // p 处的真实密度，不超过 max_density
+    pub fn density_at(&self, p: Vec3) -> f64 {
+        let d = self.density.get_color_value(0., 0., p).luminance();
+        d.clamp(0., 1.) * self.max_density
+    }
+}
+
+impl<TA: Hittable, TC: Texture, TD: Texture> HeterogeneousMedium<TA, Isotropic<TC>, TD> {
+    // 指定为Isotropic的构造函数
+    pub fn new(b: TA, max_density: f64, density: TD, txt: TC) -> Self {
+        Self {
+            boundary: b,
+            phase_function: Isotropic::new(txt),
+            density,
+            max_density,
+        }
+    }
+}
+
+impl<TA: Hittable, TC: Texture, P: PhaseFunction, TD: Texture>
+    HeterogeneousMedium<TA, Volume<TC, P>, TD>
+{
+    // 指定相函数的构造函数
+    pub fn new_with_phase(b: TA, max_density: f64, density: TD, txt: TC, phase: P) -> Self {
+        Self {
+            boundary: b,
+            phase_function: Volume::new(txt, phase),
+            density,
+            max_density,
+        }
+    }
+}
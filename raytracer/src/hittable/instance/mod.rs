@@ -1,5 +1,6 @@
 pub mod constant_medium;
 pub mod flipface;
+pub mod heterogeneous_medium;
 pub mod rotate;
 pub mod translate;
 pub mod zoom;
@@ -1,8 +1,8 @@
 use std::f64::INFINITY;
 
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
-    hittable::{HitRecord, Hittable},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
+    hittable::{HitRecord, Hittable, TraversalCost},
     optimization::aabb::AABB,
     utility::{degree_to_radian, max_f64, min_f64},
 };
@@ -29,6 +29,88 @@ impl<T: Hittable> Hittable for RotateX<T> {
     }
 
     fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self.now_box.hit(rotated_ray, t_min, t_max)?;
+        Some(self.rotate_back(rec))
+    }
+
+    fn hit_surface(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self.now_box.hit_surface(rotated_ray, t_min, t_max)?;
+        Some(self.rotate_back(rec))
+    }
+
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self
+            .now_box
+            .hit_sampled(rotated_ray, t_min, t_max, sampler)?;
+        Some(self.rotate_back(rec))
+    }
+
+    fn transmittance(&self, r: Ray, t_min: f64, t_max: f64, sampler: &mut dyn Sampler) -> f64 {
+        self.now_box
+            .transmittance(self.rotate_ray(r), t_min, t_max, sampler)
+    }
+
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self.now_box.hit_counted(rotated_ray, t_min, t_max, cost)?;
+        Some(self.rotate_back(rec))
+    }
+
+    // 出发点和方向转到旋转之前的坐标系，旋转不改变立体角，pdf 不变
+    fn pdf_value(&self, o: Vec3, v: Vec3) -> f64 {
+        self.now_box.pdf_value(self.to_object(o), self.to_object(v))
+    }
+
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.to_world(self.now_box.random(self.to_object(o), sampler))
+    }
+
+    // 光源上的采样点和法线转到旋转后的坐标系，旋转不改变面积，pdf 不变
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        let (p, normal, pdf) = self.now_box.sample_surface(sampler)?;
+        Some((self.to_world(p), self.to_world(normal), pdf))
+    }
+
+    fn surface_pdf(&self, p: Vec3, normal: Vec3) -> f64 {
+        self.now_box
+            .surface_pdf(self.to_object(p), self.to_object(normal))
+    }
+}
+
+impl<T: Hittable> RotateX<T> {
+    // 旋转之前的坐标系中的点或方向转到旋转之后
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        let mut w = v;
+        w[1] = self.cos_theta * v[1] + self.sin_theta * v[2];
+        w[2] = -self.sin_theta * v[1] + self.cos_theta * v[2];
+        w
+    }
+
+    // to_world 的逆变换
+    fn to_object(&self, v: Vec3) -> Vec3 {
+        let mut o = v;
+        o[1] = self.cos_theta * v[1] - self.sin_theta * v[2];
+        o[2] = self.sin_theta * v[1] + self.cos_theta * v[2];
+        o
+    }
+
+    // 把光线转到物体旋转之前的坐标系
+    fn rotate_ray(&self, r: Ray) -> Ray {
         let mut _orig = r.orig;
         let mut _dir = r.dir;
 
@@ -38,32 +120,30 @@ impl<T: Hittable> Hittable for RotateX<T> {
         _dir[1] = self.cos_theta * r.dir[1] - self.sin_theta * r.dir[2];
         _dir[2] = self.sin_theta * r.dir[1] + self.cos_theta * r.dir[2];
 
-        let rotated_ray = Ray {
+        Ray {
             dir: _dir,
             orig: _orig,
             tm: r.tm,
-        };
-        if let Some(mut rec) = self.now_box.hit(rotated_ray, t_min, t_max) {
-            let mut _p = rec.p;
-            let mut _normal = rec.normal;
+            wavelength: r.wavelength,
+        }
+    }
 
-            _p[1] = self.cos_theta * rec.p[1] + self.sin_theta * rec.p[2];
-            _p[2] = -self.sin_theta * rec.p[1] + self.cos_theta * rec.p[2];
+    // 把碰撞的结果转回旋转之后的坐标系
+    fn rotate_back<'a>(&self, mut rec: HitRecord<'a>) -> HitRecord<'a> {
+        let mut _p = rec.p;
+        let mut _normal = rec.normal;
 
-            _normal[1] = self.cos_theta * rec.normal[1] + self.sin_theta * rec.normal[2];
-            _normal[2] = -self.sin_theta * rec.normal[1] + self.cos_theta * rec.normal[2];
+        _p[1] = self.cos_theta * rec.p[1] + self.sin_theta * rec.p[2];
+        _p[2] = -self.sin_theta * rec.p[1] + self.cos_theta * rec.p[2];
 
-            rec.p = _p;
-            rec.set_face_normal(rotated_ray, _normal);
+        _normal[1] = self.cos_theta * rec.normal[1] + self.sin_theta * rec.normal[2];
+        _normal[2] = -self.sin_theta * rec.normal[1] + self.cos_theta * rec.normal[2];
 
-            Some(rec)
-        } else {
-            None
-        }
+        rec.p = _p;
+        rec.normal = _normal; // 旋转不改变法线在光线哪一侧，front_face 沿用原来的
+        rec
     }
-}
 
-impl<T: Hittable> RotateX<T> {
     pub fn new(p: T, angle: f64) -> Self {
         let radians = degree_to_radian(angle); //旋转角
         let _sin = radians.sin();
@@ -79,7 +159,7 @@ impl<T: Hittable> RotateX<T> {
         }
 
         let mut min_v = Vec3::new(INFINITY, INFINITY, INFINITY);
-        let mut max_v = Vec3::new(INFINITY, INFINITY, INFINITY);
+        let mut max_v = Vec3::new(-INFINITY, -INFINITY, -INFINITY);
 
         for i in 0..2 {
             for j in 0..2 {
@@ -88,10 +168,10 @@ impl<T: Hittable> RotateX<T> {
                     let y = tp_box.maximum.y * j as f64 + tp_box.minimum.y * (1 - j) as f64;
                     let z = tp_box.maximum.z * k as f64 + tp_box.minimum.z * (1 - k) as f64;
                     // 旋转公式
-                    let newx = _cos * x + _sin * z;
-                    let newz = -_sin * x + _cos * z;
+                    let newy = _cos * y + _sin * z;
+                    let newz = -_sin * y + _cos * z;
 
-                    let tester = Vec3::new(newx, y, newz);
+                    let tester = Vec3::new(x, newy, newz);
                     for c in 0..3 {
                         min_v[c] = min_f64(min_v[c], tester[c]);
                         max_v[c] = max_f64(max_v[c], tester[c]);
@@ -135,6 +215,89 @@ impl<T: Hittable> Hittable for RotateY<T> {
     }
 
     fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self.now_box.hit(rotated_ray, t_min, t_max)?;
+        Some(self.rotate_back(rec))
+    }
+
+    fn hit_surface(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self.now_box.hit_surface(rotated_ray, t_min, t_max)?;
+        Some(self.rotate_back(rec))
+    }
+
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self
+            .now_box
+            .hit_sampled(rotated_ray, t_min, t_max, sampler)?;
+        Some(self.rotate_back(rec))
+    }
+
+    fn transmittance(&self, r: Ray, t_min: f64, t_max: f64, sampler: &mut dyn Sampler) -> f64 {
+        self.now_box
+            .transmittance(self.rotate_ray(r), t_min, t_max, sampler)
+    }
+
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self.now_box.hit_counted(rotated_ray, t_min, t_max, cost)?;
+        Some(self.rotate_back(rec))
+    }
+
+    // 补全 pdf
+    // 出发点和方向转到旋转之前的坐标系，旋转不改变立体角，pdf 不变
+    fn pdf_value(&self, o: Vec3, v: Vec3) -> f64 {
+        self.now_box.pdf_value(self.to_object(o), self.to_object(v))
+    }
+
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.to_world(self.now_box.random(self.to_object(o), sampler))
+    }
+
+    // 光源上的采样点和法线转到旋转后的坐标系，旋转不改变面积，pdf 不变
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        let (p, normal, pdf) = self.now_box.sample_surface(sampler)?;
+        Some((self.to_world(p), self.to_world(normal), pdf))
+    }
+
+    fn surface_pdf(&self, p: Vec3, normal: Vec3) -> f64 {
+        self.now_box
+            .surface_pdf(self.to_object(p), self.to_object(normal))
+    }
+}
+
+impl<T: Hittable> RotateY<T> {
+    // 旋转之前的坐标系中的点或方向转到旋转之后
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        let mut w = v;
+        w[0] = self.cos_theta * v[0] + self.sin_theta * v[2];
+        w[2] = -self.sin_theta * v[0] + self.cos_theta * v[2];
+        w
+    }
+
+    // to_world 的逆变换
+    fn to_object(&self, v: Vec3) -> Vec3 {
+        let mut o = v;
+        o[0] = self.cos_theta * v[0] - self.sin_theta * v[2];
+        o[2] = self.sin_theta * v[0] + self.cos_theta * v[2];
+        o
+    }
+
+    // 把光线转到物体旋转之前的坐标系
+    fn rotate_ray(&self, r: Ray) -> Ray {
         let mut _orig = r.orig;
         let mut _dir = r.dir;
 
@@ -144,41 +307,30 @@ impl<T: Hittable> Hittable for RotateY<T> {
         _dir[0] = self.cos_theta * r.dir[0] - self.sin_theta * r.dir[2];
         _dir[2] = self.sin_theta * r.dir[0] + self.cos_theta * r.dir[2];
 
-        let rotated_ray = Ray {
+        Ray {
             dir: _dir,
             orig: _orig,
             tm: r.tm,
-        };
-        if let Some(mut rec) = self.now_box.hit(rotated_ray, t_min, t_max) {
-            let mut _p = rec.p;
-            let mut _normal = rec.normal;
-
-            _p[0] = self.cos_theta * rec.p[0] + self.sin_theta * rec.p[2];
-            _p[2] = -self.sin_theta * rec.p[0] + self.cos_theta * rec.p[2];
-
-            _normal[0] = self.cos_theta * rec.normal[0] + self.sin_theta * rec.normal[2];
-            _normal[2] = -self.sin_theta * rec.normal[0] + self.cos_theta * rec.normal[2];
-
-            rec.p = _p;
-            rec.set_face_normal(rotated_ray, _normal);
-
-            Some(rec)
-        } else {
-            None
+            wavelength: r.wavelength,
         }
     }
 
-    // 补全 pdf
-    fn pdf_value(&self, o: Vec3, v: Vec3) -> f64 {
-        self.now_box.pdf_value(o, v) + self.sin_theta
-    }
+    // 把碰撞的结果转回旋转之后的坐标系
+    fn rotate_back<'a>(&self, mut rec: HitRecord<'a>) -> HitRecord<'a> {
+        let mut _p = rec.p;
+        let mut _normal = rec.normal;
 
-    fn random(&self, o: Vec3) -> Vec3 {
-        self.now_box.random(o)
+        _p[0] = self.cos_theta * rec.p[0] + self.sin_theta * rec.p[2];
+        _p[2] = -self.sin_theta * rec.p[0] + self.cos_theta * rec.p[2];
+
+        _normal[0] = self.cos_theta * rec.normal[0] + self.sin_theta * rec.normal[2];
+        _normal[2] = -self.sin_theta * rec.normal[0] + self.cos_theta * rec.normal[2];
+
+        rec.p = _p;
+        rec.normal = _normal; // 旋转不改变法线在光线哪一侧，front_face 沿用原来的
+        rec
     }
-}
 
-impl<T: Hittable> RotateY<T> {
     pub fn new(p: T, angle: f64) -> Self {
         let radians = degree_to_radian(angle); //旋转角
         let _sin = radians.sin();
@@ -194,7 +346,7 @@ impl<T: Hittable> RotateY<T> {
         }
 
         let mut min_v = Vec3::new(INFINITY, INFINITY, INFINITY);
-        let mut max_v = Vec3::new(INFINITY, INFINITY, INFINITY);
+        let mut max_v = Vec3::new(-INFINITY, -INFINITY, -INFINITY);
 
         for i in 0..2 {
             for j in 0..2 {
@@ -229,7 +381,7 @@ impl<T: Hittable> RotateY<T> {
 }
 
 pub struct RotateZ<T>
-//绕y轴旋转
+//绕z轴旋转
 where
     T: Hittable,
 {
@@ -250,6 +402,88 @@ impl<T: Hittable> Hittable for RotateZ<T> {
     }
 
     fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self.now_box.hit(rotated_ray, t_min, t_max)?;
+        Some(self.rotate_back(rec))
+    }
+
+    fn hit_surface(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self.now_box.hit_surface(rotated_ray, t_min, t_max)?;
+        Some(self.rotate_back(rec))
+    }
+
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self
+            .now_box
+            .hit_sampled(rotated_ray, t_min, t_max, sampler)?;
+        Some(self.rotate_back(rec))
+    }
+
+    fn transmittance(&self, r: Ray, t_min: f64, t_max: f64, sampler: &mut dyn Sampler) -> f64 {
+        self.now_box
+            .transmittance(self.rotate_ray(r), t_min, t_max, sampler)
+    }
+
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        let rotated_ray = self.rotate_ray(r);
+        let rec = self.now_box.hit_counted(rotated_ray, t_min, t_max, cost)?;
+        Some(self.rotate_back(rec))
+    }
+
+    // 出发点和方向转到旋转之前的坐标系，旋转不改变立体角，pdf 不变
+    fn pdf_value(&self, o: Vec3, v: Vec3) -> f64 {
+        self.now_box.pdf_value(self.to_object(o), self.to_object(v))
+    }
+
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.to_world(self.now_box.random(self.to_object(o), sampler))
+    }
+
+    // 光源上的采样点和法线转到旋转后的坐标系，旋转不改变面积，pdf 不变
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        let (p, normal, pdf) = self.now_box.sample_surface(sampler)?;
+        Some((self.to_world(p), self.to_world(normal), pdf))
+    }
+
+    fn surface_pdf(&self, p: Vec3, normal: Vec3) -> f64 {
+        self.now_box
+            .surface_pdf(self.to_object(p), self.to_object(normal))
+    }
+}
+
+impl<T: Hittable> RotateZ<T> {
+    // 旋转之前的坐标系中的点或方向转到旋转之后
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        let mut w = v;
+        w[0] = self.cos_theta * v[0] + self.sin_theta * v[1];
+        w[1] = -self.sin_theta * v[0] + self.cos_theta * v[1];
+        w
+    }
+
+    // to_world 的逆变换
+    fn to_object(&self, v: Vec3) -> Vec3 {
+        let mut o = v;
+        o[0] = self.cos_theta * v[0] - self.sin_theta * v[1];
+        o[1] = self.sin_theta * v[0] + self.cos_theta * v[1];
+        o
+    }
+
+    // 把光线转到物体旋转之前的坐标系
+    fn rotate_ray(&self, r: Ray) -> Ray {
         let mut _orig = r.orig;
         let mut _dir = r.dir;
 
@@ -259,32 +493,30 @@ impl<T: Hittable> Hittable for RotateZ<T> {
         _dir[0] = self.cos_theta * r.dir[0] - self.sin_theta * r.dir[1];
         _dir[1] = self.sin_theta * r.dir[0] + self.cos_theta * r.dir[1];
 
-        let rotated_ray = Ray {
+        Ray {
             dir: _dir,
             orig: _orig,
             tm: r.tm,
-        };
-        if let Some(mut rec) = self.now_box.hit(rotated_ray, t_min, t_max) {
-            let mut _p = rec.p;
-            let mut _normal = rec.normal;
+            wavelength: r.wavelength,
+        }
+    }
 
-            _p[0] = self.cos_theta * rec.p[0] + self.sin_theta * rec.p[1];
-            _p[1] = -self.sin_theta * rec.p[0] + self.cos_theta * rec.p[1];
+    // 把碰撞的结果转回旋转之后的坐标系
+    fn rotate_back<'a>(&self, mut rec: HitRecord<'a>) -> HitRecord<'a> {
+        let mut _p = rec.p;
+        let mut _normal = rec.normal;
 
-            _normal[0] = self.cos_theta * rec.normal[0] + self.sin_theta * rec.normal[1];
-            _normal[1] = -self.sin_theta * rec.normal[0] + self.cos_theta * rec.normal[1];
+        _p[0] = self.cos_theta * rec.p[0] + self.sin_theta * rec.p[1];
+        _p[1] = -self.sin_theta * rec.p[0] + self.cos_theta * rec.p[1];
 
-            rec.p = _p;
-            rec.set_face_normal(rotated_ray, _normal);
+        _normal[0] = self.cos_theta * rec.normal[0] + self.sin_theta * rec.normal[1];
+        _normal[1] = -self.sin_theta * rec.normal[0] + self.cos_theta * rec.normal[1];
 
-            Some(rec)
-        } else {
-            None
-        }
+        rec.p = _p;
+        rec.normal = _normal; // 旋转不改变法线在光线哪一侧，front_face 沿用原来的
+        rec
     }
-}
 
-impl<T: Hittable> RotateZ<T> {
     pub fn new(p: T, angle: f64) -> Self {
         let radians = degree_to_radian(angle); //旋转角
         let _sin = radians.sin();
@@ -300,7 +532,7 @@ impl<T: Hittable> RotateZ<T> {
         }
 
         let mut min_v = Vec3::new(INFINITY, INFINITY, INFINITY);
-        let mut max_v = Vec3::new(INFINITY, INFINITY, INFINITY);
+        let mut max_v = Vec3::new(-INFINITY, -INFINITY, -INFINITY);
 
         for i in 0..2 {
             for j in 0..2 {
@@ -309,10 +541,10 @@ impl<T: Hittable> RotateZ<T> {
                     let y = tp_box.maximum.y * j as f64 + tp_box.minimum.y * (1 - j) as f64;
                     let z = tp_box.maximum.z * k as f64 + tp_box.minimum.z * (1 - k) as f64;
                     // 旋转公式
-                    let newx = _cos * x + _sin * z;
-                    let newz = -_sin * x + _cos * z;
+                    let newx = _cos * x + _sin * y;
+                    let newy = -_sin * x + _cos * y;
 
-                    let tester = Vec3::new(newx, y, newz);
+                    let tester = Vec3::new(newx, newy, z);
                     for c in 0..3 {
                         min_v[c] = min_f64(min_v[c], tester[c]);
                         max_v[c] = max_f64(max_v[c], tester[c]);
@@ -333,3 +565,47 @@ impl<T: Hittable> RotateZ<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        basic_component::sampler::Pcg32, hittable::objects::aarect::XYRect,
+        material::diffuse_light::DiffuseLight,
+    };
+
+    // 采到的点在旋转后的表面上，法向量与碰撞到的一致，pdf 为面积的倒数
+    fn check(light: &dyn Hittable, area: f64) {
+        let mut rng = Pcg32::new(19);
+        for _ in 0..100 {
+            let (p, n, pdf) = light.sample_surface(&mut rng).unwrap();
+            assert!((n.length() - 1.).abs() < 1e-9);
+            let rec = light
+                .hit(Ray::new(p + n, Vec3::new(0., 0., 0.) - n, 0.), 0.5, 1.5)
+                .unwrap();
+            assert!((rec.p - p).length() < 1e-9);
+            assert!((Vec3::dot(rec.normal, n).abs() - 1.).abs() < 1e-9);
+            assert!((pdf * area - 1.).abs() < 1e-9);
+            assert!((light.surface_pdf(p, n) - pdf).abs() < 1e-9);
+        }
+        // 旋转之前的矩形所在的位置不再是表面
+        assert!(light.surface_pdf(Vec3::new(0.9, 1.9, 0.), Vec3::new(0., 0., 1.)) <= 0.);
+    }
+
+    #[test]
+    fn samples_rotated_rect_lights() {
+        let light = || {
+            XYRect::new(
+                0.,
+                1.,
+                0.,
+                2.,
+                0.,
+                DiffuseLight::new_from_color(Vec3::new(1., 1., 1.)),
+            )
+        };
+        check(&RotateX::new(light(), 30.), 2.);
+        check(&RotateY::new(light(), 60.), 2.);
+        check(&RotateZ::new(light(), 45.), 2.);
+    }
+}
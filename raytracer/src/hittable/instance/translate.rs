@@ -1,6 +1,6 @@
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
-    hittable::{HitRecord, Hittable},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
+    hittable::{HitRecord, Hittable, TraversalCost},
     optimization::aabb::AABB,
 };
 
@@ -14,20 +14,53 @@ where
 
 impl<T: Hittable> Hittable for Translate<T> {
     fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let moved_ray = Ray {
-            orig: r.orig - self.offset,
-            dir: r.dir,
-            tm: r.tm,
-        };
+        let moved_ray = self.move_ray(r);
         if let Some(mut rec) = self.now_box.hit(moved_ray, t_min, t_max) {
             rec.p += self.offset;
-            rec.set_face_normal(moved_ray, rec.normal);
             Some(rec)
         } else {
             None
         }
     }
 
+    fn hit_surface(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let moved_ray = self.move_ray(r);
+        let mut rec = self.now_box.hit_surface(moved_ray, t_min, t_max)?;
+        rec.p += self.offset;
+        Some(rec)
+    }
+
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        let moved_ray = self.move_ray(r);
+        let mut rec = self.now_box.hit_sampled(moved_ray, t_min, t_max, sampler)?;
+        rec.p += self.offset;
+        Some(rec)
+    }
+
+    fn transmittance(&self, r: Ray, t_min: f64, t_max: f64, sampler: &mut dyn Sampler) -> f64 {
+        self.now_box
+            .transmittance(self.move_ray(r), t_min, t_max, sampler)
+    }
+
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        let moved_ray = self.move_ray(r);
+        let mut rec = self.now_box.hit_counted(moved_ray, t_min, t_max, cost)?;
+        rec.p += self.offset;
+        Some(rec)
+    }
+
     fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
         if let Some(mut outbox) = self.now_box.bounding_box(_time0, _time1) {
             outbox.minimum += self.offset;
@@ -38,17 +71,35 @@ impl<T: Hittable> Hittable for Translate<T> {
         }
     }
 
-    // 补充
+    // 把出发点移到平移之前的坐标系，方向不变
     fn pdf_value(&self, o: Vec3, v: Vec3) -> f64 {
-        self.now_box.pdf_value(o, v) + self.offset.length()
+        self.now_box.pdf_value(o - self.offset, v)
     }
 
-    fn random(&self, o: Vec3) -> Vec3 {
-        self.now_box.random(o)
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.now_box.random(o - self.offset, sampler)
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        let (p, normal, pdf) = self.now_box.sample_surface(sampler)?;
+        Some((p + self.offset, normal, pdf))
+    }
+
+    fn surface_pdf(&self, p: Vec3, normal: Vec3) -> f64 {
+        self.now_box.surface_pdf(p - self.offset, normal)
     }
 }
 
 impl<T: Hittable> Translate<T> {
+    fn move_ray(&self, r: Ray) -> Ray {
+        Ray {
+            orig: r.orig - self.offset,
+            dir: r.dir,
+            tm: r.tm,
+            wavelength: r.wavelength,
+        }
+    }
+
     pub fn new(before: T, _off: Vec3) -> Self {
         Self {
             offset: _off,
@@ -1,6 +1,8 @@
+use std::f64::INFINITY;
+
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
-    hittable::{HitRecord, Hittable},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
+    hittable::{HitRecord, Hittable, TraversalCost},
     optimization::aabb::AABB,
 };
 pub struct Zoom<T>
@@ -14,18 +16,44 @@ where
 
 impl<T: Hittable> Hittable for Zoom<T> {
     fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let moved_ray = Ray {
-            orig: r.orig / self.factor,
-            dir: r.dir,
-            tm: r.tm,
-        };
-        if let Some(mut rec) = self.now_box.hit(moved_ray, t_min, t_max) {
-            rec.p = rec.p * self.factor;
-            rec.set_face_normal(moved_ray, rec.normal);
-            Some(rec)
-        } else {
-            None
-        }
+        let rec = self.now_box.hit(self.zoom_ray(r), t_min, t_max)?;
+        Some(self.zoom_back(rec))
+    }
+
+    fn hit_surface(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let rec = self.now_box.hit_surface(self.zoom_ray(r), t_min, t_max)?;
+        Some(self.zoom_back(rec))
+    }
+
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        let rec = self
+            .now_box
+            .hit_sampled(self.zoom_ray(r), t_min, t_max, sampler)?;
+        Some(self.zoom_back(rec))
+    }
+
+    fn transmittance(&self, r: Ray, t_min: f64, t_max: f64, sampler: &mut dyn Sampler) -> f64 {
+        self.now_box
+            .transmittance(self.zoom_ray(r), t_min, t_max, sampler)
+    }
+
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        let rec = self
+            .now_box
+            .hit_counted(self.zoom_ray(r), t_min, t_max, cost)?;
+        Some(self.zoom_back(rec))
     }
 
     fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
@@ -38,16 +66,73 @@ impl<T: Hittable> Hittable for Zoom<T> {
         }
     }
 
+    // 缩放会改变立体角，改为在缩放后的表面上按面积采样，面积的 pdf 换算成立体角的 pdf
     fn pdf_value(&self, o: Vec3, v: Vec3) -> f64 {
-        self.now_box.pdf_value(o, v) + self.factor.length()
+        let r = Ray::new(o, v, 0.);
+        match self.hit(r, 0.001, INFINITY) {
+            Some(rec) => {
+                let distance_squared = rec.t * rec.t * v.length_squared();
+                let cosine = Vec3::dot(v, rec.normal).abs() / v.length();
+                self.surface_pdf(rec.p, rec.normal) * distance_squared / cosine
+            }
+            None => 0.,
+        }
+    }
+
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        match self.sample_surface(sampler) {
+            Some((p, _, _)) => p - o,
+            None => Vec3::new(1., 0., 0.),
+        }
     }
 
-    fn random(&self, o: Vec3) -> Vec3 {
-        self.now_box.random(o)
+    // 面积按 |det| * |n / factor| 放大（n 为缩放前的单位法向量），pdf 相应地缩小
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        let (p, normal, pdf) = self.now_box.sample_surface(sampler)?;
+        let normal = Vec3::unit_vector(normal);
+        Some((
+            p * self.factor,
+            self.zoom_normal(normal),
+            pdf / self.area_scale(normal),
+        ))
+    }
+
+    fn surface_pdf(&self, p: Vec3, normal: Vec3) -> f64 {
+        // 法向量按 factor 变换回缩放之前
+        let normal = Vec3::unit_vector(normal * self.factor);
+        self.now_box.surface_pdf(p / self.factor, normal) / self.area_scale(normal)
     }
 }
 
 impl<T: Hittable> Zoom<T> {
+    // 方向也一起缩放，碰撞的 t 与原来的光线相同
+    fn zoom_ray(&self, r: Ray) -> Ray {
+        Ray {
+            orig: r.orig / self.factor,
+            dir: r.dir / self.factor,
+            tm: r.tm,
+            wavelength: r.wavelength,
+        }
+    }
+
+    // 按逆转置变换的法线仍在光线的同一侧，front_face 沿用原来的
+    fn zoom_back<'a>(&self, mut rec: HitRecord<'a>) -> HitRecord<'a> {
+        rec.p = rec.p * self.factor;
+        rec.normal = self.zoom_normal(rec.normal);
+        rec
+    }
+
+    // 法向量按缩放的逆转置变换
+    fn zoom_normal(&self, normal: Vec3) -> Vec3 {
+        Vec3::unit_vector(normal / self.factor)
+    }
+
+    // 单位法向量为 normal 的一小块面积缩放后变为原来的多少倍
+    fn area_scale(&self, normal: Vec3) -> f64 {
+        let f = self.factor;
+        f.x * f.y * f.z * (normal / f).length()
+    }
+
     pub fn new(before: T, fac: Vec3) -> Self {
         Self {
             factor: fac,
@@ -55,3 +140,40 @@ impl<T: Hittable> Zoom<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        basic_component::sampler::Pcg32,
+        hittable::{instance::rotate::RotateX, objects::aarect::XYRect},
+        material::diffuse_light::DiffuseLight,
+    };
+
+    #[test]
+    fn scales_the_area_pdf() {
+        // 绕 x 轴转 45° 后，边 (0, 1, 0) 变为 (0, c, -c)，再缩放为 (0, 2c, -c)
+        let rect = XYRect::new(
+            0.,
+            1.,
+            0.,
+            1.,
+            0.,
+            DiffuseLight::new_from_color(Vec3::new(1., 1., 1.)),
+        );
+        let light = Zoom::new(RotateX::new(rect, 45.), Vec3::new(1., 2., 1.));
+        let area = 2.5f64.sqrt();
+        let mut rng = Pcg32::new(19);
+        for _ in 0..100 {
+            let (p, n, pdf) = light.sample_surface(&mut rng).unwrap();
+            let rec = light
+                .hit(Ray::new(p + n, Vec3::new(0., 0., 0.) - n, 0.), 0.5, 1.5)
+                .unwrap();
+            // 法向量垂直于缩放后的表面，光线的 t 也没有被缩放
+            assert!((rec.t - 1.).abs() < 1e-9);
+            assert!((rec.p - p).length() < 1e-9);
+            assert!((pdf * area - 1.).abs() < 1e-9);
+            assert!((light.surface_pdf(p, n) - pdf).abs() < 1e-9);
+        }
+    }
+}
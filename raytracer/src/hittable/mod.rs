@@ -6,10 +6,9 @@ pub mod stlloader;
 use std::f64::consts::PI;
 
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
     material::Material,
     optimization::aabb::AABB,
-    utility::random_int,
 };
 
 #[derive(Clone, Copy)]
@@ -19,7 +18,7 @@ pub struct HitRecord<'a> {
     pub t: f64,                //对应光线的 at(t)
     pub front_face: bool,      //方向是否为外侧
     pub mat: &'a dyn Material, //材料，变量类型是对包含 Material 结构体的引用
-    // 采用 dyn，因为 ray_color 中会有很多次碰撞
+    // 采用 dyn，因为一条路径上会有很多次碰撞
     // 如果不用引用/指针，改成泛型，就会多次生成变量，浪费时间
     pub u: f64, // 碰撞点对应在二维图上的坐标
     pub v: f64,
@@ -60,9 +59,119 @@ pub trait Hittable: Send + Sync // 加上后缀Send/Sync，用于多线程的传
         0.
     }
 
-    fn random(&self, _o: Vec3) -> Vec3 {
+    fn random(&self, _o: Vec3, _sampler: &mut dyn Sampler) -> Vec3 {
         Vec3::new(1., 0., 0.)
     }
+
+    // 在表面上按面积采样一点，返回这一点、它的法向量和面积上的 pdf，用于从光源出发的路径
+    // 法向量的朝向不一定是发光的一侧；不支持的物体返回 None
+    fn sample_surface(&self, _sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        None
+    }
+
+    // sample_surface 采到表面上一点 p 的 pdf，p 不在表面上时为 0
+    // normal 为 p 处的法向量，朝向不限；缩放后面积的变化与它有关
+    fn surface_pdf(&self, _p: Vec3, _normal: Vec3) -> f64 {
+        0.
+    }
+
+    // 阴影光线只关心表面，参与介质（烟、雾）不挡住光线，而是按透射率衰减
+    // 不是介质的物体与 hit 相同
+    fn hit_surface(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.hit(r, t_min, t_max)
+    }
+
+    // 与 hit 相同，但介质中的碰撞距离（自由程）由路径的采样器采样
+    // 低差异序列和 MLT 的变异因此也作用于介质，积分器应当调用这个版本
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        self.hit(r, t_min, t_max)
+    }
+
+    // 光线在 [t_min, t_max] 内穿过介质后剩下的比例，表面不影响透射率
+    fn transmittance(&self, _r: Ray, _t_min: f64, _t_max: f64, _sampler: &mut dyn Sampler) -> f64 {
+        1.
+    }
+
+    // 与 hit 相同，同时统计求交的开销，只有调试视图调用，不影响平时的遍历
+    // 默认把整个物体当作一次图元求交，由多个物体组成的容器应当逐个转发
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        cost.primitives += 1;
+        self.hit(r, t_min, t_max)
+    }
+}
+
+// 一条光线求交时访问的 BVH 结点数和与图元求交的次数
+#[derive(Copy, Clone, Default)]
+pub struct TraversalCost {
+    pub nodes: u32,
+    pub primitives: u32,
+}
+
+// 运行时才能确定类型的物体（例如从场景文件读入），通过 Box 动态分发
+impl Hittable for Box<dyn Hittable> {
+    fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        (**self).hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        (**self).bounding_box(time0, time1)
+    }
+
+    fn pdf_value(&self, o: Vec3, v: Vec3) -> f64 {
+        (**self).pdf_value(o, v)
+    }
+
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        (**self).random(o, sampler)
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        (**self).sample_surface(sampler)
+    }
+
+    fn surface_pdf(&self, p: Vec3, normal: Vec3) -> f64 {
+        (**self).surface_pdf(p, normal)
+    }
+
+    fn hit_surface(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        (**self).hit_surface(r, t_min, t_max)
+    }
+
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        (**self).hit_sampled(r, t_min, t_max, sampler)
+    }
+
+    fn transmittance(&self, r: Ray, t_min: f64, t_max: f64, sampler: &mut dyn Sampler) -> f64 {
+        (**self).transmittance(r, t_min, t_max, sampler)
+    }
+
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        (**self).hit_counted(r, t_min, t_max, cost)
+    }
 }
 
 //------------------------------------
@@ -140,10 +249,85 @@ impl Hittable for HittableList {
         sum
     }
 
-    fn random(&self, o: Vec3) -> Vec3 {
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
         let int_size = self.objects.len() as i32;
-        let t = random_int(0, int_size - 1) as usize;
+        let t = sampler.random_int(0, int_size - 1) as usize;
+
+        self.objects[t].random(o, sampler)
+    }
+
+    // 与 random 相同，先等概率地选出一个物体
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        if self.objects.is_empty() {
+            return None;
+        }
+        let n = self.objects.len();
+        let t = sampler.random_int(0, n as i32 - 1) as usize;
+        let (p, normal, pdf) = self.objects[t].sample_surface(sampler)?;
+        Some((p, normal, pdf / n as f64))
+    }
+
+    fn surface_pdf(&self, p: Vec3, normal: Vec3) -> f64 {
+        let weight = 1. / self.objects.len() as f64;
+        self.objects
+            .iter()
+            .map(|t| weight * t.surface_pdf(p, normal))
+            .sum()
+    }
+
+    fn hit_surface(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut hit_rec: Option<HitRecord> = Option::None;
+        let mut closest_so_far = t_max;
+        for i in &self.objects {
+            if let Some(temp_rec) = i.hit_surface(r, t_min, closest_so_far) {
+                closest_so_far = temp_rec.t;
+                hit_rec = Some(temp_rec);
+            }
+        }
+        hit_rec
+    }
 
-        self.objects[t].random(o)
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        let mut hit_rec: Option<HitRecord> = Option::None;
+        let mut closest_so_far = t_max;
+        for i in &self.objects {
+            if let Some(temp_rec) = i.hit_sampled(r, t_min, closest_so_far, sampler) {
+                closest_so_far = temp_rec.t;
+                hit_rec = Some(temp_rec);
+            }
+        }
+        hit_rec
+    }
+
+    // 各个介质互不重叠时，透射率相乘
+    fn transmittance(&self, r: Ray, t_min: f64, t_max: f64, sampler: &mut dyn Sampler) -> f64 {
+        self.objects
+            .iter()
+            .map(|t| t.transmittance(r, t_min, t_max, sampler))
+            .product()
+    }
+
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        let mut hit_rec: Option<HitRecord> = Option::None;
+        let mut closest_so_far = t_max;
+        for i in &self.objects {
+            if let Some(temp_rec) = i.hit_counted(r, t_min, closest_so_far, cost) {
+                closest_so_far = temp_rec.t;
+                hit_rec = Some(temp_rec);
+            }
+        }
+        hit_rec
     }
 }
@@ -1,13 +1,15 @@
 use std::f64::INFINITY;
 
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
     hittable::{HitRecord, Hittable},
     material::Material,
     optimization::aabb::AABB,
-    utility::random_double,
 };
 
+// 判断一点是否在矩形上时允许的误差
+const ON_SURFACE: f64 = 1e-4;
+
 pub struct XYRect<T>
 where
     T: Material,
@@ -69,14 +71,42 @@ impl<T: Material> Hittable for XYRect<T> {
         }
     }
 
-    fn random(&self, o: Vec3) -> Vec3 {
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (r1, r2) = sampler.get_2d();
         let random_point = Vec3::new(
-            random_double(self.x0, self.x1),
-            random_double(self.y0, self.y1),
+            self.x0 + r1 * (self.x1 - self.x0),
+            self.y0 + r2 * (self.y1 - self.y0),
             self.k,
         );
         random_point - o
     }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        let (r1, r2) = sampler.get_2d();
+        let p = Vec3::new(
+            self.x0 + r1 * (self.x1 - self.x0),
+            self.y0 + r2 * (self.y1 - self.y0),
+            self.k,
+        );
+        Some((
+            p,
+            Vec3::new(0., 0., 1.),
+            1. / ((self.x1 - self.x0) * (self.y1 - self.y0)),
+        ))
+    }
+
+    fn surface_pdf(&self, p: Vec3, _normal: Vec3) -> f64 {
+        if (p.z - self.k).abs() < ON_SURFACE
+            && p.x >= self.x0
+            && p.x <= self.x1
+            && p.y >= self.y0
+            && p.y <= self.y1
+        {
+            1. / ((self.x1 - self.x0) * (self.y1 - self.y0))
+        } else {
+            0.
+        }
+    }
 }
 
 impl<T: Material> XYRect<T> {
@@ -151,14 +181,42 @@ impl<T: Material> Hittable for XZRect<T> {
         }
     }
 
-    fn random(&self, o: Vec3) -> Vec3 {
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (r1, r2) = sampler.get_2d();
         let random_point = Vec3::new(
-            random_double(self.x0, self.x1),
+            self.x0 + r1 * (self.x1 - self.x0),
             self.k,
-            random_double(self.z0, self.z1),
+            self.z0 + r2 * (self.z1 - self.z0),
         );
         random_point - o
     }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        let (r1, r2) = sampler.get_2d();
+        let p = Vec3::new(
+            self.x0 + r1 * (self.x1 - self.x0),
+            self.k,
+            self.z0 + r2 * (self.z1 - self.z0),
+        );
+        Some((
+            p,
+            Vec3::new(0., 1., 0.),
+            1. / ((self.x1 - self.x0) * (self.z1 - self.z0)),
+        ))
+    }
+
+    fn surface_pdf(&self, p: Vec3, _normal: Vec3) -> f64 {
+        if (p.y - self.k).abs() < ON_SURFACE
+            && p.x >= self.x0
+            && p.x <= self.x1
+            && p.z >= self.z0
+            && p.z <= self.z1
+        {
+            1. / ((self.x1 - self.x0) * (self.z1 - self.z0))
+        } else {
+            0.
+        }
+    }
 }
 
 impl<T: Material> XZRect<T> {
@@ -232,14 +290,42 @@ impl<T: Material> Hittable for YZRect<T> {
         }
     }
 
-    fn random(&self, o: Vec3) -> Vec3 {
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (r1, r2) = sampler.get_2d();
         let random_point = Vec3::new(
             self.k,
-            random_double(self.y0, self.y1),
-            random_double(self.z0, self.z1),
+            self.y0 + r1 * (self.y1 - self.y0),
+            self.z0 + r2 * (self.z1 - self.z0),
         );
         random_point - o
     }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        let (r1, r2) = sampler.get_2d();
+        let p = Vec3::new(
+            self.k,
+            self.y0 + r1 * (self.y1 - self.y0),
+            self.z0 + r2 * (self.z1 - self.z0),
+        );
+        Some((
+            p,
+            Vec3::new(1., 0., 0.),
+            1. / ((self.y1 - self.y0) * (self.z1 - self.z0)),
+        ))
+    }
+
+    fn surface_pdf(&self, p: Vec3, _normal: Vec3) -> f64 {
+        if (p.x - self.k).abs() < ON_SURFACE
+            && p.y >= self.y0
+            && p.y <= self.y1
+            && p.z >= self.z0
+            && p.z <= self.z1
+        {
+            1. / ((self.y1 - self.y0) * (self.z1 - self.z0))
+        } else {
+            0.
+        }
+    }
 }
 
 impl<T: Material> YZRect<T> {
@@ -1,8 +1,8 @@
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
     hittable::{
         objects::aarect::{XYRect, XZRect, YZRect},
-        HitRecord, Hittable, HittableList,
+        HitRecord, Hittable, HittableList, TraversalCost,
     },
     material::Material,
     optimization::aabb::AABB,
@@ -27,23 +27,41 @@ impl Hittable for Cube {
         self.sides.hit(r, t_min, t_max)
     }
 
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        self.sides.hit_counted(r, t_min, t_max, cost)
+    }
+
     fn pdf_value(&self, o: Vec3, v: Vec3) -> f64 {
         self.sides.pdf_value(o, v)
     }
 
-    fn random(&self, o: Vec3) -> Vec3 {
-        self.sides.random(o)
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.sides.random(o, sampler)
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        self.sides.sample_surface(sampler)
+    }
+
+    fn surface_pdf(&self, p: Vec3, normal: Vec3) -> f64 {
+        self.sides.surface_pdf(p, normal)
     }
 }
 
 impl Cube {
-    pub fn new<T: 'static + Copy + Material>(p0: Vec3, p1: Vec3, mat: T) -> Self {
+    pub fn new<T: 'static + Clone + Material>(p0: Vec3, p1: Vec3, mat: T) -> Self {
         let mut _sides: HittableList = Default::default();
-        _sides.add(XYRect::new(p0.x, p1.x, p0.y, p1.y, p1.z, mat));
-        _sides.add(XYRect::new(p0.x, p1.x, p0.y, p1.y, p0.z, mat));
-        _sides.add(XZRect::new(p0.x, p1.x, p0.z, p1.z, p1.y, mat));
-        _sides.add(XZRect::new(p0.x, p1.x, p0.z, p1.z, p0.y, mat));
-        _sides.add(YZRect::new(p0.y, p1.y, p0.z, p1.z, p1.x, mat));
+        _sides.add(XYRect::new(p0.x, p1.x, p0.y, p1.y, p1.z, mat.clone()));
+        _sides.add(XYRect::new(p0.x, p1.x, p0.y, p1.y, p0.z, mat.clone()));
+        _sides.add(XZRect::new(p0.x, p1.x, p0.z, p1.z, p1.y, mat.clone()));
+        _sides.add(XZRect::new(p0.x, p1.x, p0.z, p1.z, p0.y, mat.clone()));
+        _sides.add(YZRect::new(p0.y, p1.y, p0.z, p1.z, p1.x, mat.clone()));
         _sides.add(YZRect::new(p0.y, p1.y, p0.z, p1.z, p0.x, mat));
 
         Self {
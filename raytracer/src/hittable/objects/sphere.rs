@@ -1,7 +1,7 @@
 use std::f64::{consts::PI, INFINITY};
 
 use crate::{
-    basic_component::{onb::ONB, ray::Ray, vec3::Vec3},
+    basic_component::{onb::ONB, ray::Ray, sampler::Sampler, vec3::Vec3},
     hittable::{HitRecord, Hittable},
     material::Material,
     optimization::aabb::AABB,
@@ -77,12 +77,27 @@ impl<T: Material> Hittable for Sphere<T> {
         }
     }
 
-    fn random(&self, o: Vec3) -> Vec3 {
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
         let dir = self.center - o;
         let distance_squared = dir.length_squared();
         let uvw = ONB::build_from_w(dir);
 
-        uvw.local_from_vec(random_to_sphere(self.radius, distance_squared))
+        uvw.local_from_vec(random_to_sphere(self.radius, distance_squared, sampler))
+    }
+
+    // 在整个球面上均匀采样
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        let normal = Vec3::random_unit_vector(sampler);
+        let area = 4. * PI * self.radius * self.radius;
+        Some((self.center + normal * self.radius, normal, 1. / area))
+    }
+
+    fn surface_pdf(&self, p: Vec3, _normal: Vec3) -> f64 {
+        if ((p - self.center).length() - self.radius).abs() < 1e-4 {
+            1. / (4. * PI * self.radius * self.radius)
+        } else {
+            0.
+        }
     }
 }
 
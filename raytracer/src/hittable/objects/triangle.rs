@@ -1,11 +1,10 @@
 use std::f64::{INFINITY, NEG_INFINITY};
 
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
     hittable::{HitRecord, Hittable},
     material::{metal::Metal, Material},
     optimization::aabb::AABB,
-    utility::random_double,
 };
 
 #[derive(Clone, Copy)]
@@ -130,11 +129,10 @@ impl<T: Material> Hittable for Triangle<T> {
             0.
         }
     }
-    fn random(&self, o: Vec3) -> Vec3 {
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
         let i = self.vers[1] - self.vers[0];
         let j = self.vers[2] - self.vers[0];
-        let mut k1 = random_double(0., 1.);
-        let mut k2 = random_double(0., 1.);
+        let (mut k1, mut k2) = sampler.get_2d();
         if k1 + k2 > 1. {
             k1 = 1. - k1;
             k2 = 1. - k2;
@@ -142,6 +140,31 @@ impl<T: Material> Hittable for Triangle<T> {
 
         (i * k1 + j * k2) - o
     }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, Vec3, f64)> {
+        let i = self.vers[1] - self.vers[0];
+        let j = self.vers[2] - self.vers[0];
+        // 落在平行四边形另一半的点对称回三角形内
+        let (mut k1, mut k2) = sampler.get_2d();
+        if k1 + k2 > 1. {
+            k1 = 1. - k1;
+            k2 = 1. - k2;
+        }
+        Some((self.vers[0] + i * k1 + j * k2, self.normal, 1. / self.area))
+    }
+
+    fn surface_pdf(&self, p: Vec3, _normal: Vec3) -> f64 {
+        if Vec3::dot(p - self.center, self.normal).abs() >= 1e-4 {
+            return 0.;
+        }
+        let gamma = Vec3::dot(p - self.vers[0], self.v);
+        let beta = Vec3::dot(p - self.vers[0], self.w);
+        if gamma >= 0. && beta >= 0. && gamma + beta <= 1. {
+            1. / self.area
+        } else {
+            0.
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -335,11 +358,10 @@ impl<T: Material> Hittable for OBJTriangle<T> {
             0.
         }
     }
-    fn random(&self, o: Vec3) -> Vec3 {
+    fn random(&self, o: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
         let i = self.vers[1] - self.vers[0];
         let j = self.vers[2] - self.vers[0];
-        let mut k1 = random_double(0., 1.);
-        let mut k2 = random_double(0., 1.);
+        let (mut k1, mut k2) = sampler.get_2d();
         if k1 + k2 > 1. {
             k1 = 1. - k1;
             k2 = 1. - k2;
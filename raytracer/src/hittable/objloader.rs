@@ -1,8 +1,8 @@
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
+    basic_component::{ray::Ray, sampler::Pcg32, vec3::Vec3},
     hittable::{
         objects::triangle::{OBJTriangle, Triangle},
-        HitRecord, Hittable, HittableList,
+        HitRecord, Hittable, HittableList, TraversalCost,
     },
     material::{lambertian::Lambertian, Material},
     optimization::{aabb::AABB, bvh::BvhNode},
@@ -24,6 +24,16 @@ impl Hittable for OBJ {
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
         self.triangles.bounding_box(time0, time1)
     }
+
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        self.triangles.hit_counted(r, t_min, t_max, cost)
+    }
 }
 
 impl OBJ {
@@ -169,14 +179,15 @@ impl OBJ {
         // println!("load succeed!");
 
         Self {
-            triangles: BvhNode::new_from_list(objects, t0, t1),
+            // 模型的划分与渲染无关，用固定的种子即可
+            triangles: BvhNode::new_from_list(objects, t0, t1, &mut Pcg32::new(0)),
         }
     }
 
     // 只加载模型，不贴图
     pub fn load_without_texture<T>(file_name: &str, t0: f64, t1: f64, mat: T) -> Self
     where
-        T: Material + 'static + Copy,
+        T: Material + 'static + Clone,
     {
         let scene = tobj::load_obj(file_name, &tobj::GPU_LOAD_OPTIONS);
 
@@ -203,14 +214,15 @@ impl OBJ {
                         pos[id[1] as usize],
                         pos[id[2] as usize],
                     ],
-                    mat,
+                    mat.clone(),
                 );
                 objects.add(tri);
             }
         }
 
         Self {
-            triangles: BvhNode::new_from_list(objects, t0, t1),
+            // 模型的划分与渲染无关，用固定的种子即可
+            triangles: BvhNode::new_from_list(objects, t0, t1, &mut Pcg32::new(0)),
         }
     }
 }
@@ -1,12 +1,12 @@
 use std::{fs::File, io::BufReader};
 
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
+    basic_component::{ray::Ray, sampler::Pcg32, vec3::Vec3},
     material::Material,
     optimization::{aabb::AABB, bvh::BvhNode},
 };
 
-use super::{objects::triangle::Triangle, Hittable, HittableList};
+use super::{objects::triangle::Triangle, Hittable, HittableList, TraversalCost};
 
 pub struct STL {
     pub triangles: BvhNode,
@@ -20,12 +20,22 @@ impl Hittable for STL {
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
         self.triangles.bounding_box(time0, time1)
     }
+
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<super::HitRecord> {
+        self.triangles.hit_counted(r, t_min, t_max, cost)
+    }
 }
 
 impl STL {
     pub fn load_from_file<T>(file_name: &str, t0: f64, t1: f64, _mat: T) -> Self
     where
-        T: Material + 'static + Copy,
+        T: Material + 'static + Clone,
     {
         let file = File::open(file_name).unwrap();
         let mut ast = BufReader::new(&file);
@@ -50,11 +60,12 @@ impl STL {
                 obj.vertices()[2][2] as f64,
             );
 
-            objects.add(Triangle::new([x, y, z], _mat));
+            objects.add(Triangle::new([x, y, z], _mat.clone()));
         }
 
         Self {
-            triangles: BvhNode::new_from_list(objects, t0, t1),
+            // 模型的划分与渲染无关，用固定的种子即可
+            triangles: BvhNode::new_from_list(objects, t0, t1, &mut Pcg32::new(0)),
         }
     }
 }
@@ -1,646 +1,468 @@
 use std::{
+    fs,
     // time::Instant,
-    f64::INFINITY,
-    fs::File,
     process::exit,
-    sync::{mpsc, Arc},
-    thread,
+    sync::Arc,
 };
 
-use hittable::{
-    instance::{
-        rotate::{RotateX, RotateZ},
-        zoom::Zoom,
-    },
-    // objects::triangle::Triangle,
-    objloader::OBJ,
-    stlloader::STL,
-};
-use image::{ImageBuffer, RgbImage};
-
 use console::style;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use optimization::pdf::MixturePDF;
-use rand::{prelude::StdRng, Rng, SeedableRng};
 
 pub mod basic_component;
+pub mod cli;
+pub mod environment;
+pub mod film;
 pub mod hittable;
 pub mod material;
 pub mod optimization;
+pub mod render;
+pub mod scene;
 pub mod texture;
 pub mod utility; //调用模块
 
 use crate::{
-    basic_component::{camera::Camera, ray::Ray, vec3::Vec3},
-    hittable::{
-        instance::{
-            constant_medium::ConstantMedium, flipface::Flipface, rotate::RotateY,
-            translate::Translate,
-        },
-        objects::{
-            aarect::{XYRect, XZRect, YZRect},
-            cube::Cube,
-            sphere::{MovingSphere, Sphere},
-            // triangle::Triangle,
-        },
-        Hittable, HittableList,
+    basic_component::{camera::CameraConfig, sampler::SamplerConfig, vec3::Vec3},
+    cli::RenderOptions,
+    film::{
+        checkpoint::{self, RenderInfo},
+        denoise::Denoiser,
+        output::{self, OutputOptions},
+        tonemap::ToneMap,
+        Film,
     },
-    material::{
-        dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal,
+    render::{
+        aov,
+        bdpt::Bdpt,
+        debug::DebugIntegrator,
+        mlt::Mlt,
+        path::PathTracer,
+        photon::PhotonMapper,
+        progressive::{FilmSample, Progressive},
+        spectral::SpectralTracer,
+        Integrator, IntegratorKind,
     },
-    optimization::{
-        bvh::BvhNode,
-        pdf::{HittablePDF, PDF},
-    },
-    texture::{
-        checker::CheckerTexture, image::ImageTexture, perlin::NoiseTexture, perlin::Perlin,
-        solid::SolidColor,
-    },
-    utility::{get_pixel_color, random_double},
+    scene::builtin::{self, BUILTIN_SCENES},
 };
 
-pub fn ray_color(
-    r: Ray,
-    background: Vec3,
-    world: &HittableList,
-    lights: &HittableList,
-    depth: i32,
-) -> Vec3 {
-    // 递归终止条件
-    // 超出限制，光无法反射，变成黑色
-    if depth <= 0 {
-        return Vec3::new(0., 0., 0.);
-    }
-
-    let emitted: Vec3;
-
-    // 判断是否碰到物体
-    // t_min 修正为 0.01，因为光线并不是在 t=0 处才会击中物体
-    if let Some(rec) = world.hit(r, 0.001, INFINITY) {
-        emitted = rec.mat.emitted(r, rec, rec.u, rec.v, rec.p);
-
-        //考虑材质的散射
-        if let Some(srec) = rec.mat.scatter(r, rec) {
-            //除了 Lambertian，都会发生镜面反射
-            if srec.is_specular {
-                return srec.attenuation
-                    * ray_color(srec.scattered, background, world, lights, depth - 1);
+fn main() {
+    let options = match cli::parse_args() {
+        cli::Command::ListScenes => {
+            for s in BUILTIN_SCENES.iter() {
+                println!("{:<14}{}", s.name, s.description);
             }
+            exit(0);
+        }
+        cli::Command::Render(options) => options,
+    };
 
-            // Lambertian 考虑 PDF，加快渲染，提高准确性
-            let p0 = HittablePDF::new(lights, rec.p);
-            let p1 = srec.cos_pdf;
-            let mixed = MixturePDF::new(&p0, p1);
-
-            let scattered = Ray::new(rec.p, mixed.generate(), r.tm);
-            let pdf_val = mixed.value(scattered.dir);
+    print!("{}[2J", 27 as char); // Clear screen
+    print!("{esc}[2J{esc}[1;1H", esc = 27 as char); // Set cursor position as 1,1
 
-            // 二者叠加
-            emitted
-                + srec.attenuation
-                    * rec.mat.scattering_pdf(r, rec, scattered)
-                    * ray_color(scattered, background, world, lights, depth - 1)
-                    / pdf_val
-        } else {
-            // Dissuse light 不会散射，只发光
-            emitted
-        }
+    // 设定图片内容，场景只构造一次，由所有线程共享
+    let scene = match &options.scene_file {
+        Some(file) => match scene::loader::load_scene_file(file, options.seed) {
+            Ok(s) => s,
+            Err(e) => fail(&format!("{}: {}", file, e)),
+        },
+        // 名字已经在解析命令行时检查过
+        None => (builtin::find(&options.scene).unwrap().build)(options.seed),
+    };
+    let settings = scene.settings;
+    let world = scene.world;
+    let lights = scene.lights;
+    let mut cam_config = scene.camera;
+    let background = scene.background;
+    // 断点中记录场景，防止用别的场景的断点继续渲染
+    let scene_name = match &options.scene_file {
+        Some(file) => file.clone(),
+        None => options.scene.clone(),
+    };
+
+    // 光谱模式只实现了路径追踪，断点中记录为不同的积分器
+    if options.spectral && options.integrator != IntegratorKind::Path {
+        fail("--spectral only works with --integrator path");
+    }
+    // MLT 的链按全部像素的采样次数划分，不能跳过已经收敛的像素
+    if options.integrator == IntegratorKind::Mlt && options.adaptive.is_some() {
+        fail("--adaptive does not work with --integrator mlt");
+    }
+    // 调试视图各不相同，也记录在断点中
+    let integrator_name = if options.spectral {
+        "path-spectral".to_string()
+    } else if options.integrator == IntegratorKind::Debug {
+        format!("debug {}", options.debug_view.name())
     } else {
-        //没碰到物体，就返回背景的颜色
-        // let unit_dir = Vec3::unit_vector(r.dir);
-        // let t = 0.5 * (unit_dir.y + 1.);
-        // Vec3::new(1., 1., 1.) * (1. - t) + Vec3::new(0.5, 0.7, 1.) * t //渐变色
-        background
+        options.integrator.name().to_string()
+    };
+
+    // 像素重建滤波器，断点中记录它的名字和半径
+    let filter_radius = options
+        .filter_radius
+        .unwrap_or_else(|| options.filter.default_radius());
+    let filter = options.filter.build(filter_radius);
+    let filter_name = format!("{} {}", options.filter.name(), filter_radius);
+
+    // 命令行中指定的相机参数优先
+    if let Some(v) = options.lookfrom {
+        cam_config.lookfrom = v;
     }
-}
-
-fn add_cornell_lights() -> HittableList {
-    let mut lights: HittableList = Default::default();
-
-    let light = DiffuseLight::new_from_color(Vec3::new(15., 15., 15.));
-    lights.add(XZRect::new(213., 343., 227., 332., 554., light));
-
-    lights
-}
-
-fn add_book2_lights() -> HittableList {
-    let mut lights: HittableList = Default::default();
-
-    let light = DiffuseLight::new_from_color(Vec3::new(7., 7., 7.));
-    lights.add(XZRect::new(123., 423., 147., 412., 554., light));
-
-    lights
-}
-
-fn scene_book2() -> HittableList {
-    let mut boxes1: HittableList = Default::default();
-    let mut boxes2: HittableList = Default::default();
-    let mut world: HittableList = Default::default();
-
-    let ground = Lambertian::new_from_color(Vec3::new(0.48, 0.83, 0.53));
-    // 生成凹凸的地面
-    let boxes_per_side = 20;
-    let mut rng = StdRng::seed_from_u64(19260817); // 从特定的种子生成
-    for i in 0..boxes_per_side {
-        for j in 0..boxes_per_side {
-            let w = 100.;
-            let x0 = -1000. + i as f64 * w;
-            let z0 = -1000. + j as f64 * w;
-            let y0 = 0.;
-            let x1 = x0 + w;
-            let y1 = rng.gen_range(1.0..101.0);
-            // 伪随机，保证每次画出的图形相同
-            let z1 = z0 + w;
-
-            boxes1.add(Cube::new(
-                Vec3::new(x0, y0, z0),
-                Vec3::new(x1, y1, z1),
-                ground,
-            ));
-        }
+    if let Some(v) = options.lookat {
+        cam_config.lookat = v;
     }
-    world.add(BvhNode::new_from_list(boxes1, 0., 1.));
-
-    // 顶部的矩形光源
-    let light = DiffuseLight::new_from_color(Vec3::new(7., 7., 7.));
-    let reverse = Flipface::new(XZRect::new(123., 423., 147., 412., 554., light));
-    world.add(reverse);
-
-    let center1 = Vec3::new(400., 400., 200.);
-    let center2 = center1 + Vec3::new(30., 0., 0.);
-    let moving_sphere_material = Lambertian::new_from_color(Vec3::new(0.7, 0.3, 0.1));
-    world.add(MovingSphere::new(
-        50.,
-        center1,
-        center2,
-        0.,
-        1.,
-        moving_sphere_material,
-    ));
-
-    // let aluminum = Metal::new(Vec3::new(0.8, 0.85, 0.88), 0.);
-    world.add(Sphere::new(
-        Vec3::new(260., 150., 45.),
-        50.,
-        Dielectric::new(1.5),
-    ));
-    world.add(Sphere::new(
-        Vec3::new(0., 150., 145.),
-        50.,
-        Metal::new(Vec3::new(0.8, 0.8, 0.9), 1.),
-    ));
-
-    // 加上黑雾作为背景
-    let mut boundary = Sphere::new(Vec3::new(360., 150., 145.), 70., Dielectric::new(1.5));
-    world.add(boundary); // 额外加入一个雾化的球（恰好在中央）
-    world.add(ConstantMedium::new_from_color(
-        boundary,
-        0.2,
-        Vec3::new(0.2, 0.4, 0.9),
-    ));
-    boundary = Sphere::new(Vec3::new(0., 0., 0.), 5000., Dielectric::new(1.5));
-    world.add(ConstantMedium::new_from_color(
-        boundary,
-        0.0001,
-        Vec3::new(1., 1., 1.),
-    ));
-
-    // 地球贴图的球体
-    let emat = Lambertian::new(ImageTexture::new_from_file("import_pic/earthmap.jpg"));
-    world.add(Sphere::new(Vec3::new(400., 200., 400.), 100., emat));
-    let pertext = NoiseTexture::new(Perlin::new(), 0.1);
-    world.add(Sphere::new(
-        Vec3::new(220., 280., 300.),
-        80.,
-        Lambertian::new(pertext),
-    )); // 加上频率为 0.1 的噪声纹理，变为蓝色的球
-
-    let white = Lambertian::new_from_color(Vec3::new(0.73, 0.73, 0.73));
-    let ns = 1000; //小球个数
-    for _j in 0..ns {
-        boxes2.add(Sphere::new(
-            Vec3 {
-                x: rng.gen_range(0.0..165.0),
-                y: rng.gen_range(0.0..165.0),
-                z: rng.gen_range(0.0..165.0),
-            }, // 伪随机
-            10.,
-            white,
-        ));
+    if let Some(v) = options.vup {
+        cam_config.vup = v;
     }
-    // 由小球组成的立方体，旋转+平移
-    world.add(Translate::new(
-        RotateY::new(BvhNode::new_from_list(boxes2, 0., 1.), 15.),
-        Vec3::new(-100., 270., 395.),
-    ));
-
-    world
-}
-
-fn cornell_box() -> HittableList {
-    let mut world: HittableList = Default::default();
-
-    let red = Lambertian::new_from_color(Vec3::new(0.65, 0.05, 0.05));
-    let white = Lambertian::new_from_color(Vec3::new(0.73, 0.73, 0.73));
-    let green = Lambertian::new_from_color(Vec3::new(0.12, 0.45, 0.15));
-    let light = DiffuseLight::new_from_color(Vec3::new(15., 15., 15.));
-    // 用颜色来控制亮度？
-
-    world.add(YZRect::new(0., 555., 0., 555., 555., green));
-    world.add(YZRect::new(0., 555., 0., 555., 0., red));
-    world.add(XZRect::new(0., 555., 0., 555., 0., white));
-    world.add(XZRect::new(0., 555., 0., 555., 555., white));
-    world.add(XYRect::new(0., 555., 0., 555., 555., white));
-
-    world.add(Flipface::new(XZRect::new(
-        213., 343., 227., 332., 554., light,
-    )));
-
-    let aluminum = Metal::new(Vec3::new(0.8, 0.85, 0.88), 0.);
-    let box1 = Cube::new(Vec3::new(0., 0., 0.), Vec3::new(165., 330., 165.), aluminum);
-    // 先旋转再平移
-    let rt1 = RotateY::new(box1, 15.); //旋转后的立方体 rt1
-    let tr1 = Translate::new(rt1, Vec3::new(265., 0., 295.)); //平移后的立方体 tr1
-    world.add(tr1);
-    // 同理
-    let box2 = Cube::new(Vec3::new(0., 0., 0.), Vec3::new(165., 165., 165.), white);
-    let rt2 = RotateY::new(box2, -18.);
-    let tr2 = Translate::new(rt2, Vec3::new(130., 0., 65.));
-    world.add(tr2);
-
-    let obj = STL::load_from_file("import_pic/someobj/astronaut.stl", 0., 1., red);
-    let t1 = Zoom::new(obj, Vec3::new(3., 3., 3.));
-    let t2 = RotateX::new(t1, 90.);
-    let t3 = RotateY::new(t2, 180.);
-    let t4 = Translate::new(t3, Vec3::new(450., 350., 400.));
-    world.add(t4);
-
-    world
-}
-
-fn add_my_lights() -> HittableList {
-    let mut lights: HittableList = Default::default();
-
-    let light = DiffuseLight::new_from_color(Vec3::new(15., 15., 15.));
-    lights.add(Cube::new(
-        Vec3::new(213., 530., 127.),
-        Vec3::new(343., 550., 232.),
-        light,
-    ));
-    lights.add(XYRect::new(50., 505., 50., 382., -801., light));
-
-    lights
-}
-
-fn my_scene() -> HittableList {
-    let mut world: HittableList = Default::default();
-
-    let white = Lambertian::new_from_color(Vec3::new(0.73, 0.73, 0.73));
-    let light = DiffuseLight::new_from_color(Vec3::new(15., 15., 15.));
-
-    world.add(YZRect::new(
-        -100.,
-        620.,
-        -400.,
-        785.,
-        855.,
-        Lambertian::new(ImageTexture::new_from_file("import_pic/vapor.png")),
-    ));
-    world.add(YZRect::new(
-        -100.,
-        620.,
-        -400.,
-        785.,
-        -300.,
-        Lambertian::new(ImageTexture::new_from_file("import_pic/disco.png")),
-    ));
-    // 遮挡层，为了实现金属反光
-    world.add(YZRect::new(-100., 620., -802., 100., -299., white));
-    world.add(YZRect::new(-100., 620., -802., 100., 854., white));
-
-    world.add(Flipface::new(Cube::new(
-        Vec3::new(213., 530., 127.),
-        Vec3::new(343., 550., 232.),
-        light,
-    )));
-    world.add(XYRect::new(50., 505., 50., 382., -801., light));
-    world.add(XYRect::new(-200., 755., 0., 555., -802., white));
-
-    world.add(XZRect::new(-200., 755., -802., 555., 0., white));
-    world.add(XZRect::new(-200., 755., -802., 555., 555., white));
-    world.add(XYRect::new(
-        -200.,
-        755.,
-        0.,
-        555.,
-        500.,
-        Lambertian::new(ImageTexture::new_from_file("import_pic/cyberpunk.png")),
-    ));
-
-    let tp_obj = OBJ::load_from_file("import_pic/someobj/thomas.obj", 0., 1.);
-    let tp1 = Zoom::new(tp_obj, Vec3::new(35., 35., 35.));
-    let tp2 = RotateY::new(tp1, 220.);
-    let tp3 = RotateX::new(tp2, 0.);
-    let tp4 = RotateZ::new(tp3, 0.);
-    let tp5 = Translate::new(tp4, Vec3::new(500., 100., 300.));
-    world.add(tp5);
-
-    let tp_obj2 = OBJ::load_from_file("import_pic/someobj/guy.obj", 0., 1.);
-    let t1 = Zoom::new(tp_obj2, Vec3::new(20., 20., 20.));
-    let t2 = RotateY::new(t1, 130.);
-    let t3 = Translate::new(t2, Vec3::new(0., 50., 150.));
-    world.add(t3);
-
-    world
-}
-
-fn simple_light() -> HittableList {
-    let mut world: HittableList = Default::default();
-
-    let mat1 = Lambertian::new(NoiseTexture::new(Perlin::new(), 4.));
-    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., mat1));
-    world.add(Sphere::new(Vec3::new(0., 2., 0.), 2., mat1));
-
-    let mat2 = DiffuseLight::new_from_color(Vec3::new(4., 4., 4.));
-    world.add(XYRect::new(3., 5., 1., 3., -2., mat2));
-    world.add(Sphere::new(Vec3::new(0., 7., 0.), 2., mat2));
-
-    world
-}
-
-fn earth() -> HittableList {
-    let mut world: HittableList = Default::default();
-    let image = ImageTexture::new_from_file("import_pic/earthmap.jpg");
-    let mat1 = Lambertian::new(image);
-
-    world.add(Sphere::new(Vec3::new(0., 0., 0.), 2., mat1));
-
-    world
-}
-
-fn two_spheres() -> HittableList {
-    let mut world: HittableList = Default::default();
-    // let checker = CheckerTexture {
-    //     odd : SolidColor::new(0.2, 0.3, 0.1),
-    //     even : SolidColor::new(0.9, 0.9, 0.9),
-    // };
-    let mat1 = Lambertian::new(NoiseTexture::new(Perlin::new(), 4.));
-
-    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., mat1));
-    world.add(Sphere::new(Vec3::new(0., 2., 0.), 2., mat1));
-
-    world
-}
-
-fn random_scene() -> HittableList {
-    let mut world: HittableList = Default::default();
-
-    let checker = CheckerTexture {
-        odd: SolidColor::new(0.2, 0.3, 0.1),
-        even: SolidColor::new(0.9, 0.9, 0.9),
-    }; //棋盘状的纹理
-    let ground_material = Lambertian::new(checker);
+    if let Some(v) = options.vfov {
+        cam_config.vfov = v;
+    }
+    if let Some(v) = options.aperture {
+        cam_config.aperture = v; // 光圈，用来控制虚化
+    }
+    if let Some(v) = options.focus_dist {
+        cam_config.focus_dist = v;
+    }
+    // 每一个像素点由多少次光线来确定
+    let max_depth = options.max_depth.unwrap_or(settings.max_depth);
 
-    world.add(Sphere::new(
-        Vec3::new(0., -1000., 0.),
-        1000.,
-        ground_material,
-    ));
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = random_double(0., 1.);
-            let _center = Vec3::new(
-                a as f64 + random_double(0., 1.),
-                0.2,
-                b as f64 + random_double(0., 1.),
-            );
+    // 断点中记录影响采样结果的全部设置，恢复时逐项比较
+    let scene_hash = match &options.scene_file {
+        Some(file) => match fs::read(file) {
+            Ok(bytes) => checkpoint::content_hash(&bytes),
+            Err(e) => fail(&format!("{}: {}", file, e)),
+        },
+        None => 0,
+    };
+    let render_info = RenderInfo {
+        scene: scene_name,
+        scene_hash,
+        seed: options.seed,
+        filter: filter_name,
+        sampler: options.sampler.name().to_string(),
+        integrator: integrator_name,
+        settings: render_settings(&options, &cam_config, max_depth),
+    };
+
+    // 从断点继续时，图像大小沿用断点中的设置
+    let resumed = options.resume.as_ref().map(|path| {
+        let ckpt = checkpoint::load(path).unwrap_or_else(|e| fail(&e));
+        if let Some(e) = ckpt.info.mismatch(&render_info) {
+            fail(&format!("checkpoint `{}` {}", path, e));
+        }
+        ckpt.film
+    });
 
-            if (_center - Vec3::new(4., 0.2, 0.)).length() > 0.9 {
-                if choose_mat < 0.8 {
-                    //diffuse
-                    let _albedo = Vec3::random(0., 1.) * Vec3::random(0., 1.);
-                    //随机生成小球的反照率
-                    //用反照率对应生成'纹理'颜色
-                    let sphere_material = Lambertian::new_from_color(_albedo);
-                    let _center2 = _center + Vec3::new(0., random_double(0., 0.5), 0.);
-                    // world.add(sphere::Sphere {
-                    //     center: _center,
-                    //     radius: 0.2,
-                    //     mat: sphere_material,
-                    // });
-                    world.add(MovingSphere::new(
-                        0.2,
-                        _center,
-                        _center2,
-                        0.,
-                        1.,
-                        sphere_material,
-                    ));
-                } else if choose_mat < 0.95 {
-                    //metal
-                    let _albedo = Vec3::random(0.5, 1.);
-                    let _fuzz = random_double(0., 0.5);
-                    let sphere_material = Metal::new(_albedo, _fuzz);
-                    world.add(Sphere::new(_center, 0.2, sphere_material));
-                } else {
-                    //glass
-                    let sphere_material = Dielectric { ir: 1.5 };
-                    world.add(Sphere::new(_center, 0.2, sphere_material));
+    // ----------------------设定图像的内容-------------------------
+    // 命令行中的参数优先，否则使用场景推荐的参数
+    let (width, height) = match &resumed {
+        Some(film) => (film.width, film.height),
+        None => {
+            let width = options.width.unwrap_or(settings.width);
+            let height = match options.height {
+                Some(h) => h,
+                // 只指定了宽度时，保持场景的长宽比
+                None if options.width.is_some() => {
+                    ((width as f64 * settings.height as f64 / settings.width as f64).round() as u32)
+                        .max(2)
                 }
-            }
+                None => settings.height,
+            };
+            (width, height)
         }
+    };
+    if options.width.unwrap_or(width) != width || options.height.unwrap_or(height) != height {
+        fail(&format!(
+            "the checkpoint has size {}x{}, which differs from --width/--height",
+            width, height
+        ));
+    }
+    let aspect_ratio = width as f64 / height as f64;
+    let path = options.output.clone();
+    let output_options = OutputOptions {
+        quality: options.quality, // From 0 to 100
+        bit_depth: options.bit_depth,
+        ppm_ascii: options.ppm_ascii,
+        exr_compression: options.exr_compression,
+        // 调试视图的颜色是法线、深度等数据，按原样写入
+        tone_map: if options.integrator == IntegratorKind::Debug {
+            ToneMap::identity()
+        } else {
+            options.tone_map
+        },
+    };
+    if let Err(e) = output::check_options(&path, &output_options) {
+        fail(&e);
     }
 
-    let mat_1 = Dielectric::new(1.5);
-    world.add(Sphere::new(Vec3::new(0., 1., 0.), 1., mat_1));
-    let mat_2 = Lambertian::new_from_color(Vec3::new(0.4, 0.2, 0.1));
-    world.add(Sphere::new(Vec3::new(-4., 1., 0.), 1., mat_2));
-    let mat_3 = Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.);
-    world.add(Sphere::new(Vec3::new(4., 1., 0.), 1., mat_3));
-
-    world
-}
-
-fn main() {
-    print!("{}[2J", 27 as char); // Clear screen
-    print!("{esc}[2J{esc}[1;1H", esc = 27 as char); // Set cursor position as 1,1
-
-    // ----------------------设定图像的内容-------------------------
-    let aspect_ratio = 1.;
-    let width = 800;
-    let height = (width as f64 / aspect_ratio) as u32;
-    let quality = 100; // From 0 to 100
-    let path = "output/output.jpg";
-
-    let samples_per_pixel = 50;
-    // 每一个像素点由多少次光线来确定
-    let max_depth = 50;
-
-    let lookfrom = Vec3::new(278., 278., -800.);
-    let lookat = Vec3::new(278., 278., 0.);
-    let vup = Vec3::new(0., 1., 0.);
-    let aperture = 0.; // 光圈，用来控制虚化
-    let dist_to_focus = 10.;
-    let background = Vec3::new(0., 0., 0.);
-    // 黑色的背景
+    let samples_per_pixel = options
+        .samples_per_pixel
+        .unwrap_or(settings.samples_per_pixel);
+    let denoise = options.denoise.unwrap_or(settings.denoise);
+
+    // 渲染之前检查所有要写的文件：主图像、AOV、降噪后的图像和热力图
+    let mut outputs: Vec<String> = options
+        .aovs
+        .iter()
+        .map(|a| output::aov_path(&path, a.name()))
+        .collect();
+    outputs.push(path.clone());
+    if denoise {
+        outputs.push(output::denoised_path(&path));
+    }
+    for p in outputs.iter() {
+        output::check_path(p, &output::EXTENSIONS).unwrap_or_else(|e| fail(&e));
+    }
+    if let Some(heatmap) = &options.heatmap {
+        output::check_path(heatmap, &["png"]).unwrap_or_else(|e| fail(&e));
+    }
 
-    let cam: Camera = Camera::new(
-        lookfrom,
-        lookat,
-        vup,
-        40.,
-        aspect_ratio,
-        aperture,
-        dist_to_focus,
-        0.,
-        1.,
-    );
+    let world = Arc::new(world);
+    let lights = Arc::new(lights);
 
-    if false {
-        my_scene();
-        scene_book2();
-        cornell_box();
-        random_scene();
-        two_spheres();
-        earth();
-        simple_light();
-        add_book2_lights();
-        add_cornell_lights();
-        add_my_lights();
-    } //用来防止报错
+    let cam = cam_config.build(aspect_ratio);
 
     //------------------------------输出图像的特定信息-----------------------------
     println!(
-        "Image size: {}\nJPEG quality: {}\nSamples_per_pixel: {}",
+        "Image size: {}\nOutput: {}\nSamples_per_pixel: {}",
         style(width.to_string() + &"x".to_string() + &height.to_string()).yellow(),
-        style(quality.to_string()).yellow(),
+        style(&path).yellow(),
         style(samples_per_pixel).yellow(),
     );
+    if let Some(adaptive) = options.adaptive {
+        println!(
+            "Adaptive sampling: {} to {} samples, threshold {}",
+            style(adaptive.min_samples.min(samples_per_pixel as u32)).yellow(),
+            style(samples_per_pixel).yellow(),
+            style(adaptive.threshold).yellow(),
+        );
+    }
 
-    // Create image data
-    let mut img: RgbImage = ImageBuffer::new(width, height);
+    // 累加颜色的帧缓冲
+    let mut film = match resumed {
+        Some(film) => {
+            println!(
+                "Resuming from {} samples per pixel",
+                style(film.min_samples()).yellow()
+            );
+            film
+        }
+        None => Film::new(width, height),
+    };
 
     //-------------------------多线程部分---------------------------
 
-    println!("Multi-threading!");
     // let begin_time = Instant::now();
-    let thread_number = 16; // 线程数
-
-    let section_line_number = height / thread_number; // 每个线程处理的行数
-    let mut thread_pool = Vec::<_>::new(); // 进程池
-    let mut output_pixel_color = Vec::new(); // 画出的像素颜色
-
-    let multi_progress = Arc::new(MultiProgress::new()); // 多个进度条
-    multi_progress.set_move_cursor(true);
+    // 线程数，默认使用全部的 CPU 核心
+    let thread_number = options.thread_number.unwrap_or_else(num_cpus::get);
+    println!(
+        "Multi-threading with {} threads!",
+        style(thread_number).yellow()
+    );
 
-    for thread_id in 0..thread_number {
-        // 计算出行首与行末的编号
-        let line_begin = section_line_number * thread_id;
-        let mut line_end = line_begin + section_line_number;
-        if line_end > height || (thread_id == thread_number - 1 && line_end < height) {
-            // 不足的最后一行，自动补齐
-            line_end = height;
+    let seed = options.seed;
+    let aov_world = world.clone();
+    // 分层采样按目标的采样次数分层
+    let sampler_config = SamplerConfig::new(options.sampler, seed, samples_per_pixel as u32);
+    let aov_sampler = sampler_config.clone();
+    let tracer = PathTracer {
+        max_depth,
+        rr_depth: options.rr_depth,
+        heuristic: options.mis,
+        world: world.clone(),
+        lights: lights.clone(),
+        env: background.clone(),
+    };
+    let integrator: Arc<dyn Integrator> = match options.integrator {
+        IntegratorKind::Path if options.spectral => Arc::new(SpectralTracer::new(tracer)),
+        IntegratorKind::Path => Arc::new(tracer),
+        IntegratorKind::Bdpt => Arc::new(Bdpt {
+            max_depth,
+            rr_depth: options.rr_depth,
+            heuristic: options.mis,
+            camera: cam,
+            width,
+            height,
+            world,
+            lights,
+            env: background,
+        }),
+        IntegratorKind::Ppm => {
+            let mapper = PhotonMapper::new(
+                tracer,
+                options.photons,
+                options.samples_per_pass,
+                options.photon_radius,
+                seed,
+                (cam.time0, cam.time1),
+            );
+            println!("Photon radius: {}", style(mapper.radius()).yellow());
+            Arc::new(mapper)
         }
-
-        // 设定图片内容
-        // 要保证每次都能生成相同的图片，即部分伪随机
-        let world: HittableList = cornell_box();
-        let lights: HittableList = add_cornell_lights();
-
-        // 设置进度条
-        let mp = multi_progress.clone();
-        let progress_bar = mp.add(ProgressBar::new(((line_end - line_begin) * width) as u64));
-        progress_bar.set_style(ProgressStyle::default_bar()
-                                .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] [{pos}/{len}] ({eta})")
-                                .progress_chars("#>-"));
-
-        //-------------------- 内部的线程 -----------------------------------------------------
-        let (tx, rx) = mpsc::channel(); //信道
-        thread_pool.push((
-            thread::spawn(move || {
-                progress_bar.set_position(0);
-
-                let channel_send = tx;
-                let mut section_pixel_color = Vec::new(); // 临时记录线程的计算结果
-
-                // 计算通过某一像素点的颜色
-                for y in line_begin..line_end {
-                    for x in 0..width {
-                        let mut color = Vec3::new(0., 0., 0.);
-                        for _s in 0..samples_per_pixel {
-                            // 抗锯齿
-                            let u = (x as f64 + random_double(0., 1.)) / (width - 1) as f64;
-                            let v = (y as f64 + random_double(0., 1.)) / (height - 1) as f64;
-
-                            let r = cam.get_ray(u, v); //多次求通过该像素的光线
-                            color += ray_color(r, background, &world, &lights, max_depth);
-                        }
-                        section_pixel_color.push(color); // 记录该线程计算出的颜色
-
-                        progress_bar.inc(1);
-                    }
-                }
-                channel_send.send(section_pixel_color).unwrap(); // 通过信道把结果向外传递
-                progress_bar.finish_with_message("Finished!");
-            }),
-            rx,
-        ));
+        IntegratorKind::Mlt => {
+            let mlt = Mlt::new(
+                tracer,
+                cam,
+                (width, height),
+                samples_per_pixel as u32,
+                options.mlt,
+                seed,
+                thread_number,
+            )
+            .unwrap_or_else(|| fail("estimating the MLT normalization failed"));
+            println!("MLT normalization: {}", style(mlt.normalization()).yellow());
+            Arc::new(mlt)
+        }
+        IntegratorKind::Debug => Arc::new(DebugIntegrator::new(
+            options.debug_view,
+            world,
+            cam,
+            options.ao_radius,
+            options.depth_max,
+            options.bvh_cost_max,
+        )),
+    };
+    let pass_integrator = integrator.clone();
+    let sample = Arc::new(move |x: u32, y: u32, s: u32| {
+        // 每次采样的随机数只由种子、像素位置和采样序号决定，与线程无关
+        let mut sampler = sampler_config.for_pixel(x, y, s);
+
+        // 抗锯齿，采样点在像素内随机分布，再由滤波器分配到周围的像素
+        let (dx, dy) = sampler.get_2d();
+        let u = (x as f64 + dx) / (width - 1) as f64;
+        let v = (y as f64 + dy) / (height - 1) as f64;
+
+        let r = cam.get_ray(u, v, &mut *sampler); //多次求通过该像素的光线
+        let mut splats = Vec::new();
+        let color = integrator.radiance(r, (x, y), s, &mut *sampler, &mut splats);
+        FilmSample {
+            dx,
+            dy,
+            color,
+            splats,
+        }
+    });
+
+    // 每一轮给所有像素增加几次采样，图像被切成小块，线程空闲时从队列中取下一块
+    let progressive = Progressive {
+        samples_per_pixel: samples_per_pixel as u32,
+        samples_per_pass: options.samples_per_pass,
+        adaptive: options.adaptive,
+        filter,
+        thread_number,
+    };
+    let checkpoint_path = options
+        .checkpoint
+        .clone()
+        .or_else(|| options.resume.clone());
+    let begin_pass = |samples| pass_integrator.begin_pass(samples);
+    let result = progressive.render(&mut film, sample, begin_pass, |film, pass| {
+        if let Some(ckpt) = &checkpoint_path {
+            checkpoint::save(ckpt, &render_info, film)?;
+        }
+        if let Some(n) = options.preview_every {
+            if pass % n == 0 {
+                output::save_image(film, &path, &output_options)?;
+            }
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        fail(&e);
     }
-    // 等待所有进程结束，再执行主线程
-    multi_progress.join().unwrap();
 
-    let mut thread_finish_successfully = true;
-    let collecting_progress_bar = ProgressBar::new(thread_number as u64);
-    // 接收信息，修改进度条
-    for thread_id in 0..thread_number {
-        let thread = thread_pool.remove(0);
-        match thread.0.join() {
-            Ok(_) => {
-                let mut received = thread.1.recv().unwrap();
-                output_pixel_color.append(&mut received);
-                collecting_progress_bar.inc(1);
+    // Output image to file
+    println!("Ouput image as \"{}\"", style(&path).yellow());
+    if let Err(e) = output::save_image(&film, &path, &output_options) {
+        // Err(_) => panic!("Outputting image fails."),
+        fail(&e);
+    }
+    // 降噪需要反照率和法线，与 AOV 一起计算
+    if denoise || !options.aovs.is_empty() {
+        let buffers = aov::render(width, height, thread_number, cam, aov_world, aov_sampler)
+            .unwrap_or_else(|| fail("rendering the AOVs failed"));
+        for &a in options.aovs.iter() {
+            let aov_path = output::aov_path(&path, a.name());
+            let aov_film = Film::from_values(width, height, buffers.values(a));
+            println!("Ouput {} as \"{}\"", a.name(), style(&aov_path).yellow());
+            if let Err(e) = output::save_image(&aov_film, &aov_path, &output_options) {
+                fail(&e);
             }
-            Err(_) => {
-                thread_finish_successfully = false;
-                println!(
-                    "Joining the {} thread failed!",
-                    style(thread_id.to_string()).red()
-                );
+        }
+
+        if denoise {
+            let denoised = Denoiser::default()
+                .denoise(&film, &buffers.albedo, &buffers.normal, thread_number)
+                .unwrap_or_else(|| fail("denoising failed"));
+            let denoised_path = output::denoised_path(&path);
+            println!(
+                "Ouput denoised image as \"{}\"",
+                style(&denoised_path).yellow()
+            );
+            let denoised_film = Film::from_values(width, height, denoised);
+            if let Err(e) = output::save_image(&denoised_film, &denoised_path, &output_options) {
+                fail(&e);
             }
         }
     }
-    if !thread_finish_successfully {
-        exit(1);
+    if let Some(heatmap) = &options.heatmap {
+        let result = output::save_heatmap(&film, heatmap, samples_per_pixel as u32);
+        if let Err(e) = result {
+            fail(&e);
+        }
     }
-    collecting_progress_bar.finish_and_clear();
+    // println!(
+    //     "Time used : {}",
+    //     style(HumanDuration(begin_time.elapsed())).yellow()
+    // );
+    // 统计运行时间
 
-    //---------------------------利用计算结果给图像上色----------------------------------
-    let mut pixel_id = 0;
-    for y in 0..height {
-        for x in 0..width {
-            let pixel_color = get_pixel_color(output_pixel_color[pixel_id], samples_per_pixel);
-            let pixel = img.get_pixel_mut(x, height - y - 1);
-            *pixel = image::Rgb(pixel_color);
+    exit(0);
+}
 
-            pixel_id += 1;
-        }
+// 断点中记录的其他设置，键为对应的命令行参数；只记录当前积分器用到的参数
+fn render_settings(
+    options: &RenderOptions,
+    cam: &CameraConfig,
+    max_depth: i32,
+) -> Vec<(String, String)> {
+    let vec = |v: Vec3| format!("{} {} {}", v.x, v.y, v.z);
+    let auto = |v: Option<f64>| v.map_or("auto".to_string(), |v| v.to_string());
+    let mut settings = vec![
+        ("lookfrom", vec(cam.lookfrom)),
+        ("lookat", vec(cam.lookat)),
+        ("vup", vec(cam.vup)),
+        ("vfov", cam.vfov.to_string()),
+        ("aperture", cam.aperture.to_string()),
+        ("focus-dist", cam.focus_dist.to_string()),
+    ];
+    if options.integrator == IntegratorKind::Debug {
+        settings.push(("ao-radius", auto(options.ao_radius)));
+        settings.push(("depth-max", auto(options.depth_max)));
+        settings.push(("bvh-cost-max", options.bvh_cost_max.to_string()));
+    } else {
+        settings.push(("depth", max_depth.to_string()));
+        settings.push(("rr-depth", options.rr_depth.to_string()));
+        settings.push(("mis", options.mis.name().to_string()));
     }
-
-    // Output image to file
-    println!("Ouput image as \"{}\"", style(path).yellow());
-    let output_image = image::DynamicImage::ImageRgb8(img);
-    let mut output_file = File::create(path).unwrap();
-    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
-        Ok(_) => {
-            // println!(
-            //     "Time used : {}",
-            //     style(HumanDuration(begin_time.elapsed())).yellow()
-            // );
-            // 统计运行时间
-        }
-        // Err(_) => panic!("Outputting image fails."),
-        Err(_) => println!("{}", style("Outputting image fails.").red()),
+    if options.integrator == IntegratorKind::Ppm {
+        // 每轮换一张光子图，每轮的采样次数也决定了用哪张图
+        settings.push(("photons", options.photons.to_string()));
+        settings.push(("photon-radius", auto(options.photon_radius)));
+        settings.push(("pass-spp", options.samples_per_pass.to_string()));
     }
+    if options.integrator == IntegratorKind::Mlt {
+        settings.push(("mlt-bootstrap", options.mlt.bootstrap.to_string()));
+        settings.push(("mlt-chain", options.mlt.chain_length.to_string()));
+        settings.push((
+            "mlt-large-step",
+            options.mlt.large_step_probability.to_string(),
+        ));
+    }
+    settings
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect()
+}
 
-    exit(0);
+fn fail(message: &str) -> ! {
+    println!("{}", style(message).red());
+    exit(1);
 }
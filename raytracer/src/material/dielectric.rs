@@ -1,17 +1,44 @@
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
     hittable::HitRecord,
     material::{Material, ScatterRecord},
     optimization::pdf::CosinePDF,
-    utility::{min_f64, random_double},
+    utility::min_f64,
 };
 
 #[derive(Clone, Copy)]
 pub struct Dielectric {
     //电介质
     pub ir: f64, //折射系数
+    pub dispersion: Dispersion,
 }
 
+// 折射率随波长的变化，只在光谱模式下起作用，波长以 μm 为单位代入
+#[derive(Clone, Copy)]
+pub enum Dispersion {
+    None,
+    Cauchy { a: f64, b: f64 },              // n = a + b / λ^2
+    Sellmeier { b: [f64; 3], c: [f64; 3] }, // n^2 = 1 + Σ b λ^2 / (λ^2 - c)
+}
+
+impl Dispersion {
+    pub fn ior(&self, lambda_nm: f64) -> Option<f64> {
+        let l2 = (lambda_nm * 1e-3) * (lambda_nm * 1e-3);
+        match *self {
+            Dispersion::None => None,
+            Dispersion::Cauchy { a, b } => Some(a + b / l2),
+            Dispersion::Sellmeier { b, c } => {
+                // 系数由场景加载时检查，在波长范围内 n^2 >= 1
+                let n2 = 1. + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f64>();
+                Some(n2.sqrt())
+            }
+        }
+    }
+}
+
+// RGB 模式下使用钠黄线（589.3 nm）处的折射率
+const D_LINE: f64 = 589.3;
+
 impl Dielectric {
     pub fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
         //利用 Schlick's approximation 进行估计
@@ -22,10 +49,16 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in: Ray, rec: HitRecord) -> Option<ScatterRecord> {
-        let mut refraction_ratio = self.ir;
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        let ir = self.ir_at(r_in.wavelength);
+        let mut refraction_ratio = ir;
         if rec.front_face {
-            refraction_ratio = 1. / self.ir;
+            refraction_ratio = 1. / ir;
         }
 
         let unit_dir = Vec3::unit_vector(r_in.dir);
@@ -37,7 +70,7 @@ impl Material for Dielectric {
         //计算出的正弦值大于1，说明该光线不能折射
         let _dir: Vec3;
         if cannot_refract
-            || Dielectric::reflectance(cos_theta, refraction_ratio) > random_double(0., 1.)
+            || Dielectric::reflectance(cos_theta, refraction_ratio) > sampler.random_double(0., 1.)
         {
             _dir = Vec3::reflect(unit_dir, rec.normal);
         } else {
@@ -52,10 +85,38 @@ impl Material for Dielectric {
             is_specular: true,
         })
     }
+
+    // 透明的材质不吸收光，反照率为白色
+    fn albedo(&self, _rec: HitRecord) -> Vec3 {
+        Vec3::new(1., 1., 1.)
+    }
+
+    fn dispersive(&self) -> bool {
+        !matches!(self.dispersion, Dispersion::None)
+    }
 }
 
 impl Dielectric {
     pub fn new(c: f64) -> Self {
-        Self { ir: c }
+        Self {
+            ir: c,
+            dispersion: Dispersion::None,
+        }
+    }
+
+    pub fn new_with_dispersion(dispersion: Dispersion) -> Self {
+        Self {
+            ir: dispersion.ior(D_LINE).unwrap_or(1.5),
+            dispersion,
+        }
+    }
+
+    // 波长为 0（RGB 模式）或没有色散时使用固定的折射率
+    pub fn ir_at(&self, lambda: f64) -> f64 {
+        if lambda > 0. {
+            self.dispersion.ior(lambda).unwrap_or(self.ir)
+        } else {
+            self.ir
+        }
     }
 }
@@ -1,8 +1,11 @@
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
     hittable::HitRecord,
-    material::{Material, ScatterRecord},
-    optimization::pdf::CosinePDF,
+    material::{
+        phase::IsotropicPhase,
+        volume::{phase_pdf, scatter_phase},
+        Material, ScatterRecord,
+    },
     texture::{solid::SolidColor, Texture},
 };
 
@@ -16,14 +19,22 @@ where
 }
 
 impl<T: Texture> Material for Isotropic<T> {
-    fn scatter(&self, r_in: Ray, rec: HitRecord) -> Option<ScatterRecord> {
-        Some(ScatterRecord {
-            scattered: Ray::new(rec.p, Vec3::random_vec_in_unit_sphere(), r_in.tm),
-            attenuation: self.albedo.get_color_value(rec.u, rec.v, rec.p),
-            cos_pdf: CosinePDF::empty(),
-            pdf_type: 0,
-            is_specular: true,
-        })
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        let albedo = self.albedo.get_color_value(rec.u, rec.v, rec.p);
+        Some(scatter_phase(&IsotropicPhase, albedo, r_in, rec, sampler))
+    }
+
+    fn scattering_pdf(&self, r_in: Ray, _rec: HitRecord, scattered: Ray) -> f64 {
+        phase_pdf(&IsotropicPhase, r_in, scattered)
+    }
+
+    fn albedo(&self, rec: HitRecord) -> Vec3 {
+        self.albedo.get_color_value(rec.u, rec.v, rec.p)
     }
 }
 
@@ -1,7 +1,7 @@
 use std::f64::consts::PI;
 
 use crate::{
-    basic_component::{onb::ONB, ray::Ray, vec3::Vec3},
+    basic_component::{onb::ONB, ray::Ray, sampler::Sampler, vec3::Vec3},
     hittable::HitRecord,
     material::{Material, ScatterRecord},
     optimization::pdf::CosinePDF,
@@ -19,7 +19,12 @@ where
 }
 
 impl<T: Texture> Material for Lambertian<T> {
-    fn scatter(&self, _r_in: Ray, rec: HitRecord) -> Option<ScatterRecord> {
+    fn scatter(
+        &self,
+        _r_in: Ray,
+        rec: HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
         // let mut scatter_dir = rec.normal + Vec3::random_unit_vector();
 
         //防止 scatter_dir 相加后恰好为 0 向量
@@ -30,7 +35,7 @@ impl<T: Texture> Material for Lambertian<T> {
         // 加上 pdf，选择 importance sampling
 
         let uvw = ONB::build_from_w(rec.normal);
-        let dir = uvw.local_from_vec(Vec3::random_cosine_direction());
+        let dir = uvw.local_from_vec(Vec3::random_cosine_direction(sampler));
         Some(ScatterRecord {
             scattered: Ray::new(rec.p, Vec3::unit_vector(dir), _r_in.tm),
             attenuation: self.albedo.get_color_value(rec.u, rec.v, rec.p),
@@ -48,6 +53,10 @@ impl<T: Texture> Material for Lambertian<T> {
             cosine / PI
         }
     }
+
+    fn albedo(&self, rec: HitRecord) -> Vec3 {
+        self.albedo.get_color_value(rec.u, rec.v, rec.p)
+    }
 }
 
 impl<T: Texture> Lambertian<T> {
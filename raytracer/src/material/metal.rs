@@ -1,5 +1,5 @@
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
     hittable::HitRecord,
     material::{Material, ScatterRecord},
     optimization::pdf::CosinePDF,
@@ -12,11 +12,16 @@ pub struct Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: Ray, rec: HitRecord) -> Option<ScatterRecord> {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
         let reflected = Vec3::reflect(Vec3::unit_vector(r_in.dir), rec.normal);
         let _scattered = Ray::new(
             rec.p,
-            reflected + Vec3::random_vec_in_unit_sphere() * self.fuzz, //模糊化反射
+            reflected + Vec3::random_vec_in_unit_sphere(sampler) * self.fuzz, //模糊化反射
             r_in.tm,
         );
         let _attenuation = self.albedo;
@@ -33,6 +38,10 @@ impl Material for Metal {
             None
         }
     }
+
+    fn albedo(&self, _rec: HitRecord) -> Vec3 {
+        self.albedo
+    }
 }
 
 impl Metal {
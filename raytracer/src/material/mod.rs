@@ -3,16 +3,25 @@ pub mod diffuse_light;
 pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
+pub mod phase;
+pub mod volume;
+
+use std::sync::Arc;
 
 use crate::{
-    basic_component::{ray::Ray, vec3::Vec3},
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
     hittable::HitRecord,
     optimization::pdf::CosinePDF,
 };
 
 pub trait Material: Send + Sync {
     //材料对光线的反射情况
-    fn scatter(&self, _r_in: Ray, _rec: HitRecord) -> Option<ScatterRecord> {
+    fn scatter(
+        &self,
+        _r_in: Ray,
+        _rec: HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
         None
     }
     //材料发出的光的颜色，默认是黑色
@@ -23,6 +32,42 @@ pub trait Material: Send + Sync {
     fn scattering_pdf(&self, _r_in: Ray, _rec: HitRecord, _scattered: Ray) -> f64 {
         0.
     }
+    // 表面本身的颜色（反照率），用于输出 albedo 缓冲，默认是黑色
+    fn albedo(&self, _rec: HitRecord) -> Vec3 {
+        Vec3::new(0., 0., 0.)
+    }
+    // 散射方向是否与波长有关（色散），光谱模式下此时只能保留主波长
+    fn dispersive(&self) -> bool {
+        false
+    }
+}
+
+// 可以在多个物体之间共享的材质，用于运行时构造的场景
+impl Material for Arc<dyn Material> {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        (**self).scatter(r_in, rec, sampler)
+    }
+
+    fn emitted(&self, r_in: Ray, rec: HitRecord, u: f64, v: f64, p: Vec3) -> Vec3 {
+        (**self).emitted(r_in, rec, u, v, p)
+    }
+
+    fn scattering_pdf(&self, r_in: Ray, rec: HitRecord, scattered: Ray) -> f64 {
+        (**self).scattering_pdf(r_in, rec, scattered)
+    }
+
+    fn albedo(&self, rec: HitRecord) -> Vec3 {
+        (**self).albedo(rec)
+    }
+
+    fn dispersive(&self) -> bool {
+        (**self).dispersive()
+    }
 }
 
 pub struct ScatterRecord {
@@ -30,7 +75,7 @@ pub struct ScatterRecord {
     pub attenuation: Vec3, //衰减系数
     pub scattered: Ray,    //反射光
     pub cos_pdf: CosinePDF,
-    pub pdf_type: i32,
+    pub pdf_type: i32,     // 0: 镜面反射，1: cos 分布，2: 介质中按相函数散射
     pub is_specular: bool, // 判断是镜面反射/漫反射
 }
 
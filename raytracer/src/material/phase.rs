@@ -0,0 +1,135 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::basic_component::{onb::ONB, sampler::Sampler, vec3::Vec3};
+
+// 相函数：介质中一点把沿 wo 前进的光散射到 wi 方向的比例，wo、wi 都是单位向量
+// 相函数在球面上积分为 1，所以它本身就是按它采样时的 pdf
+pub trait PhaseFunction: Send + Sync {
+    fn evaluate(&self, wo: Vec3, wi: Vec3) -> f64;
+
+    // 按相函数采样散射方向，返回方向和它的 pdf
+    fn sample(&self, wo: Vec3, sampler: &mut dyn Sampler) -> (Vec3, f64);
+}
+
+impl PhaseFunction for Arc<dyn PhaseFunction> {
+    fn evaluate(&self, wo: Vec3, wi: Vec3) -> f64 {
+        (**self).evaluate(wo, wi)
+    }
+
+    fn sample(&self, wo: Vec3, sampler: &mut dyn Sampler) -> (Vec3, f64) {
+        (**self).sample(wo, sampler)
+    }
+}
+
+// 各个方向均匀散射
+#[derive(Default, Clone, Copy)]
+pub struct IsotropicPhase;
+
+impl PhaseFunction for IsotropicPhase {
+    fn evaluate(&self, _wo: Vec3, _wi: Vec3) -> f64 {
+        1. / (4. * PI)
+    }
+
+    fn sample(&self, wo: Vec3, sampler: &mut dyn Sampler) -> (Vec3, f64) {
+        let cos_theta = 1. - 2. * sampler.get_1d();
+        (around(wo, cos_theta, sampler), 1. / (4. * PI))
+    }
+}
+
+// Henyey-Greenstein：g > 0 时偏向前方（雾中光源周围的光晕），g < 0 时偏向后方，g = 0 即各向同性
+#[derive(Clone, Copy)]
+pub struct HenyeyGreenstein {
+    pub g: f64, // 散射角余弦的平均值，在 (-1, 1) 内
+}
+
+impl PhaseFunction for HenyeyGreenstein {
+    fn evaluate(&self, wo: Vec3, wi: Vec3) -> f64 {
+        hg(Vec3::dot(wo, wi), self.g)
+    }
+
+    fn sample(&self, wo: Vec3, sampler: &mut dyn Sampler) -> (Vec3, f64) {
+        let g = self.g;
+        let u = sampler.get_1d();
+        // 反解累积分布函数
+        let cos_theta = if g.abs() < 1e-3 {
+            1. - 2. * u
+        } else {
+            let s = (1. - g * g) / (1. - g + 2. * g * u);
+            ((1. + g * g - s * s) / (2. * g)).max(-1.).min(1.)
+        };
+        (around(wo, cos_theta, sampler), hg(cos_theta, g))
+    }
+}
+
+impl HenyeyGreenstein {
+    pub fn new(g: f64) -> Self {
+        Self { g }
+    }
+}
+
+// 两个 HG 的混合，一个向前一个向后，更接近云、烟的实测结果
+#[derive(Clone, Copy)]
+pub struct DoubleHenyeyGreenstein {
+    pub forward: HenyeyGreenstein,
+    pub backward: HenyeyGreenstein,
+    pub weight: f64, // forward 所占的比例
+}
+
+impl PhaseFunction for DoubleHenyeyGreenstein {
+    fn evaluate(&self, wo: Vec3, wi: Vec3) -> f64 {
+        self.weight * self.forward.evaluate(wo, wi)
+            + (1. - self.weight) * self.backward.evaluate(wo, wi)
+    }
+
+    // 按比例选一个 HG 采样，pdf 是两者的混合
+    fn sample(&self, wo: Vec3, sampler: &mut dyn Sampler) -> (Vec3, f64) {
+        let (wi, _) = if sampler.get_1d() < self.weight {
+            self.forward.sample(wo, sampler)
+        } else {
+            self.backward.sample(wo, sampler)
+        };
+        (wi, self.evaluate(wo, wi))
+    }
+}
+
+impl DoubleHenyeyGreenstein {
+    pub fn new(g1: f64, g2: f64, weight: f64) -> Self {
+        Self {
+            forward: HenyeyGreenstein::new(g1),
+            backward: HenyeyGreenstein::new(g2),
+            weight,
+        }
+    }
+}
+
+// Rayleigh 散射：远小于波长的粒子（晴朗的空气），前后对称，侧面最弱
+#[derive(Default, Clone, Copy)]
+pub struct Rayleigh;
+
+impl PhaseFunction for Rayleigh {
+    fn evaluate(&self, wo: Vec3, wi: Vec3) -> f64 {
+        let cos_theta = Vec3::dot(wo, wi);
+        3. / (16. * PI) * (1. + cos_theta * cos_theta)
+    }
+
+    fn sample(&self, wo: Vec3, sampler: &mut dyn Sampler) -> (Vec3, f64) {
+        // 累积分布函数 (μ^3 + 3μ + 4) / 8 = u，用卡尔达诺公式解这个三次方程
+        let q = 4. * sampler.get_1d() - 2.;
+        let d = (q * q + 1.).sqrt();
+        let cos_theta = ((q + d).cbrt() + (q - d).cbrt()).max(-1.).min(1.);
+        let wi = around(wo, cos_theta, sampler);
+        (wi, 3. / (16. * PI) * (1. + cos_theta * cos_theta))
+    }
+}
+
+fn hg(cos_theta: f64, g: f64) -> f64 {
+    let denom = 1. + g * g - 2. * g * cos_theta;
+    (1. - g * g) / (4. * PI * denom * denom.sqrt())
+}
+
+// 与 wo 夹角的余弦为 cos_theta、绕 wo 均匀分布的方向
+fn around(wo: Vec3, cos_theta: f64, sampler: &mut dyn Sampler) -> Vec3 {
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let phi = 2. * PI * sampler.get_1d();
+    ONB::build_from_w(wo).local(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
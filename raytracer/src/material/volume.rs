@@ -0,0 +1,82 @@
+use crate::{
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
+    hittable::HitRecord,
+    material::{
+        phase::{IsotropicPhase, PhaseFunction},
+        Material, ScatterRecord,
+    },
+    optimization::pdf::CosinePDF,
+    texture::{solid::SolidColor, Texture},
+};
+
+// 介质中的散射：方向的分布由相函数决定，颜色由反照率决定
+// 不是镜面反射，路径追踪会在这里向光源采样，按相函数的 pdf 做 MIS
+#[derive(Default, Clone)]
+pub struct Volume<T, P>
+where
+    T: Texture,
+    P: PhaseFunction,
+{
+    pub albedo: T,
+    pub phase: P,
+}
+
+impl<T: Texture, P: PhaseFunction> Material for Volume<T, P> {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        let albedo = self.albedo.get_color_value(rec.u, rec.v, rec.p);
+        Some(scatter_phase(&self.phase, albedo, r_in, rec, sampler))
+    }
+
+    fn scattering_pdf(&self, r_in: Ray, _rec: HitRecord, scattered: Ray) -> f64 {
+        phase_pdf(&self.phase, r_in, scattered)
+    }
+
+    fn albedo(&self, rec: HitRecord) -> Vec3 {
+        self.albedo.get_color_value(rec.u, rec.v, rec.p)
+    }
+}
+
+impl<T: Texture, P: PhaseFunction> Volume<T, P> {
+    pub fn new(albedo: T, phase: P) -> Self {
+        Self { albedo, phase }
+    }
+}
+
+impl Volume<SolidColor, IsotropicPhase> {
+    pub fn new_from_color(c: Vec3) -> Self {
+        Self {
+            albedo: SolidColor { color_value: c },
+            phase: IsotropicPhase,
+        }
+    }
+}
+
+// 按相函数采样散射方向，pdf_type 为 2 表示方向的 pdf 由 scattering_pdf 给出
+pub fn scatter_phase<P: PhaseFunction>(
+    phase: &P,
+    albedo: Vec3,
+    r_in: Ray,
+    rec: HitRecord,
+    sampler: &mut dyn Sampler,
+) -> ScatterRecord {
+    let (dir, _) = phase.sample(Vec3::unit_vector(r_in.dir), sampler);
+    ScatterRecord {
+        scattered: Ray::new(rec.p, dir, r_in.tm),
+        attenuation: albedo,
+        cos_pdf: CosinePDF::empty(),
+        pdf_type: 2,
+        is_specular: false,
+    }
+}
+
+pub fn phase_pdf<P: PhaseFunction>(phase: &P, r_in: Ray, scattered: Ray) -> f64 {
+    phase.evaluate(
+        Vec3::unit_vector(r_in.dir),
+        Vec3::unit_vector(scattered.dir),
+    )
+}
@@ -1,9 +1,9 @@
 use std::cmp::Ordering;
 
 use crate::{
-    hittable::{Hittable, HittableList},
+    basic_component::{ray::Ray, sampler::Sampler},
+    hittable::{HitRecord, Hittable, HittableList, TraversalCost},
     optimization::aabb::AABB,
-    utility::random_int,
 };
 
 pub struct BvhNode {
@@ -45,6 +45,97 @@ impl Hittable for BvhNode {
     fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
         Some(self.cube)
     }
+
+    fn hit_surface(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        if !self.cube.hit(r, t_min, t_max) {
+            return None;
+        }
+
+        let mut hit_rec = None;
+        let mut closest_so_far = t_max;
+        let left = self.left.as_ref().unwrap();
+        if let Some(hit_left) = left.hit_surface(r, t_min, closest_so_far) {
+            closest_so_far = hit_left.t;
+            hit_rec = Some(hit_left);
+        }
+        if let Some(right) = self.right.as_ref() {
+            if let Some(hit_right) = right.hit_surface(r, t_min, closest_so_far) {
+                hit_rec = Some(hit_right);
+            }
+        }
+
+        hit_rec
+    }
+
+    fn hit_sampled(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        if !self.cube.hit(r, t_min, t_max) {
+            return None;
+        }
+
+        let mut hit_rec = None;
+        let mut closest_so_far = t_max;
+        let left = self.left.as_ref().unwrap();
+        if let Some(hit_left) = left.hit_sampled(r, t_min, closest_so_far, sampler) {
+            closest_so_far = hit_left.t;
+            hit_rec = Some(hit_left);
+        }
+        if let Some(right) = self.right.as_ref() {
+            if let Some(hit_right) = right.hit_sampled(r, t_min, closest_so_far, sampler) {
+                hit_rec = Some(hit_right);
+            }
+        }
+
+        hit_rec
+    }
+
+    fn transmittance(&self, r: Ray, t_min: f64, t_max: f64, sampler: &mut dyn Sampler) -> f64 {
+        if !self.cube.hit(r, t_min, t_max) {
+            return 1.;
+        }
+        let left = self
+            .left
+            .as_ref()
+            .unwrap()
+            .transmittance(r, t_min, t_max, sampler);
+        match self.right.as_ref() {
+            Some(right) => left * right.transmittance(r, t_min, t_max, sampler),
+            None => left,
+        }
+    }
+
+    fn hit_counted(
+        &self,
+        r: Ray,
+        t_min: f64,
+        t_max: f64,
+        cost: &mut TraversalCost,
+    ) -> Option<HitRecord> {
+        cost.nodes += 1;
+        if !self.cube.hit(r, t_min, t_max) {
+            return None;
+        }
+
+        let mut hit_rec = None;
+        let mut closest_so_far = t_max;
+        let left = self.left.as_ref().unwrap();
+        if let Some(hit_left) = left.hit_counted(r, t_min, closest_so_far, cost) {
+            closest_so_far = hit_left.t;
+            hit_rec = Some(hit_left);
+        }
+        if let Some(right) = self.right.as_ref() {
+            if let Some(hit_right) = right.hit_counted(r, t_min, closest_so_far, cost) {
+                hit_rec = Some(hit_right);
+            }
+        }
+
+        hit_rec
+    }
 }
 
 impl BvhNode {
@@ -107,15 +198,25 @@ impl BvhNode {
         }
     }
 
-    pub fn new_from_list(list: HittableList, time0: f64, time1: f64) -> Self {
-        BvhNode::new_from_vec(list.objects, time0, time1)
+    pub fn new_from_list(
+        list: HittableList,
+        time0: f64,
+        time1: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Self {
+        BvhNode::new_from_vec(list.objects, time0, time1, sampler)
     }
 
     #[allow(clippy::borrowed_box)]
-    pub fn new_from_vec(src_objects: Vec<Box<dyn Hittable>>, time0: f64, time1: f64) -> Self {
+    pub fn new_from_vec(
+        src_objects: Vec<Box<dyn Hittable>>,
+        time0: f64,
+        time1: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Self {
         let mut objects = src_objects;
 
-        let axis = random_int(0, 2) as usize;
+        let axis = sampler.random_int(0, 2) as usize;
         // 从三种比较方式中随机选择
         let cmp = |x: &Box<dyn Hittable>, y: &Box<dyn Hittable>| {
             f64::partial_cmp(
@@ -148,8 +249,12 @@ impl BvhNode {
                 let mut left_vec = objects;
                 let right_vec = left_vec.split_off(object_span / 2);
                 Self::new(
-                    Some(Box::new(Self::new_from_vec(left_vec, time0, time1))),
-                    Some(Box::new(Self::new_from_vec(right_vec, time0, time1))),
+                    Some(Box::new(Self::new_from_vec(
+                        left_vec, time0, time1, sampler,
+                    ))),
+                    Some(Box::new(Self::new_from_vec(
+                        right_vec, time0, time1, sampler,
+                    ))),
                     time0,
                     time1,
                 )
@@ -0,0 +1,192 @@
+// 分段常数的一维分布：把 [0, 1) 等分成 func.len() 段，每段的概率密度与 func 成正比
+pub struct Distribution1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f64>) -> Self {
+        let n = func.len();
+        let mut cdf = vec![0.; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + func[i] / n as f64;
+        }
+        let integral = cdf[n];
+        // 全为 0 时退化为均匀分布
+        for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+            if integral > 0. {
+                *c /= integral;
+            } else {
+                *c = i as f64 / n as f64;
+            }
+        }
+        Self {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    // func 在 [0, 1) 上的积分
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    // 把均匀的 u 映射到这个分布上，返回采样点、它的 pdf 和所在的段
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        // 最后一个 cdf[i] <= u 的段
+        let mut lo = 0;
+        let mut hi = self.count();
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            if self.cdf[mid] <= u {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let width = self.cdf[lo + 1] - self.cdf[lo];
+        let mut du = u - self.cdf[lo];
+        if width > 0. {
+            du /= width;
+        }
+        let x = ((lo as f64 + du) / self.count() as f64).min(1. - f64::EPSILON);
+        (x, self.segment_pdf(lo), lo)
+    }
+
+    pub fn pdf(&self, x: f64) -> f64 {
+        self.segment_pdf(self.segment(x))
+    }
+
+    fn segment(&self, x: f64) -> usize {
+        ((x * self.count() as f64) as usize).min(self.count() - 1)
+    }
+
+    fn segment_pdf(&self, i: usize) -> f64 {
+        if self.integral > 0. {
+            self.func[i] / self.integral
+        } else {
+            1.
+        }
+    }
+}
+
+// [0, 1)^2 上分段常数的二维分布，func 按行存储，每行 width 个值
+// 先按每一行的积分（边缘分布）选出一行，再在这一行内（条件分布）采样
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(func: &[f64], width: usize, height: usize) -> Self {
+        let conditional: Vec<Distribution1D> = (0..height)
+            .map(|y| Distribution1D::new(func[y * width..(y + 1) * width].to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|c| c.integral()).collect());
+        Self {
+            conditional,
+            marginal,
+        }
+    }
+
+    pub fn integral(&self) -> f64 {
+        self.marginal.integral()
+    }
+
+    // 返回采样点 (x, y) 和它的 pdf，y 对应行
+    pub fn sample(&self, u: (f64, f64)) -> ((f64, f64), f64) {
+        let (y, pdf_y, row) = self.marginal.sample(u.1);
+        let (x, pdf_x, _) = self.conditional[row].sample(u.0);
+        ((x, y), pdf_x * pdf_y)
+    }
+
+    pub fn pdf(&self, x: f64, y: f64) -> f64 {
+        let row = &self.conditional[self.marginal.segment(y)];
+        if self.marginal.integral > 0. {
+            row.func[row.segment(x)] / self.marginal.integral
+        } else {
+            1.
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn samples_in_proportion_to_the_function() {
+        let d = Distribution1D::new(vec![1., 3., 0., 4.]);
+        assert_close(d.integral(), 2.);
+        // 分层的 u 落入每一段的次数与函数值成正比，值为 0 的段不会被选中
+        let n = 8000;
+        let mut counts = [0; 4];
+        let mut last = 0.;
+        for i in 0..n {
+            let u = (i as f64 + 0.5) / n as f64;
+            let (x, pdf, segment) = d.sample(u);
+            assert!(x >= last && x < 1.);
+            last = x;
+            assert_eq!(segment, (x * 4.) as usize);
+            assert_close(pdf, d.pdf(x));
+            counts[segment] += 1;
+        }
+        assert_eq!(counts, [1000, 3000, 0, 4000]);
+        assert_close(d.pdf(0.1), 0.5);
+        assert_close(d.pdf(0.3), 1.5);
+        assert_close(d.pdf(0.6), 0.);
+        assert_close(d.pdf(0.99), 2.);
+    }
+
+    #[test]
+    fn all_zero_is_uniform() {
+        let d = Distribution1D::new(vec![0.; 5]);
+        assert_close(d.integral(), 0.);
+        for &u in [0., 0.3, 0.7, 0.999].iter() {
+            let (x, pdf, _) = d.sample(u);
+            assert_close(x, u);
+            assert_close(pdf, 1.);
+        }
+    }
+
+    #[test]
+    fn pdf_2d_matches_the_samples_and_integrates_to_one() {
+        let (width, height) = (4, 3);
+        let func = [0., 1., 2., 3., 5., 0., 0., 1., 2., 2., 2., 2.];
+        let d = Distribution2D::new(&func, width, height);
+        assert_close(d.integral(), 20. / 12.);
+
+        // 每个格子中心的 pdf 乘以格子的面积，总和为 1，并且与函数值成正比
+        let mut total = 0.;
+        for y in 0..height {
+            for x in 0..width {
+                let p = d.pdf(
+                    (x as f64 + 0.5) / width as f64,
+                    (y as f64 + 0.5) / height as f64,
+                );
+                assert_close(p, func[y * width + x] / d.integral());
+                total += p / (width * height) as f64;
+            }
+        }
+        assert_close(total, 1.);
+
+        for i in 0..50 {
+            for j in 0..50 {
+                let u = ((i as f64 + 0.5) / 50., (j as f64 + 0.5) / 50.);
+                let ((x, y), pdf) = d.sample(u);
+                assert!(pdf > 0.);
+                assert_close(pdf, d.pdf(x, y));
+            }
+        }
+    }
+}
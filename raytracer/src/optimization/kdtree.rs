@@ -0,0 +1,123 @@
+use crate::basic_component::vec3::Vec3;
+
+// 点的 kd 树：每次在坐标范围最大的轴上取中位数划分，
+// 节点不单独分配，直接按 [左子树, 中位数, 右子树] 的顺序放在数组中
+pub struct KdTree<T> {
+    items: Vec<(Vec3, T)>,
+    axes: Vec<usize>, // 每个节点划分的轴
+}
+
+impl<T> KdTree<T> {
+    pub fn new(mut items: Vec<(Vec3, T)>) -> Self {
+        let mut axes = vec![0; items.len()];
+        build(&mut items, &mut axes);
+        Self { items, axes }
+    }
+
+    // 对与 p 的距离不超过 radius 的每个点调用 f
+    pub fn for_each_within<F: FnMut(Vec3, &T)>(&self, p: Vec3, radius: f64, mut f: F) {
+        self.search(0, self.items.len(), p, radius * radius, &mut f);
+    }
+
+    fn search<F: FnMut(Vec3, &T)>(&self, lo: usize, hi: usize, p: Vec3, r2: f64, f: &mut F) {
+        if lo >= hi {
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        let (q, item) = &self.items[mid];
+        if (*q - p).length_squared() <= r2 {
+            f(*q, item);
+        }
+        let axis = self.axes[mid];
+        let d = p[axis] - q[axis];
+        // 先找 p 所在的一侧，另一侧只有与划分平面的距离不超过半径时才需要找
+        let (near, far) = if d < 0. {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.search(near.0, near.1, p, r2, f);
+        if d * d <= r2 {
+            self.search(far.0, far.1, p, r2, f);
+        }
+    }
+}
+
+fn build<T>(items: &mut [(Vec3, T)], axes: &mut [usize]) {
+    if items.len() <= 1 {
+        return;
+    }
+    let mut min = items[0].0;
+    let mut max = items[0].0;
+    for (p, _) in items.iter() {
+        for a in 0..3 {
+            min[a] = min[a].min(p[a]);
+            max[a] = max[a].max(p[a]);
+        }
+    }
+    let extent = max - min;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+
+    let mid = items.len() / 2;
+    items.select_nth_unstable_by(mid, |a, b| a.0[axis].partial_cmp(&b.0[axis]).unwrap());
+    axes[mid] = axis;
+    let (left, right) = items.split_at_mut(mid);
+    let (left_axes, right_axes) = axes.split_at_mut(mid);
+    build(left, left_axes);
+    build(&mut right[1..], &mut right_axes[1..]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic_component::sampler::{Pcg32, Sampler};
+
+    // 与逐个检查所有点的结果相同
+    fn check(points: &[Vec3], queries: &[(Vec3, f64)]) {
+        let tree = KdTree::new(points.iter().copied().zip(0..).collect());
+        for &(p, radius) in queries {
+            let mut found = Vec::new();
+            tree.for_each_within(p, radius, |q, &i| {
+                assert!((q - points[i]).length_squared() == 0.);
+                found.push(i);
+            });
+            found.sort_unstable();
+            let expected: Vec<usize> = (0..points.len())
+                .filter(|&i| (points[i] - p).length_squared() <= radius * radius)
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn finds_the_same_points_as_brute_force() {
+        let mut rng = Pcg32::new(20);
+        let mut random_point = |scale: Vec3| {
+            let (x, y) = rng.get_2d();
+            Vec3::new(x * scale.x, y * scale.y, rng.get_1d() * scale.z)
+        };
+        // 各轴的范围不同，划分的轴也不同
+        let scale = Vec3::new(10., 1., 3.);
+        let points: Vec<Vec3> = (0..1000).map(|_| random_point(scale)).collect();
+        let queries: Vec<(Vec3, f64)> = (0..200)
+            .map(|i| (random_point(scale), [0.05, 0.3, 1., 20.][i % 4]))
+            .collect();
+        check(&points, &queries);
+    }
+
+    #[test]
+    fn handles_duplicates_and_small_trees() {
+        let p = Vec3::new(1., 2., 3.);
+        let points = vec![p, p, Vec3::new(1., 2., 4.), p, Vec3::new(0., 2., 3.)];
+        let queries = [(p, 0.), (p, 1.), (Vec3::new(1., 2., 3.5), 0.5)];
+        check(&points, &queries);
+        check(&points[..1], &queries);
+        check(&[], &queries);
+    }
+}
@@ -1,3 +1,5 @@
 pub mod aabb;
 pub mod bvh;
+pub mod distribution;
+pub mod kdtree;
 pub mod pdf;
@@ -1,15 +1,14 @@
 use std::f64::consts::PI;
 
 use crate::{
-    basic_component::{onb::ONB, vec3::Vec3},
+    basic_component::{onb::ONB, sampler::Sampler, vec3::Vec3},
     hittable::Hittable,
-    utility::random_double,
 };
 
 pub trait PDF {
     fn value(&self, _d: Vec3) -> f64;
 
-    fn generate(&self) -> Vec3;
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3;
 }
 
 //------------------------------------------------------------------
@@ -19,8 +18,9 @@ pub struct CosinePDF {
 }
 
 impl PDF for CosinePDF {
-    fn generate(&self) -> Vec3 {
-        self.uvw.local_from_vec(Vec3::random_cosine_direction())
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        self.uvw
+            .local_from_vec(Vec3::random_cosine_direction(sampler))
     }
 
     fn value(&self, d: Vec3) -> f64 {
@@ -57,8 +57,8 @@ where
 }
 
 impl<'a, T: Hittable> PDF for HittablePDF<'a, T> {
-    fn generate(&self) -> Vec3 {
-        self.ptr.random(self.o)
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        self.ptr.random(self.o, sampler)
     }
 
     fn value(&self, d: Vec3) -> f64 {
@@ -71,35 +71,3 @@ impl<'a, T: Hittable> HittablePDF<'a, T> {
         Self { o: orig, ptr: p }
     }
 }
-
-//-------------------------------------------------------------------
-
-pub struct MixturePDF<'a, T>
-// 将两类 pdf 线性混合
-where
-    T: Hittable,
-{
-    pub p0: &'a HittablePDF<'a, T>,
-    pub p1: CosinePDF,
-}
-
-impl<'a, T: Hittable> PDF for MixturePDF<'a, T> {
-    fn generate(&self) -> Vec3 {
-        // p = 0.5
-        if random_double(0., 1.) < 0.5 {
-            self.p0.generate()
-        } else {
-            self.p1.generate()
-        }
-    }
-
-    fn value(&self, d: Vec3) -> f64 {
-        0.5 * self.p0.value(d) + 0.5 * self.p1.value(d)
-    }
-}
-
-impl<'a, T: Hittable> MixturePDF<'a, T> {
-    pub fn new(_p0: &'a HittablePDF<'a, T>, _p1: CosinePDF) -> Self {
-        Self { p0: _p0, p1: _p1 }
-    }
-}
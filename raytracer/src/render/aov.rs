@@ -0,0 +1,138 @@
+use std::{collections::HashMap, f64::INFINITY, sync::Arc};
+
+use indicatif::ProgressBar;
+
+use crate::{
+    basic_component::{camera::Camera, sampler::SamplerConfig, vec3::Vec3},
+    hittable::{Hittable, HittableList},
+    material::Material,
+    render::scheduler,
+};
+
+// 辅助输出（AOV）：每个像素中心的主光线第一次击中的表面信息
+#[derive(Copy, Clone, PartialEq)]
+pub enum Aov {
+    Albedo,   // 材质的反照率
+    Normal,   // 着色法线，朝向光线射来的一侧
+    Depth,    // 相机空间中沿视线方向的深度
+    Position, // 世界坐标
+    ObjectId, // 物体（材质）编号，背景为 0
+}
+
+pub const AOV_NAMES: [&str; 5] = ["albedo", "normal", "depth", "position", "id"];
+
+impl Aov {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "albedo" => Some(Aov::Albedo),
+            "normal" => Some(Aov::Normal),
+            "depth" => Some(Aov::Depth),
+            "position" => Some(Aov::Position),
+            "id" => Some(Aov::ObjectId),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::ObjectId => "id",
+        }
+    }
+}
+
+// 单个像素的结果，没有击中物体时全部为 0
+#[derive(Copy, Clone, Default)]
+struct AovSample {
+    albedo: Vec3,
+    normal: Vec3,
+    depth: f64,
+    position: Vec3,
+    material: usize, // 材质的地址，之后换成编号
+}
+
+// 所有 AOV 的缓冲，按行存储，y = 0 为图像底部
+pub struct AovBuffers {
+    pub width: u32,
+    pub height: u32,
+    pub albedo: Vec<Vec3>,
+    pub normal: Vec<Vec3>,
+    pub depth: Vec<f64>,
+    pub position: Vec<Vec3>,
+    pub object_id: Vec<u32>,
+}
+
+impl AovBuffers {
+    // 以颜色的形式取出某一个 AOV，单通道的 AOV 复制到三个通道
+    pub fn values(&self, aov: Aov) -> Vec<Vec3> {
+        match aov {
+            Aov::Albedo => self.albedo.clone(),
+            Aov::Normal => self.normal.clone(),
+            Aov::Depth => self.depth.iter().map(|&d| Vec3::new(d, d, d)).collect(),
+            Aov::Position => self.position.clone(),
+            Aov::ObjectId => self
+                .object_id
+                .iter()
+                .map(|&id| Vec3::new(id as f64, id as f64, id as f64))
+                .collect(),
+        }
+    }
+}
+
+// 每个像素只追踪一条穿过像素中心的主光线，镜头与时间的采样和第 0 次采样相同
+pub fn render(
+    width: u32,
+    height: u32,
+    thread_number: usize,
+    cam: Camera,
+    world: Arc<HittableList>,
+    sampler: SamplerConfig,
+) -> Option<AovBuffers> {
+    let progress_bar = ProgressBar::hidden();
+    let samples = scheduler::render(width, height, thread_number, &progress_bar, move |x, y| {
+        let mut sampler = sampler.for_pixel(x, y, 0);
+        sampler.get_2d(); // 跳过像素内的位置
+        let u = (x as f64 + 0.5) / (width - 1) as f64;
+        let v = (y as f64 + 0.5) / (height - 1) as f64;
+        let r = cam.get_ray(u, v, &mut *sampler);
+        match world.hit(r, 0.001, INFINITY) {
+            Some(rec) => AovSample {
+                albedo: rec.mat.albedo(rec),
+                normal: rec.normal,
+                depth: Vec3::dot(rec.p - cam.origin, Vec3::new(0., 0., 0.) - cam.w),
+                position: rec.p,
+                material: rec.mat as *const dyn Material as *const u8 as usize,
+            },
+            None => AovSample::default(),
+        }
+    })?;
+
+    // 地址每次运行都不同，按从图像左上角开始第一次出现的顺序编号
+    // 这样同一个场景、相机和分辨率下，编号总是相同的
+    let mut ids = HashMap::new();
+    for y in (0..height).rev() {
+        for x in 0..width {
+            let material = samples[(y * width + x) as usize].material;
+            if material != 0 {
+                let next = ids.len() as u32 + 1;
+                ids.entry(material).or_insert(next);
+            }
+        }
+    }
+
+    Some(AovBuffers {
+        width,
+        height,
+        albedo: samples.iter().map(|s| s.albedo).collect(),
+        normal: samples.iter().map(|s| s.normal).collect(),
+        depth: samples.iter().map(|s| s.depth).collect(),
+        position: samples.iter().map(|s| s.position).collect(),
+        object_id: samples
+            .iter()
+            .map(|s| ids.get(&s.material).copied().unwrap_or(0))
+            .collect(),
+    })
+}
@@ -0,0 +1,521 @@
+use std::{
+    f64::{consts::PI, INFINITY},
+    sync::Arc,
+};
+
+use crate::{
+    basic_component::{camera::Camera, onb::ONB, ray::Ray, sampler::Sampler, vec3::Vec3},
+    environment::Environment,
+    hittable::{HitRecord, Hittable, HittableList},
+    optimization::pdf::{CosinePDF, PDF},
+    render::{emitting_side, path::MisHeuristic, progressive::Splat, Integrator},
+};
+
+// 双向路径追踪（Veach 1997）：分别从相机和光源出发随机游走得到两条子路径，
+// 取相机子路径的前 t 个顶点和光源子路径的前 s 个顶点连接成一条完整的路径
+// 同一条路径可以由不同的 (s, t) 得到，按各自的 pdf 做 MIS
+// t = 1 时连接到镜头上，落在别的像素上，作为 splat 交给 film
+// 光源子路径从 lights 中的物体表面出发，背景只由相机子路径像路径追踪一样计算
+pub struct Bdpt {
+    pub max_depth: i32, // 路径最多有多少段
+    pub rr_depth: i32,
+    pub heuristic: MisHeuristic,
+    pub camera: Camera,
+    pub width: u32, // 图像大小，用来把光线换算到胶片上的位置
+    pub height: u32,
+    pub world: Arc<HittableList>,
+    pub lights: Arc<HittableList>,
+    pub env: Arc<dyn Environment>,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum VertexKind {
+    Camera,
+    Light,
+    Surface,
+}
+
+// 子路径上的一个顶点，pdf_fwd 为沿子路径的方向采到这一点的面积 pdf，pdf_rev 为反方向的
+#[derive(Copy, Clone)]
+struct Vertex<'a> {
+    kind: VertexKind,
+    p: Vec3,
+    n: Vec3, // 表面为朝向来时一侧的法向量，光源为发光一侧的法向量，相机不使用
+    rec: Option<HitRecord<'a>>,
+    r_in: Ray,         // 到达这一点的光线
+    attenuation: Vec3, // 材质的衰减，f * cos 为 attenuation * scattering_pdf
+    lobe: Vec3,        // 按材质采样时 cos 分布的轴
+    beta: Vec3,        // 子路径到这一点为止的吞吐量
+    delta: bool,       // 镜面反射等无法与其他顶点连接的顶点
+    pdf_fwd: f64,
+    pdf_rev: f64,
+}
+
+impl<'a> Vertex<'a> {
+    fn new(kind: VertexKind, p: Vec3, n: Vec3, beta: Vec3, pdf_fwd: f64) -> Self {
+        Self {
+            kind,
+            p,
+            n,
+            rec: None,
+            r_in: Ray::default(),
+            attenuation: Vec3::new(0., 0., 0.),
+            lobe: Vec3::new(0., 0., 0.),
+            beta,
+            delta: false,
+            pdf_fwd,
+            pdf_rev: 0.,
+        }
+    }
+
+    // 材质的 f 乘以 dir 方向的 cos，不能连接的顶点为 0
+    fn fcos(&self, dir: Vec3) -> Vec3 {
+        match self.rec {
+            Some(rec) if self.kind == VertexKind::Surface && !self.delta => {
+                let scattered = Ray::new(self.p, dir, self.r_in.tm);
+                self.attenuation * rec.mat.scattering_pdf(self.r_in, rec, scattered)
+            }
+            _ => Vec3::new(0., 0., 0.),
+        }
+    }
+
+    // 表面上这一点向来时一侧发出的光
+    fn emitted(&self) -> Vec3 {
+        match self.rec {
+            Some(rec) => rec.mat.emitted(self.r_in, rec, rec.u, rec.v, rec.p),
+            None => Vec3::new(0., 0., 0.),
+        }
+    }
+}
+
+impl Integrator for Bdpt {
+    fn radiance(
+        &self,
+        r: Ray,
+        _pixel: (u32, u32),
+        _index: u32,
+        sampler: &mut dyn Sampler,
+        splats: &mut Vec<Splat>,
+    ) -> Vec3 {
+        let max_len = self.max_depth.max(0) as usize;
+        let mut camera_path = Vec::with_capacity(max_len + 1);
+        let one = Vec3::new(1., 1., 1.);
+        camera_path.push(Vertex::new(
+            VertexKind::Camera,
+            r.orig,
+            self.camera.w,
+            one,
+            1.,
+        ));
+        let pdf = self.camera_pdf(r.orig, r.dir);
+        let mut radiance = self.walk(r, one, pdf, max_len + 1, &mut camera_path, sampler);
+        let light_path = self.light_path(r.tm, max_len, sampler);
+
+        for t in 1..=camera_path.len() {
+            for s in 0..=light_path.len() {
+                if s + t > max_len + 1 || (s == 0 && t == 1) || (s == 1 && t == 1) {
+                    continue;
+                }
+                if t == 1 {
+                    if let Some(splat) = self.splat(&light_path, &camera_path, s, sampler) {
+                        splats.push(splat);
+                    }
+                } else {
+                    radiance += self.connect(&light_path, &camera_path, s, t, sampler);
+                }
+            }
+        }
+        radiance
+    }
+}
+
+impl Bdpt {
+    // 从光源上按面积采样一点，向发光的一侧按 cos 分布发出光线
+    fn light_path(&self, tm: f64, max_len: usize, sampler: &mut dyn Sampler) -> Vec<Vertex<'_>> {
+        let mut path = Vec::with_capacity(max_len);
+        if max_len == 0 {
+            return path;
+        }
+        let (p, n, pdf_pos) = match self.lights.sample_surface(sampler) {
+            Some(s) if s.2 > 0. => s,
+            _ => return path,
+        };
+        let (normal, le) = match emitting_side(&self.world, p, n, tm) {
+            Some(side) => side,
+            None => return path,
+        };
+        let dir = ONB::build_from_w(normal).local_from_vec(Vec3::random_cosine_direction(sampler));
+        let pdf_dir = CosinePDF::new(normal).value(dir);
+        if pdf_dir <= 0. {
+            return path;
+        }
+        path.push(Vertex::new(VertexKind::Light, p, normal, le, pdf_pos));
+        // le * cos / (pdf_pos * pdf_dir)，cos 分布下化简为 le * π / pdf_pos
+        let beta = le * (PI / pdf_pos);
+        self.walk(
+            Ray::new(p, dir, tm),
+            beta,
+            pdf_dir,
+            max_len,
+            &mut path,
+            sampler,
+        );
+        path
+    }
+
+    // 随机游走，把顶点依次加到 path 中，直到 path 有 max_len 个顶点
+    // beta 为到达下一个顶点前的吞吐量，pdf 为采到 ray 方向的立体角 pdf（镜面反射为 0）
+    // 相机子路径像路径追踪一样计算背景，返回背景的贡献
+    fn walk<'a>(
+        &'a self,
+        mut ray: Ray,
+        mut beta: Vec3,
+        mut pdf: f64,
+        max_len: usize,
+        path: &mut Vec<Vertex<'a>>,
+        sampler: &mut dyn Sampler,
+    ) -> Vec3 {
+        let camera_side = path[0].kind == VertexKind::Camera;
+        let start = beta.x.max(beta.y).max(beta.z);
+        let mut radiance = Vec3::new(0., 0., 0.);
+        while path.len() < max_len {
+            let rec = match self.world.hit_sampled(ray, 0.001, INFINITY, sampler) {
+                Some(rec) => rec,
+                None => {
+                    if camera_side {
+                        // 上一个顶点是相机或镜面反射时，背景不可能由背景采样得到
+                        let weight = if pdf > 0. && path.len() > 1 {
+                            self.heuristic.weight(pdf, &[self.env.pdf(ray.dir)])
+                        } else {
+                            1.
+                        };
+                        radiance += beta * self.env.radiance(ray.dir) * weight;
+                    }
+                    break;
+                }
+            };
+            let prev = path.len() - 1;
+            let mut v = Vertex::new(VertexKind::Surface, rec.p, rec.normal, beta, 0.);
+            v.rec = Some(rec);
+            v.r_in = ray;
+            v.pdf_fwd = convert_density(pdf, &path[prev], &v);
+            let srec = match rec.mat.scatter(ray, rec, sampler) {
+                Some(srec) => srec,
+                None => {
+                    path.push(v);
+                    break;
+                }
+            };
+            // 顶点之间的连接只考虑表面，介质中的散射与镜面反射一样，只沿采样到的方向继续
+            let delta = srec.is_specular || srec.pdf_type == 2;
+            v.attenuation = srec.attenuation;
+            v.delta = delta;
+            v.lobe = srec.cos_pdf.uvw.w();
+            path.push(v);
+
+            // 俄罗斯轮盘赌，与路径追踪相同，只是吞吐量相对于起点
+            // 只影响之后的顶点，这一点本身的连接总会计算
+            let bounce = path.len() - 2;
+            if bounce >= self.rr_depth.max(0) as usize {
+                let p = beta.x.max(beta.y).max(beta.z) / start;
+                if p < 1. {
+                    if sampler.get_1d() >= p {
+                        break;
+                    }
+                    beta /= p;
+                }
+            }
+
+            if delta {
+                beta = beta * srec.attenuation;
+                ray = srec.scattered;
+                pdf = 0.;
+                path[prev].pdf_rev = 0.;
+                continue;
+            }
+
+            // 背景的下一事件估计，阴影光线没有碰到表面时才有贡献，穿过介质时乘上透射率
+            if camera_side && path.len() < max_len {
+                if let Some((dir, env_pdf)) = self.env.sample(sampler) {
+                    let shadow = Ray::new(rec.p, dir, ray.tm);
+                    let scattering_pdf = rec.mat.scattering_pdf(ray, rec, shadow);
+                    if env_pdf > 0.
+                        && scattering_pdf > 0.
+                        && self.world.hit_surface(shadow, 0.001, INFINITY).is_none()
+                    {
+                        let tr = self.world.transmittance(shadow, 0.001, INFINITY, sampler);
+                        let weight = self.heuristic.weight(env_pdf, &[srec.cos_pdf.value(dir)]);
+                        radiance += beta
+                            * srec.attenuation
+                            * self.env.radiance(dir)
+                            * (tr * scattering_pdf * weight / env_pdf);
+                    }
+                }
+            }
+
+            let scattered = Ray::new(rec.p, srec.cos_pdf.generate(sampler), ray.tm);
+            let pdf_dir = srec.cos_pdf.value(scattered.dir);
+            if pdf_dir <= 0. {
+                break;
+            }
+            beta = beta * srec.attenuation * rec.mat.scattering_pdf(ray, rec, scattered) / pdf_dir;
+            // 反方向：从这一点按材质采样回到上一个顶点
+            let v = path[prev + 1];
+            path[prev].pdf_rev = self.pdf(&v, &path[prev]);
+            ray = scattered;
+            pdf = pdf_dir;
+        }
+        radiance
+    }
+
+    // 连接光源子路径的前 s 个顶点和相机子路径的前 t 个顶点（t >= 2），返回乘上 MIS 权重后的贡献
+    fn connect(
+        &self,
+        light_path: &[Vertex],
+        camera_path: &[Vertex],
+        s: usize,
+        t: usize,
+        sampler: &mut dyn Sampler,
+    ) -> Vec3 {
+        let zero = Vec3::new(0., 0., 0.);
+        let pt = &camera_path[t - 1];
+        if pt.kind != VertexKind::Surface {
+            return zero;
+        }
+        if s == 0 {
+            // 相机子路径自己碰到了光源
+            let le = pt.emitted();
+            if le.luminance() <= 0. {
+                return zero;
+            }
+            return pt.beta * le * self.mis_weight(light_path, camera_path, None, 0, t);
+        }
+
+        if s == 1 {
+            // 在光源上重新采样一点
+            let (p, _, pdf_pos) = match self.lights.sample_surface(sampler) {
+                Some(sample) if sample.2 > 0. => sample,
+                _ => return zero,
+            };
+            let d = p - pt.p;
+            let dist = d.length();
+            let dir = d / dist;
+            let fcos = pt.fcos(dir);
+            if fcos.luminance() <= 0. {
+                return zero;
+            }
+            // 沿连线找到的第一个表面就是这一点时才没有被遮挡，发光的亮度也取自这里
+            // 中间的介质不挡住光线，按透射率衰减
+            let ray = Ray::new(pt.p, dir, pt.r_in.tm);
+            let rec = match self.world.hit_surface(ray, 0.001, INFINITY) {
+                Some(rec) if (rec.t - dist).abs() < 1e-3 * dist.max(1.) => rec,
+                _ => return zero,
+            };
+            let le = rec.mat.emitted(ray, rec, rec.u, rec.v, rec.p);
+            if le.luminance() <= 0. {
+                return zero;
+            }
+            let tr = self.world.transmittance(ray, 0.001, rec.t, sampler);
+            // rec.normal 朝向 pt，也就是发光的一侧
+            let light = Vertex::new(VertexKind::Light, p, rec.normal, le, pdf_pos);
+            let cos = Vec3::dot(rec.normal, dir).abs();
+            let l = pt.beta * fcos * le * (tr * cos / (dist * dist * pdf_pos));
+            return l * self.mis_weight(light_path, camera_path, Some(light), 1, t);
+        }
+
+        let qs = &light_path[s - 1];
+        let d = qs.p - pt.p;
+        let dist = d.length();
+        let dir = d / dist;
+        let f = pt.fcos(dir) * qs.fcos(Vec3::new(0., 0., 0.) - dir);
+        if f.luminance() <= 0. {
+            return zero;
+        }
+        let tr = self.visibility(pt.p, qs.p, pt.r_in.tm, sampler);
+        if tr <= 0. {
+            return zero;
+        }
+        let l = pt.beta * qs.beta * f * (tr / (dist * dist));
+        l * self.mis_weight(light_path, camera_path, None, s, t)
+    }
+
+    // t = 1：把光源子路径的第 s 个顶点连接到镜头上，返回落在胶片上的位置和贡献
+    fn splat(
+        &self,
+        light_path: &[Vertex],
+        camera_path: &[Vertex],
+        s: usize,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Splat> {
+        let qs = &light_path[s - 1];
+        if qs.kind != VertexKind::Surface || qs.delta {
+            return None;
+        }
+        let lens = self.camera.sample_lens(sampler);
+        let d = lens - qs.p;
+        let dist = d.length();
+        let dir = d / dist;
+        let (x, y) = self.raster(lens, Vec3::new(0., 0., 0.) - dir)?;
+        let fcos = qs.fcos(dir);
+        if fcos.luminance() <= 0. {
+            return None;
+        }
+        let tr = self.visibility(qs.p, lens, qs.r_in.tm, sampler);
+        if tr <= 0. {
+            return None;
+        }
+        // 相机的重要性 W 乘以镜头处的 cos、除以距离的平方，再除以镜头上采样的 pdf
+        // 胶片上均匀采样时化简为 f^2 / (A cos^3 θ d^2)，与镜头大小无关
+        let cos = Vec3::dot(dir, self.camera.w);
+        let f = self.camera.focus_dist();
+        let importance = f * f / (self.film_area() * cos * cos * cos * dist * dist);
+        let one = Vec3::new(1., 1., 1.);
+        let camera = Vertex::new(VertexKind::Camera, lens, self.camera.w, one, 1.);
+        let weight = self.mis_weight(light_path, camera_path, Some(camera), s, 1);
+        Some(Splat {
+            x,
+            y,
+            color: qs.beta * fcos * (importance * weight * tr),
+        })
+    }
+
+    // 两点之间的透射率：被表面挡住时为 0，穿过介质时按 ratio tracking 估计
+    fn visibility(&self, a: Vec3, b: Vec3, tm: f64, sampler: &mut dyn Sampler) -> f64 {
+        let d = b - a;
+        let dist = d.length();
+        let ray = Ray::new(a, d / dist, tm);
+        if self.world.hit_surface(ray, 0.001, dist - 0.001).is_some() {
+            return 0.;
+        }
+        self.world.transmittance(ray, 0.001, dist - 0.001, sampler)
+    }
+
+    // 胶片的实际面积：像素 x 对应 u ∈ [x / (width - 1), (x + 1) / (width - 1)]，比 [0, 1] 略大
+    fn film_area(&self) -> f64 {
+        let w = self.width as f64;
+        let h = self.height as f64;
+        self.camera.film_area() * w / (w - 1.) * h / (h - 1.)
+    }
+
+    // 从镜头上的 lens_point 出发、方向为 dir 的光线落在图像上的连续坐标，不在图像内时为 None
+    fn raster(&self, lens_point: Vec3, dir: Vec3) -> Option<(f64, f64)> {
+        let (u, v) = self.camera.film_position(lens_point, dir)?;
+        let x = u * (self.width - 1) as f64;
+        let y = v * (self.height - 1) as f64;
+        if x < 0. || y < 0. || x >= self.width as f64 || y >= self.height as f64 {
+            return None;
+        }
+        Some((x, y))
+    }
+
+    // 相机从镜头上的 lens_point 发出 dir 方向光线的立体角 pdf
+    fn camera_pdf(&self, lens_point: Vec3, dir: Vec3) -> f64 {
+        if self.raster(lens_point, dir).is_none() {
+            return 0.;
+        }
+        let cos = -Vec3::dot(Vec3::unit_vector(dir), self.camera.w);
+        let f = self.camera.focus_dist();
+        f * f / (self.film_area() * cos * cos * cos)
+    }
+
+    // 从 cur 出发采到 next 的面积 pdf
+    fn pdf(&self, cur: &Vertex, next: &Vertex) -> f64 {
+        let dir = next.p - cur.p;
+        let pdf = match cur.kind {
+            VertexKind::Camera => self.camera_pdf(cur.p, dir),
+            VertexKind::Light => CosinePDF::new(cur.n).value(dir),
+            VertexKind::Surface if cur.delta => 0.,
+            VertexKind::Surface => CosinePDF::new(cur.lobe).value(dir),
+        };
+        convert_density(pdf, cur, next)
+    }
+
+    // 光源上的顶点 cur 发出的光线采到 next 的面积 pdf
+    fn pdf_light(&self, cur: &Vertex, next: &Vertex) -> f64 {
+        let pdf = CosinePDF::new(cur.n).value(next.p - cur.p);
+        convert_density(pdf, cur, next)
+    }
+
+    // 按 Veach 的方法，依次把连接点向两侧移动，得到其他策略采到同一条路径的 pdf 之比 r_i
+    // 权重为 1 / (1 + Σ r_i)（平衡启发式）或 1 / (1 + Σ r_i^2)（幂启发式）
+    // sampled 为连接时重新采样的顶点，替换 s = 1 时光源子路径或 t = 1 时相机子路径的第一个顶点
+    fn mis_weight(
+        &self,
+        light_path: &[Vertex],
+        camera_path: &[Vertex],
+        sampled: Option<Vertex>,
+        s: usize,
+        t: usize,
+    ) -> f64 {
+        if s + t == 2 {
+            return 1.;
+        }
+        let mut lv = light_path[..s].to_vec();
+        let mut cv = camera_path[..t].to_vec();
+        if let Some(v) = sampled {
+            if s == 1 {
+                lv[0] = v;
+            } else if t == 1 {
+                cv[0] = v;
+            }
+        }
+
+        // 连接点两侧的顶点的 pdf_rev 由另一条子路径决定
+        if s == 0 {
+            // 光源子路径从这一点出发的 pdf；光源不在 lights 中时只有这一种策略
+            let pdf_origin = self.lights.surface_pdf(cv[t - 1].p, cv[t - 1].n);
+            if pdf_origin <= 0. {
+                return 1.;
+            }
+            cv[t - 1].pdf_rev = pdf_origin;
+            cv[t - 2].pdf_rev = self.pdf_light(&cv[t - 1], &cv[t - 2]);
+        } else {
+            cv[t - 1].pdf_rev = self.pdf(&lv[s - 1], &cv[t - 1]);
+            if t > 1 {
+                cv[t - 2].pdf_rev = self.pdf(&cv[t - 1], &cv[t - 2]);
+            }
+            lv[s - 1].pdf_rev = self.pdf(&cv[t - 1], &lv[s - 1]);
+            if s > 1 {
+                lv[s - 2].pdf_rev = self.pdf(&lv[s - 1], &lv[s - 2]);
+            }
+        }
+
+        // 镜面反射的顶点 pdf 为 0，两个方向上都当作 1，比值不变
+        let remap = |p: f64| if p != 0. { p } else { 1. };
+        let f = |r: f64| match self.heuristic {
+            MisHeuristic::Balance => r,
+            MisHeuristic::Power => r * r,
+        };
+        let mut sum = 0.;
+        let mut r = 1.;
+        for i in (1..t).rev() {
+            r *= remap(cv[i].pdf_rev) / remap(cv[i].pdf_fwd);
+            if !cv[i].delta && !cv[i - 1].delta {
+                sum += f(r);
+            }
+        }
+        let mut r = 1.;
+        for i in (0..s).rev() {
+            r *= remap(lv[i].pdf_rev) / remap(lv[i].pdf_fwd);
+            let delta_prev = i > 0 && lv[i - 1].delta;
+            if !lv[i].delta && !delta_prev {
+                sum += f(r);
+            }
+        }
+        1. / (1. + sum)
+    }
+}
+
+// 把从 cur 出发的立体角 pdf 换算成 next 处的面积 pdf，相机不在表面上，不乘 cos
+fn convert_density(pdf: f64, cur: &Vertex, next: &Vertex) -> f64 {
+    let d = next.p - cur.p;
+    let dist_sq = d.length_squared();
+    if dist_sq == 0. {
+        return 0.;
+    }
+    if next.kind == VertexKind::Camera {
+        pdf / dist_sq
+    } else {
+        pdf * Vec3::dot(next.n, d).abs() / (dist_sq * dist_sq.sqrt())
+    }
+}
@@ -0,0 +1,186 @@
+use std::{f64::INFINITY, sync::Arc};
+
+use crate::{
+    basic_component::{camera::Camera, onb::ONB, ray::Ray, sampler::Sampler, vec3::Vec3},
+    film::heat_color,
+    hittable::{Hittable, HittableList, TraversalCost},
+    render::{progressive::Splat, Integrator},
+};
+
+// 调试视图：不计算光照，只显示主光线第一次击中的表面的某种信息，用来检查场景哪里出了问题
+// 没有击中物体时为黑色
+#[derive(Copy, Clone, PartialEq)]
+pub enum DebugView {
+    ShadingNormal,    // 着色法线，朝向光线射来的一侧
+    GeometricNormal,  // 几何法线，朝向物体的外侧
+    Uv,               // 纹理坐标，(u, v, 0)
+    Depth,            // 相机空间中沿视线方向的深度，除以 depth_max 映射到 [0, 1]
+    Albedo,           // 材质的反照率
+    AmbientOcclusion, // 环境光遮蔽，半径内没有遮挡的比例
+    FrontFace,        // 击中正面为绿色，背面为红色
+    BvhCost,          // 主光线访问的 BVH 结点数加上图元求交次数的热力图
+}
+
+pub const DEBUG_VIEWS: [&str; 8] = [
+    "normal",
+    "geometric-normal",
+    "uv",
+    "depth",
+    "albedo",
+    "ao",
+    "front-face",
+    "bvh-cost",
+];
+
+impl DebugView {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "normal" => Some(DebugView::ShadingNormal),
+            "geometric-normal" => Some(DebugView::GeometricNormal),
+            "uv" => Some(DebugView::Uv),
+            "depth" => Some(DebugView::Depth),
+            "albedo" => Some(DebugView::Albedo),
+            "ao" => Some(DebugView::AmbientOcclusion),
+            "front-face" => Some(DebugView::FrontFace),
+            "bvh-cost" => Some(DebugView::BvhCost),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DebugView::ShadingNormal => "normal",
+            DebugView::GeometricNormal => "geometric-normal",
+            DebugView::Uv => "uv",
+            DebugView::Depth => "depth",
+            DebugView::Albedo => "albedo",
+            DebugView::AmbientOcclusion => "ao",
+            DebugView::FrontFace => "front-face",
+            DebugView::BvhCost => "bvh-cost",
+        }
+    }
+}
+
+pub struct DebugIntegrator {
+    pub view: DebugView,
+    pub world: Arc<HittableList>,
+    pub camera: Camera,
+    pub ao_radius: f64,    // 环境光遮蔽的光线只检查这个距离内的遮挡
+    pub depth_max: f64,    // 深度视图中白色对应的深度
+    pub bvh_cost_max: u32, // 热力图中白色对应的求交次数
+}
+
+impl DebugIntegrator {
+    // ao_radius 为 None 时取场景包围盒对角线的 1/10
+    // depth_max 为 None 时取包围盒的角点中最大的深度，整个场景都落在 [0, 1] 内
+    pub fn new(
+        view: DebugView,
+        world: Arc<HittableList>,
+        camera: Camera,
+        ao_radius: Option<f64>,
+        depth_max: Option<f64>,
+        bvh_cost_max: u32,
+    ) -> Self {
+        let bbox = world.bounding_box(camera.time0, camera.time1);
+        let ao_radius = ao_radius.unwrap_or_else(|| match bbox {
+            Some(b) => (b.maximum - b.minimum).length() * 0.1,
+            None => 1.,
+        });
+        let depth_max = depth_max.unwrap_or_else(|| {
+            let max = match bbox {
+                Some(b) => (0..8)
+                    .map(|i| {
+                        let corner = Vec3::new(
+                            if i & 1 == 0 { b.minimum.x } else { b.maximum.x },
+                            if i & 2 == 0 { b.minimum.y } else { b.maximum.y },
+                            if i & 4 == 0 { b.minimum.z } else { b.maximum.z },
+                        );
+                        Vec3::dot(corner - camera.origin, Vec3::new(0., 0., 0.) - camera.w)
+                    })
+                    .fold(0., f64::max),
+                None => 0.,
+            };
+            // 场景在相机背后或者包围盒无穷大时退回 1
+            if max > 0. && max.is_finite() {
+                max
+            } else {
+                1.
+            }
+        });
+        Self {
+            view,
+            world,
+            camera,
+            ao_radius,
+            depth_max,
+            bvh_cost_max,
+        }
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn radiance(
+        &self,
+        r: Ray,
+        _pixel: (u32, u32),
+        _index: u32,
+        sampler: &mut dyn Sampler,
+        _splats: &mut Vec<Splat>,
+    ) -> Vec3 {
+        let black = Vec3::new(0., 0., 0.);
+        if self.view == DebugView::BvhCost {
+            // 只有这个视图走计数的遍历，其他积分器的求交没有额外的开销
+            let mut cost = TraversalCost::default();
+            self.world.hit_counted(r, 0.001, INFINITY, &mut cost);
+            let tests = (cost.nodes + cost.primitives) as f64;
+            return heat_color(tests / self.bvh_cost_max as f64);
+        }
+        let rec = match self.world.hit(r, 0.001, INFINITY) {
+            Some(rec) => rec,
+            None => return black,
+        };
+
+        match self.view {
+            DebugView::ShadingNormal => normal_color(rec.normal),
+            DebugView::GeometricNormal => {
+                let n = if rec.front_face {
+                    rec.normal
+                } else {
+                    black - rec.normal
+                };
+                normal_color(n)
+            }
+            DebugView::Uv => Vec3::new(rec.u, rec.v, 0.),
+            DebugView::Depth => {
+                let d =
+                    Vec3::dot(rec.p - self.camera.origin, black - self.camera.w) / self.depth_max;
+                Vec3::new(d, d, d)
+            }
+            DebugView::Albedo => rec.mat.albedo(rec),
+            DebugView::AmbientOcclusion => {
+                // 按 cos 分布采样一个方向，结果为 cos 加权的未遮挡比例
+                let dir = ONB::build_from_w(rec.normal)
+                    .local_from_vec(Vec3::random_cosine_direction(sampler));
+                let ray = Ray::new(rec.p, dir, r.tm);
+                if self.world.hit(ray, 0.001, self.ao_radius).is_some() {
+                    black
+                } else {
+                    Vec3::new(1., 1., 1.)
+                }
+            }
+            DebugView::FrontFace => {
+                if rec.front_face {
+                    Vec3::new(0., 1., 0.)
+                } else {
+                    Vec3::new(1., 0., 0.)
+                }
+            }
+            DebugView::BvhCost => unreachable!(),
+        }
+    }
+}
+
+// 单位法向量的各分量从 [-1, 1] 映射到 [0, 1]
+fn normal_color(n: Vec3) -> Vec3 {
+    (Vec3::unit_vector(n) + Vec3::new(1., 1., 1.)) * 0.5
+}
@@ -0,0 +1,311 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use indicatif::ProgressBar;
+
+use crate::{
+    basic_component::{
+        camera::Camera,
+        ray::Ray,
+        sampler::{hash, Pcg32, Sampler},
+        vec3::Vec3,
+    },
+    optimization::distribution::Distribution1D,
+    render::{path::PathTracer, progressive::Splat, scheduler, Integrator},
+};
+
+// 原始样本空间的 Metropolis 光传输（PSSMLT，Kelemen et al. 2002）
+// 路径追踪用到的所有随机数组成一个 [0, 1)^∞ 中的向量，把它当作马尔可夫链的状态，
+// 按路径贡献的亮度 f 做 Metropolis 采样：小步在当前向量附近扰动，大步整个重新随机
+// 一旦找到穿过小缝隙的路径，附近的路径都会被反复探索，适合光源被遮挡、只从缝隙照进来的场景
+// 采样点在整张图像上移动，贡献都作为 splat 交给 film
+#[derive(Copy, Clone)]
+pub struct MltSettings {
+    pub bootstrap: usize,  // 估计归一化常数 b 的路径数，同时作为各条链的起点
+    pub chain_length: u32, // 每条马尔可夫链变异的次数
+    pub large_step_probability: f64, // 一次变异为大步的概率
+    pub sigma: f64,        // 小步扰动的标准差
+}
+
+impl Default for MltSettings {
+    fn default() -> Self {
+        Self {
+            bootstrap: 100000,
+            chain_length: 1000,
+            large_step_probability: 0.3,
+            sigma: 0.01,
+        }
+    }
+}
+
+pub struct Mlt {
+    pub tracer: PathTracer,
+    pub camera: Camera,
+    pub width: u32,
+    pub height: u32,
+    pub samples_per_pixel: u32, // 目标采样次数，决定一共变异多少次
+    pub settings: MltSettings,
+    pub seed: u64,
+    bootstrap: Distribution1D, // 按 f 选择链的起点
+    b: f64,                    // f 在原始样本空间上的积分
+}
+
+impl Mlt {
+    // 先用路径追踪采样 settings.bootstrap 条路径，估计 b
+    // bootstrap 的路径排成一行交给调度器多线程计算，每条路径的种子固定，结果与线程数无关
+    // 有线程失败时返回 None
+    pub fn new(
+        tracer: PathTracer,
+        camera: Camera,
+        size: (u32, u32),
+        samples_per_pixel: u32,
+        settings: MltSettings,
+        seed: u64,
+        thread_number: usize,
+    ) -> Option<Self> {
+        let mlt = Arc::new(Self {
+            tracer,
+            camera,
+            width: size.0,
+            height: size.1,
+            samples_per_pixel,
+            settings,
+            seed,
+            bootstrap: Distribution1D::new(vec![0.]),
+            b: 0.,
+        });
+        let progress_bar = ProgressBar::hidden();
+        let weights = {
+            let mlt = mlt.clone();
+            scheduler::render(
+                settings.bootstrap as u32,
+                1,
+                thread_number,
+                &progress_bar,
+                move |k, _| {
+                    let (_, _, l) = mlt.evaluate(&mut mlt.bootstrap_sampler(k as usize));
+                    contribution(l)
+                },
+            )?
+        };
+        // 调度器返回时所有线程都已结束，这里是唯一的引用
+        let mut mlt = Arc::try_unwrap(mlt).ok()?;
+        mlt.bootstrap = Distribution1D::new(weights);
+        mlt.b = mlt.bootstrap.integral();
+        Some(mlt)
+    }
+
+    pub fn normalization(&self) -> f64 {
+        self.b
+    }
+
+    // 第 k 条 bootstrap 路径的采样器，链从这里出发时用同一个种子重现它的状态
+    fn bootstrap_sampler(&self, k: usize) -> MltSampler {
+        MltSampler::new(
+            Pcg32::new_stream(hash(self.seed, k as u64), MLT_STREAM),
+            self.settings,
+        )
+    }
+
+    // 原始样本的前两维为图像上的位置，其余的交给相机和路径追踪
+    // 返回位置和这条路径带回的辐射度
+    fn evaluate(&self, sampler: &mut MltSampler) -> (f64, f64, Vec3) {
+        let (s, t) = sampler.get_2d();
+        let px = s * self.width as f64;
+        let py = t * self.height as f64;
+        let u = px / (self.width - 1) as f64;
+        let v = py / (self.height - 1) as f64;
+        let r = self.camera.get_ray(u, v, sampler);
+        (px, py, self.tracer.trace(r, sampler, None))
+    }
+
+    // 运行第 chain 条马尔可夫链，变异 mutations 次
+    // 每次变异都把提议的状态和当前的状态按接受概率分配贡献（期望值的写法），比只记录当前状态噪声小
+    fn run_chain(&self, chain: u64, mutations: u32, splats: &mut Vec<Splat>) {
+        let mut rng = Pcg32::new_stream(hash(self.seed, chain), MLT_STREAM + 1);
+        let (_, _, k) = self.bootstrap.sample(rng.get_1d());
+        let mut sampler = self.bootstrap_sampler(k);
+        let (mut x, mut y, mut l) = self.evaluate(&mut sampler);
+        let mut f = contribution(l);
+        if f <= 0. {
+            return;
+        }
+        // 之后的变异用这条链自己的随机数
+        sampler.rng = Pcg32::new_stream(hash(self.seed, chain), MLT_STREAM + 2);
+
+        for _ in 0..mutations {
+            sampler.start_iteration();
+            let (px, py, pl) = self.evaluate(&mut sampler);
+            let pf = contribution(pl);
+            let accept = (pf / f).min(1.);
+            if accept > 0. {
+                splats.push(Splat {
+                    x: px,
+                    y: py,
+                    color: pl * (accept * self.b / pf),
+                });
+            }
+            if accept < 1. {
+                splats.push(Splat {
+                    x,
+                    y,
+                    color: l * ((1. - accept) * self.b / f),
+                });
+            }
+            if rng.get_1d() < accept {
+                x = px;
+                y = py;
+                l = pl;
+                f = pf;
+                sampler.accept();
+            } else {
+                sampler.reject();
+            }
+        }
+    }
+}
+
+// 渐进式渲染仍然逐个像素调用，把所有像素的所有采样排成一列，每 chain_length 个对应一条链
+// 轮到一条链的第一个采样时运行整条链，图像的颜色全部来自 splat
+impl Integrator for Mlt {
+    fn radiance(
+        &self,
+        _r: Ray,
+        pixel: (u32, u32),
+        index: u32,
+        _sampler: &mut dyn Sampler,
+        splats: &mut Vec<Splat>,
+    ) -> Vec3 {
+        let pixel_count = self.width as u64 * self.height as u64;
+        let length = self.settings.chain_length as u64;
+        let g = index as u64 * pixel_count + (pixel.1 * self.width + pixel.0) as u64;
+        if g % length == 0 && self.b > 0. {
+            // 最后一条链只变异到目标的采样次数为止，从断点继续增加采样时照常运行
+            let total = pixel_count * self.samples_per_pixel as u64;
+            let mutations = if g < total {
+                length.min(total - g)
+            } else {
+                length
+            };
+            self.run_chain(g / length, mutations as u32, splats);
+        }
+        Vec3::new(0., 0., 0.)
+    }
+}
+
+const MLT_STREAM: u64 = 0x6d6c74;
+
+fn contribution(l: Vec3) -> f64 {
+    let f = l.luminance();
+    if f.is_finite() {
+        f.max(0.)
+    } else {
+        0.
+    }
+}
+
+// 原始样本向量的一维，记录最后一次修改在第几次变异，以及拒绝时恢复用的备份
+#[derive(Copy, Clone, Default)]
+struct PrimarySample {
+    value: f64,
+    last_modified: u64,
+    value_backup: f64,
+    modify_backup: u64,
+}
+
+// 按需变异的采样器：路径用到某一维时才把它变异到当前的迭代，
+// 跳过的小步合并成一次标准差为 sigma * sqrt(n) 的扰动，在上次大步之前的值直接重新随机
+struct MltSampler {
+    rng: Pcg32,
+    settings: MltSettings,
+    samples: Vec<PrimarySample>,
+    iteration: u64,
+    large_step: bool,
+    last_large_step: u64, // 最后一次被接受的大步
+    index: usize,         // 这次变异中下一个要用的维度
+}
+
+impl MltSampler {
+    fn new(rng: Pcg32, settings: MltSettings) -> Self {
+        // 初始状态相当于一次大步
+        Self {
+            rng,
+            settings,
+            samples: Vec::new(),
+            iteration: 0,
+            large_step: true,
+            last_large_step: 0,
+            index: 0,
+        }
+    }
+
+    fn start_iteration(&mut self) {
+        self.iteration += 1;
+        self.large_step = self.rng.get_1d() < self.settings.large_step_probability;
+        self.index = 0;
+    }
+
+    fn accept(&mut self) {
+        if self.large_step {
+            self.last_large_step = self.iteration;
+        }
+    }
+
+    // 恢复这次变异改动过的维度
+    fn reject(&mut self) {
+        for x in self.samples.iter_mut() {
+            if x.last_modified == self.iteration {
+                x.value = x.value_backup;
+                x.last_modified = x.modify_backup;
+            }
+        }
+        self.iteration -= 1;
+    }
+
+    fn mutate(&mut self, i: usize) {
+        // 第一次用到的维度相当于在最后一次大步时随机生成
+        while self.samples.len() <= i {
+            let value = self.rng.get_1d();
+            self.samples.push(PrimarySample {
+                value,
+                last_modified: self.last_large_step,
+                ..PrimarySample::default()
+            });
+        }
+        let mut x = self.samples[i];
+        if x.last_modified < self.last_large_step {
+            x.value = self.rng.get_1d();
+            x.last_modified = self.last_large_step;
+        }
+        x.value_backup = x.value;
+        x.modify_backup = x.last_modified;
+        if self.large_step {
+            x.value = self.rng.get_1d();
+        } else {
+            let n = (self.iteration - x.last_modified) as f64;
+            // Box-Muller 变换得到标准正态分布
+            let r = (-2. * (1. - self.rng.get_1d()).ln()).sqrt();
+            let normal = r * (2. * PI * self.rng.get_1d()).cos();
+            x.value += normal * self.settings.sigma * n.sqrt();
+            x.value -= x.value.floor();
+            if x.value >= 1. {
+                x.value = 0.;
+            }
+        }
+        x.last_modified = self.iteration;
+        self.samples[i] = x;
+    }
+}
+
+impl Sampler for MltSampler {
+    fn get_1d(&mut self) -> f64 {
+        let i = self.index;
+        self.mutate(i);
+        self.index += 1;
+        self.samples[i].value
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let u = self.get_1d();
+        (u, self.get_1d())
+    }
+}
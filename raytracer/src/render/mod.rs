@@ -0,0 +1,84 @@
+pub mod aov;
+pub mod bdpt;
+pub mod debug;
+pub mod mlt;
+pub mod path;
+pub mod photon;
+pub mod progressive;
+pub mod scheduler;
+pub mod spectral;
+
+use std::ops::Range;
+
+use crate::{
+    basic_component::{ray::Ray, sampler::Sampler, vec3::Vec3},
+    hittable::{Hittable, HittableList},
+    render::progressive::Splat,
+};
+
+// 积分器：估计一条相机光线带回的辐射度
+// 从光源出发的路径可能落在别的像素上，这些贡献放进 splats，由 film 另外累加
+// pixel 为采样所在的像素，index 为这是像素的第几次采样
+pub trait Integrator: Send + Sync {
+    fn radiance(
+        &self,
+        r: Ray,
+        pixel: (u32, u32),
+        index: u32,
+        sampler: &mut dyn Sampler,
+        splats: &mut Vec<Splat>,
+    ) -> Vec3;
+
+    // 每一轮开始前调用，这一轮各像素的采样序号都在 samples 内
+    // 需要按采样序号准备数据（如光子图）的积分器在这里一次准备好，渲染时只读
+    fn begin_pass(&self, _samples: Range<u32>) {}
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum IntegratorKind {
+    Path,  // 路径追踪
+    Bdpt,  // 双向路径追踪
+    Ppm,   // 路径追踪 + 渐进式光子映射的焦散
+    Mlt,   // 原始样本空间的 Metropolis 光传输
+    Debug, // 调试视图，不计算光照
+}
+
+pub const INTEGRATORS: [&str; 5] = ["path", "bdpt", "ppm", "mlt", "debug"];
+
+impl IntegratorKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "path" => Some(IntegratorKind::Path),
+            "bdpt" => Some(IntegratorKind::Bdpt),
+            "ppm" => Some(IntegratorKind::Ppm),
+            "mlt" => Some(IntegratorKind::Mlt),
+            "debug" => Some(IntegratorKind::Debug),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IntegratorKind::Path => "path",
+            IntegratorKind::Bdpt => "bdpt",
+            IntegratorKind::Ppm => "ppm",
+            IntegratorKind::Mlt => "mlt",
+            IntegratorKind::Debug => "debug",
+        }
+    }
+}
+
+// 光源只有一面发光，从两侧分别看向光源上的点 p，找到发光的一侧和它的亮度
+pub fn emitting_side(world: &HittableList, p: Vec3, n: Vec3, tm: f64) -> Option<(Vec3, Vec3)> {
+    let n = Vec3::unit_vector(n);
+    for &side in [n, Vec3::new(0., 0., 0.) - n].iter() {
+        let ray = Ray::new(p + side * 1e-3, Vec3::new(0., 0., 0.) - side, tm);
+        if let Some(rec) = world.hit(ray, 0., 2e-3) {
+            let le = rec.mat.emitted(ray, rec, rec.u, rec.v, rec.p);
+            if le.luminance() > 0. {
+                return Some((side, le));
+            }
+        }
+    }
+    None
+}
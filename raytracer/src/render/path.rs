@@ -0,0 +1,271 @@
+use std::{f64::INFINITY, sync::Arc};
+
+use crate::{
+    basic_component::{ray::Ray, sampler::Sampler, spectrum::SampledWavelengths, vec3::Vec3},
+    environment::Environment,
+    hittable::{HitRecord, Hittable, HittableList},
+    material::ScatterRecord,
+    optimization::pdf::{HittablePDF, PDF},
+    render::{photon::PhotonMap, progressive::Splat, Integrator},
+};
+
+// 多重重要性采样（MIS）中各个采样策略的权重
+#[derive(Copy, Clone, PartialEq)]
+pub enum MisHeuristic {
+    Balance, // pdf 之比
+    Power,   // pdf 平方之比，一方明显更好时更偏向它
+}
+
+pub const MIS_HEURISTICS: [&str; 2] = ["balance", "power"];
+
+impl MisHeuristic {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "balance" => Some(MisHeuristic::Balance),
+            "power" => Some(MisHeuristic::Power),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MisHeuristic::Balance => "balance",
+            MisHeuristic::Power => "power",
+        }
+    }
+
+    // pdf 为 a 的策略采到某个方向时的权重，其他策略采到同一方向的 pdf 为 others
+    pub fn weight(self, a: f64, others: &[f64]) -> f64 {
+        let f = |p: f64| match self {
+            MisHeuristic::Balance => p,
+            MisHeuristic::Power => p * p,
+        };
+        let sum = f(a) + others.iter().map(|&p| f(p)).sum::<f64>();
+        if sum > 0. {
+            f(a) / sum
+        } else {
+            0.
+        }
+    }
+}
+
+// 迭代的路径追踪：沿着路径累乘吞吐量（throughput），每次碰到发光体就把 吞吐量 * 发光 加到结果中
+// 与递归的写法期望相同，但不需要为每次弹射保留一层栈
+// 每个漫反射的顶点上做下一事件估计（NEE）：向光源和背景各采样一个方向并发出阴影光线，
+// 再按材质采样下一段路径；三种策略都可能得到发光的贡献，按 MIS 的权重合并
+pub struct PathTracer {
+    pub max_depth: i32, // 路径最多经过多少次碰撞，超过后当作黑色
+    pub rr_depth: i32,  // 经过多少次碰撞后开始俄罗斯轮盘赌
+    pub heuristic: MisHeuristic,
+    pub world: Arc<HittableList>,  // 场景中的所有物体
+    pub lights: Arc<HittableList>, // 需要着重采样的光源
+    pub env: Arc<dyn Environment>,
+}
+
+impl Integrator for PathTracer {
+    fn radiance(
+        &self,
+        r: Ray,
+        _pixel: (u32, u32),
+        _index: u32,
+        sampler: &mut dyn Sampler,
+        _splats: &mut Vec<Splat>,
+    ) -> Vec3 {
+        self.trace(r, sampler, None)
+    }
+}
+
+impl PathTracer {
+    // 给出光子图时，第一个漫反射顶点上的焦散（经过镜面反射到达这里的光源的光）由光子图估计，
+    // 路径追踪就不再计算这部分，以免重复
+    pub fn trace(&self, r: Ray, sampler: &mut dyn Sampler, photons: Option<&PhotonMap>) -> Vec3 {
+        let (world, lights, env) = (&*self.world, &*self.lights, &*self.env);
+        let mut radiance = Vec3::new(0., 0., 0.);
+        let mut throughput = Vec3::new(1., 1., 1.);
+        let mut ray = r;
+        // 上一个顶点的位置和按材质采样的 pdf
+        // 相机和镜面反射发出的光线不可能由光源或背景采样得到，碰到光源时权重为 1
+        let mut prev_p = r.orig;
+        let mut bsdf_pdf = 0.;
+        let mut specular = true;
+        // diffuse_seen: 是否经过了漫反射的顶点
+        // caustic: 是否在第一个漫反射顶点之后的镜面反射链上，此时碰到的光源已经算在光子图中
+        let mut diffuse_seen = false;
+        let mut caustic = false;
+        // 光谱模式下光线带有主波长，颜色都换成三个波长上的光谱值
+        let wavelength = r.wavelength;
+        let mut hero_only = false;
+
+        for bounce in 0..self.max_depth {
+            // t_min 修正为 0.001，因为光线并不是在 t=0 处才会击中物体
+            let hit = world.hit_sampled(ray, 0.001, INFINITY, sampler);
+            //没碰到物体，就是背景的颜色
+            let emitted = match hit {
+                Some(rec) => rec.mat.emitted(ray, rec, rec.u, rec.v, rec.p),
+                None => env.radiance(ray.dir),
+            };
+            let emitted = spectral(emitted, wavelength);
+            let weight = if specular {
+                1.
+            } else {
+                let (light_pdf, env_pdf) = light_pdfs(prev_p, ray.dir, lights, env);
+                self.heuristic.weight(bsdf_pdf, &[light_pdf, env_pdf])
+            };
+            let photon_emitter = match hit {
+                Some(rec) => caustic && specular && lights.surface_pdf(rec.p, rec.normal) > 0.,
+                None => false,
+            };
+            if !photon_emitter {
+                radiance += throughput * emitted * weight;
+            }
+
+            let rec = match hit {
+                Some(rec) => rec,
+                None => break,
+            };
+            //考虑材质的散射，Dissuse light 不会散射，只发光
+            let srec = match rec.mat.scatter(ray, rec, sampler) {
+                Some(srec) => srec,
+                None => break,
+            };
+
+            // 俄罗斯轮盘赌：吞吐量小于 1 时，以吞吐量的最大分量为概率继续，
+            // 继续的路径除以这个概率，期望不变；暗淡的路径很快就会停下
+            // 放在加上这一点的发光之后，朝光源采样的路径权重虽小，打到光源时也不会被丢掉
+            if bounce >= self.rr_depth {
+                let p = throughput.x.max(throughput.y).max(throughput.z);
+                if p < 1. {
+                    if sampler.get_1d() >= p {
+                        break;
+                    }
+                    throughput /= p;
+                }
+            }
+
+            // 色散时折射的方向只对主波长成立，另外两个波长的路径停止，主波长乘以 3 保持期望不变
+            if wavelength > 0. && !hero_only && rec.mat.dispersive() {
+                throughput = Vec3::new(throughput.x * 3., 0., 0.);
+                hero_only = true;
+            }
+            let attenuation = spectral(srec.attenuation, wavelength);
+
+            //除了 Lambertian 和介质，都会发生镜面反射，不对光源采样
+            if srec.is_specular {
+                throughput = throughput * attenuation;
+                ray = srec.scattered;
+                ray.wavelength = wavelength;
+                specular = true;
+                continue;
+            }
+
+            if srec.pdf_type == 2 {
+                // 光子不会穿过介质，经过介质的焦散仍由路径追踪计算
+                caustic = false;
+            } else if let Some(map) = photons {
+                caustic = !diffuse_seen;
+                if caustic {
+                    radiance += throughput * map.radiance(ray, rec, srec.attenuation);
+                }
+                diffuse_seen = true;
+            }
+
+            if !lights.objects.is_empty() {
+                let dir = HittablePDF::new(lights, rec.p).generate(sampler);
+                let (light_pdf, env_pdf) = light_pdfs(rec.p, dir, lights, env);
+                let others = [scatter_pdf(ray, rec, &srec, dir), env_pdf];
+                let direct = self.direct(ray, rec, attenuation, (dir, light_pdf), &others, sampler);
+                radiance += throughput * direct;
+            }
+            if let Some((dir, env_pdf)) = env.sample(sampler) {
+                let (light_pdf, _) = light_pdfs(rec.p, dir, lights, env);
+                let others = [scatter_pdf(ray, rec, &srec, dir), light_pdf];
+                let direct = self.direct(ray, rec, attenuation, (dir, env_pdf), &others, sampler);
+                radiance += throughput * direct;
+            }
+
+            // 按材质（cos 分布）采样下一段路径，介质中直接使用按相函数采样的方向
+            let mut scattered = if srec.pdf_type == 2 {
+                srec.scattered
+            } else {
+                Ray::new(rec.p, srec.cos_pdf.generate(sampler), ray.tm)
+            };
+            scattered.wavelength = wavelength;
+            bsdf_pdf = scatter_pdf(ray, rec, &srec, scattered.dir);
+            if bsdf_pdf <= 0. {
+                break;
+            }
+            throughput =
+                throughput * attenuation * rec.mat.scattering_pdf(ray, rec, scattered) / bsdf_pdf;
+            prev_p = rec.p;
+            specular = false;
+            ray = scattered;
+        }
+        radiance
+    }
+
+    // 沿光源或背景采样到的方向 dir 发出阴影光线，第一次碰到的发光乘上材质与 MIS 的权重
+    // pdf 为采到这个方向的策略的 pdf，others 为另外两种策略的 pdf
+    // 被遮挡时碰到的是不发光的物体，贡献自然为 0
+    fn direct(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+        attenuation: Vec3,
+        (dir, pdf): (Vec3, f64),
+        others: &[f64],
+        sampler: &mut dyn Sampler,
+    ) -> Vec3 {
+        let mut shadow = Ray::new(rec.p, dir, r_in.tm);
+        shadow.wavelength = r_in.wavelength;
+        let scattering_pdf = rec.mat.scattering_pdf(r_in, rec, shadow);
+        if pdf <= 0. || scattering_pdf <= 0. {
+            return Vec3::new(0., 0., 0.);
+        }
+
+        // 与按材质采样时的处理相同，这样三种策略估计的是同一个积分
+        // 阴影光线穿过介质时不散射，用 ratio tracking 估计透射率
+        let world = &*self.world;
+        let emitted = match world.hit_surface(shadow, 0.001, INFINITY) {
+            Some(l) => {
+                l.mat.emitted(shadow, l, l.u, l.v, l.p)
+                    * world.transmittance(shadow, 0.001, l.t, sampler)
+            }
+            None => {
+                self.env.radiance(shadow.dir)
+                    * world.transmittance(shadow, 0.001, INFINITY, sampler)
+            }
+        };
+        let emitted = spectral(emitted, r_in.wavelength);
+        let weight = self.heuristic.weight(pdf, others);
+        attenuation * emitted * (scattering_pdf * weight / pdf)
+    }
+}
+
+// 光线带有波长时，把 RGB 的颜色换成三个波长上的光谱值
+fn spectral(c: Vec3, wavelength: f64) -> Vec3 {
+    if wavelength > 0. {
+        SampledWavelengths::from_hero(wavelength).upsample(c)
+    } else {
+        c
+    }
+}
+
+// 按材质采样得到 dir 方向的 pdf：表面上是 cos 分布，介质中是相函数本身
+fn scatter_pdf(r_in: Ray, rec: HitRecord, srec: &ScatterRecord, dir: Vec3) -> f64 {
+    if srec.pdf_type == 2 {
+        rec.mat
+            .scattering_pdf(r_in, rec, Ray::new(rec.p, dir, r_in.tm))
+    } else {
+        srec.cos_pdf.value(dir)
+    }
+}
+
+// 从 origin 出发的 dir 方向由光源采样和背景采样得到的 pdf
+fn light_pdfs(origin: Vec3, dir: Vec3, lights: &HittableList, env: &dyn Environment) -> (f64, f64) {
+    let light_pdf = if lights.objects.is_empty() {
+        0.
+    } else {
+        HittablePDF::new(lights, origin).value(dir)
+    };
+    (light_pdf, env.pdf(dir))
+}
@@ -0,0 +1,242 @@
+use std::{
+    f64::{consts::PI, INFINITY},
+    ops::Range,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    basic_component::{
+        onb::ONB,
+        ray::Ray,
+        sampler::{hash, Pcg32, Sampler},
+        vec3::Vec3,
+    },
+    hittable::{HitRecord, Hittable},
+    optimization::kdtree::KdTree,
+    render::{emitting_side, path::PathTracer, progressive::Splat, Integrator},
+};
+
+// 每一轮半径平方缩小的比例 (k + α) / (k + 1)，α 越小缩得越快
+const ALPHA: f64 = 2. / 3.;
+// 自动选择初始半径时，让一个光子附近平均有这么多个光子
+const NEIGHBORS: usize = 16;
+
+pub struct Photon {
+    pub dir: Vec3,   // 到达时的传播方向
+    pub power: Vec3, // 携带的光通量
+}
+
+// 一轮发出的光子中，经过镜面反射后落在漫反射表面上的部分
+pub struct PhotonMap {
+    tree: KdTree<Photon>,
+    radius: f64,
+    emitted: usize, // 发出的光子总数
+}
+
+impl PhotonMap {
+    // 漫反射表面上一点沿 r_in 反方向出射的焦散辐射度：半径内光子的通量乘以 BRDF，除以圆盘面积
+    pub fn radiance(&self, r_in: Ray, rec: HitRecord, attenuation: Vec3) -> Vec3 {
+        let mut sum = Vec3::new(0., 0., 0.);
+        self.tree.for_each_within(rec.p, self.radius, |_, photon| {
+            // 只统计从法线一侧射来的光子，薄板另一面的光子不算
+            let cos = -Vec3::dot(Vec3::unit_vector(photon.dir), rec.normal);
+            if cos <= 0. {
+                return;
+            }
+            let wi = Ray::new(rec.p, Vec3::new(0., 0., 0.) - photon.dir, r_in.tm);
+            let f = attenuation * (rec.mat.scattering_pdf(r_in, rec, wi) / cos);
+            sum += f * photon.power;
+        });
+        sum / (PI * self.radius * self.radius * self.emitted as f64)
+    }
+}
+
+// 路径追踪 + 渐进式光子映射（Knaus & Zwicker 2011）
+// 光源发出的光子只沿镜面反射传播，第一次落在漫反射表面时存进光子图；
+// 路径追踪在第一个漫反射顶点上用光子图估计焦散，其余的光照照常计算
+// 每一轮渲染用一张新的光子图，半径逐轮缩小，各轮平均的结果收敛到正确的图像
+
+pub struct PhotonMapper {
+    pub tracer: PathTracer,
+    pub photon_count: usize,  // 每张光子图发出的光子数
+    pub samples_per_map: u32, // 多少次采样共用一张光子图
+    pub seed: u64,
+    pub time0: f64, // 光子在拍摄时间内随机发出
+    pub time1: f64,
+    radius: f64, // 第一张光子图的半径
+    // 这一轮用到的光子图和它们的序号，在 begin_pass 中一次生成，渲染时只读
+    maps: RwLock<Vec<(u32, Arc<PhotonMap>)>>,
+}
+
+impl PhotonMapper {
+    // radius 为 None 时，由第一张光子图中光子的密度决定
+    pub fn new(
+        tracer: PathTracer,
+        photon_count: usize,
+        samples_per_map: u32,
+        radius: Option<f64>,
+        seed: u64,
+        shutter: (f64, f64),
+    ) -> Self {
+        let mut mapper = Self {
+            tracer,
+            photon_count,
+            samples_per_map,
+            seed,
+            time0: shutter.0,
+            time1: shutter.1,
+            radius: 0.,
+            maps: RwLock::new(Vec::new()),
+        };
+        let photons = mapper.trace_photons(0);
+        mapper.radius = match radius {
+            Some(r) => r,
+            None => mapper.estimate_radius(&photons),
+        };
+        let first = mapper.build(0, photons);
+        mapper.maps = RwLock::new(vec![(0, Arc::new(first))]);
+        mapper
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    // 第 index 张光子图；没有经过 begin_pass 准备时临时生成，结果相同，只是慢
+    fn map(&self, index: u32) -> Arc<PhotonMap> {
+        let maps = self.maps.read().unwrap();
+        match maps.iter().find(|(i, _)| *i == index) {
+            Some((_, map)) => map.clone(),
+            None => Arc::new(self.build(index, self.trace_photons(index))),
+        }
+    }
+
+    fn build(&self, index: u32, photons: Vec<(Vec3, Photon)>) -> PhotonMap {
+        // r_i^2 = r_0^2 * Π_{k=1}^{i} (k + α) / (k + 1)
+        let mut r2 = self.radius * self.radius;
+        for k in 1..=index {
+            r2 *= (k as f64 + ALPHA) / (k as f64 + 1.);
+        }
+        PhotonMap {
+            tree: KdTree::new(photons),
+            radius: r2.sqrt(),
+            emitted: self.photon_count,
+        }
+    }
+
+    // 第 index 张光子图的光子，随机数只由种子和 index 决定
+    fn trace_photons(&self, index: u32) -> Vec<(Vec3, Photon)> {
+        let (world, lights) = (&*self.tracer.world, &*self.tracer.lights);
+        let mut rng = Pcg32::new_stream(hash(self.seed, 0x70686f746f6e), index as u64);
+        let mut photons = Vec::new();
+        for _ in 0..self.photon_count {
+            let (p, n, pdf_pos) = match lights.sample_surface(&mut rng) {
+                Some(s) if s.2 > 0. => s,
+                _ => continue,
+            };
+            let tm = rng.random_double(self.time0, self.time1);
+            let (normal, le) = match emitting_side(world, p, n, tm) {
+                Some(side) => side,
+                None => continue,
+            };
+            // 按 cos 分布发出，通量 le * cos / (pdf_pos * pdf_dir) 化简为 le * π / pdf_pos
+            let dir =
+                ONB::build_from_w(normal).local_from_vec(Vec3::random_cosine_direction(&mut rng));
+            let mut power = le * (PI / pdf_pos);
+            let mut ray = Ray::new(p, dir, tm);
+            let mut specular = false;
+            for _ in 0..self.tracer.max_depth {
+                let rec = match world.hit_sampled(ray, 0.001, INFINITY, &mut rng) {
+                    Some(rec) => rec,
+                    None => break,
+                };
+                let srec = match rec.mat.scatter(ray, rec, &mut rng) {
+                    Some(srec) => srec,
+                    None => break,
+                };
+                // 介质中散射的光子不再继续，由路径追踪计算
+                if srec.pdf_type == 2 {
+                    break;
+                }
+                if !srec.is_specular {
+                    // 直接照到漫反射表面的光由路径追踪计算
+                    if specular {
+                        photons.push((
+                            rec.p,
+                            Photon {
+                                dir: ray.dir,
+                                power,
+                            },
+                        ));
+                    }
+                    break;
+                }
+                specular = true;
+                power = power * srec.attenuation;
+                ray = srec.scattered;
+            }
+        }
+        photons
+    }
+
+    // 取一些光子，到第 NEIGHBORS 近的光子的距离的中位数
+    // 焦散处光子密集，这样的半径刚好能看清焦散的形状
+    fn estimate_radius(&self, photons: &[(Vec3, Photon)]) -> f64 {
+        if photons.len() <= NEIGHBORS {
+            // 几乎没有焦散，半径的取值无关紧要
+            return match self.tracer.world.bounding_box(self.time0, self.time1) {
+                Some(b) => (b.maximum - b.minimum).length() * 1e-3,
+                None => 1.,
+            };
+        }
+        let step = (photons.len() / 64).max(1);
+        let mut radii: Vec<f64> = photons
+            .iter()
+            .step_by(step)
+            .map(|(p, _)| {
+                let mut d: Vec<f64> = photons
+                    .iter()
+                    .map(|(q, _)| (*q - *p).length_squared())
+                    .collect();
+                // d 中包含光子自己
+                let (_, kth, _) =
+                    d.select_nth_unstable_by(NEIGHBORS, |a, b| a.partial_cmp(b).unwrap());
+                kth.sqrt()
+            })
+            .collect();
+        let mid = radii.len() / 2;
+        let (_, median, _) = radii.select_nth_unstable_by(mid, |a, b| a.partial_cmp(b).unwrap());
+        *median
+    }
+}
+
+impl Integrator for PhotonMapper {
+    fn radiance(
+        &self,
+        r: Ray,
+        _pixel: (u32, u32),
+        index: u32,
+        sampler: &mut dyn Sampler,
+        _splats: &mut Vec<Splat>,
+    ) -> Vec3 {
+        let map = self.map(index / self.samples_per_map);
+        self.tracer.trace(r, sampler, Some(&map))
+    }
+
+    // 生成这一轮的采样用到的光子图，上一轮留下的图能用的话直接沿用
+    fn begin_pass(&self, samples: Range<u32>) {
+        let mut maps = self.maps.write().unwrap();
+        let first = samples.start / self.samples_per_map;
+        let last = (samples.end - 1) / self.samples_per_map;
+        let next = (first..=last)
+            .map(|index| match maps.iter().find(|(i, _)| *i == index) {
+                Some(entry) => entry.clone(),
+                None => (
+                    index,
+                    Arc::new(self.build(index, self.trace_photons(index))),
+                ),
+            })
+            .collect();
+        *maps = next;
+    }
+}
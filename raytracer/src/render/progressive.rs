@@ -0,0 +1,172 @@
+use std::{ops::Range, sync::Arc};
+
+use crate::{
+    basic_component::vec3::Vec3,
+    film::{filter::Filter, Film, FilmTile},
+    render::scheduler,
+};
+
+// 自适应采样：像素的相对误差低于阈值后就不再采样
+#[derive(Copy, Clone)]
+pub struct Adaptive {
+    pub min_samples: u32, // 至少采样这么多次才判断是否收敛
+    pub threshold: f64,
+}
+
+// 一次采样的结果：采样点在像素内的位置（[0, 1)，相对像素的左下角）和颜色
+// 以及这次采样落在其他位置上的贡献
+pub struct FilmSample {
+    pub dx: f64,
+    pub dy: f64,
+    pub color: Vec3,
+    pub splats: Vec<Splat>,
+}
+
+// 直接加到图像某个位置上的贡献，(x, y) 为整张图像中的连续坐标
+// 不经过滤波器，最后按平均每个像素的采样次数归一化
+#[derive(Copy, Clone)]
+pub struct Splat {
+    pub x: f64,
+    pub y: f64,
+    pub color: Vec3,
+}
+
+// 渐进式渲染：每一轮给每个像素增加若干次采样，累加到 film 中
+// 每一轮结束后都可以输出预览、保存断点
+pub struct Progressive {
+    pub samples_per_pixel: u32, // 目标采样次数，自适应采样时为上限
+    pub samples_per_pass: u32,
+    pub adaptive: Option<Adaptive>,
+    pub filter: Arc<dyn Filter>,
+    pub thread_number: usize,
+}
+
+impl Progressive {
+    // 像素 id 这一轮需要的采样次数，已经完成的像素为 0
+    // 只由 film 中的数据决定，所以与线程数无关，从断点恢复时也一样
+    pub fn pass_samples(&self, film: &Film, id: usize) -> u32 {
+        let done = film.samples[id];
+        if let Some(adaptive) = self.adaptive {
+            if done >= adaptive.min_samples && film.relative_error(id) < adaptive.threshold {
+                return 0;
+            }
+        }
+        self.samples_per_pixel
+            .saturating_sub(done)
+            .min(self.samples_per_pass)
+    }
+
+    // 最多还需要的轮数，自适应采样时可能提前结束
+    pub fn passes_left(&self, film: &Film) -> u32 {
+        let left = (0..film.samples.len())
+            .filter(|&id| self.pass_samples(film, id) > 0)
+            .map(|id| self.samples_per_pixel - film.samples[id])
+            .max()
+            .unwrap_or(0);
+        // 向上取整，最后一轮的采样次数可能不足 samples_per_pass
+        left / self.samples_per_pass + (left % self.samples_per_pass).min(1)
+    }
+
+    // sample(x, y, s) 计算像素 (x, y) 的第 s 次采样
+    // 采样序号接着 film 中已有的次数，所以从断点恢复的结果与一次渲染完相同
+    // 每一轮开始前调用 begin_pass(这一轮的采样序号范围)
+    // 每一轮结束后调用 on_pass(film, 完成的轮数)，返回错误时停止渲染
+    pub fn render<F, B, C>(
+        &self,
+        film: &mut Film,
+        sample: Arc<F>,
+        mut begin_pass: B,
+        mut on_pass: C,
+    ) -> Result<(), String>
+    where
+        F: Fn(u32, u32, u32) -> FilmSample + Send + Sync + 'static,
+        B: FnMut(Range<u32>),
+        C: FnMut(&Film, u32) -> Result<(), String>,
+    {
+        let width = film.width;
+        let pixel_count = (width * film.height) as u64;
+        let progress_bar = scheduler::new_progress_bar(self.passes_left(film) as u64 * pixel_count);
+
+        let mut pass = 0;
+        loop {
+            let todo: Vec<u32> = (0..film.samples.len())
+                .map(|id| self.pass_samples(film, id))
+                .collect();
+            if todo.iter().all(|&n| n == 0) {
+                break;
+            }
+            // 收敛的像素会让剩余的轮数变少
+            progress_bar
+                .set_length(progress_bar.position() + self.passes_left(film) as u64 * pixel_count);
+            let active = || (0..todo.len()).filter(|&id| todo[id] > 0);
+            let first = active().map(|id| film.samples[id]).min().unwrap();
+            let end = active()
+                .map(|id| film.samples[id] + todo[id])
+                .max()
+                .unwrap();
+            begin_pass(first..end);
+
+            let todo = Arc::new(todo);
+            let pass_done = Arc::new(film.samples.clone());
+            let (pass_todo, sample, filter) = (todo.clone(), sample.clone(), self.filter.clone());
+            let pass_result = scheduler::render_tiles(
+                width,
+                film.height,
+                self.thread_number,
+                &progress_bar,
+                move |tile| {
+                    // 每一块先累加到自己的缓冲中，采样会按滤波器的权重分散到相邻的像素
+                    let mut film_tile = FilmTile::new(tile, filter.radius());
+                    let mut stats = Vec::with_capacity(tile.pixel_count());
+                    let mut splats: Vec<Splat> = Vec::new();
+                    for y in tile.y0..tile.y1 {
+                        for x in tile.x0..tile.x1 {
+                            let id = (y * width + x) as usize;
+                            let first = pass_done[id];
+                            let (mut luminance, mut luminance_sq, mut accepted) = (0., 0., 0);
+                            for s in first..first + pass_todo[id] {
+                                let fs = sample(x, y, s);
+                                let finite =
+                                    fs.splats.iter().filter(|s| s.color.luminance().is_finite());
+                                splats.extend(finite);
+                                let l = fs.color.luminance();
+                                // NaN、无穷大的采样会污染周围的像素，直接丢弃
+                                if !l.is_finite() {
+                                    continue;
+                                }
+                                let (px, py) = (x as f64 + fs.dx, y as f64 + fs.dy);
+                                film_tile.add_sample(px, py, fs.color, &*filter);
+                                luminance += l;
+                                luminance_sq += l * l;
+                                accepted += 1;
+                            }
+                            stats.push((luminance, luminance_sq, accepted));
+                        }
+                    }
+                    (film_tile, stats, splats)
+                },
+            )
+            .ok_or_else(|| "rendering failed".to_string())?;
+
+            // 按块的顺序合并，浮点数相加的顺序固定，结果与线程数无关
+            for (tile, (film_tile, stats, splats)) in pass_result {
+                film.merge_tile(&film_tile);
+                for s in splats {
+                    film.add_splat(s.x, s.y, s.color);
+                }
+                let mut stats = stats.into_iter();
+                for y in tile.y0..tile.y1 {
+                    for x in tile.x0..tile.x1 {
+                        let id = film.index(x, y);
+                        let (luminance, luminance_sq, accepted) = stats.next().unwrap();
+                        film.add_stats(id, luminance, luminance_sq, todo[id], accepted);
+                    }
+                }
+            }
+            pass += 1;
+            on_pass(film, pass)?;
+        }
+        progress_bar.finish_with_message("Finished!");
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+
+pub const TILE_SIZE: u32 = 16; // 每一块的边长（像素）
+
+// 图像中的一块矩形区域，左闭右开
+#[derive(Copy, Clone)]
+pub struct Tile {
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+impl Tile {
+    pub fn pixel_count(&self) -> usize {
+        ((self.x1 - self.x0) * (self.y1 - self.y0)) as usize
+    }
+}
+
+// 把图像切成 tile_size * tile_size 的小块，边缘的块可能更小
+pub fn split_tiles(width: u32, height: u32, tile_size: u32) -> Vec<Tile> {
+    let mut tiles = Vec::new();
+    for y0 in (0..height).step_by(tile_size as usize) {
+        for x0 in (0..width).step_by(tile_size as usize) {
+            tiles.push(Tile {
+                x0,
+                y0,
+                x1: (x0 + tile_size).min(width),
+                y1: (y0 + tile_size).min(height),
+            });
+        }
+    }
+    tiles
+}
+
+// 所有线程共享的任务队列，线程空闲时就取走下一块
+pub struct TileQueue {
+    tiles: Vec<Tile>,
+    next: AtomicUsize,
+}
+
+impl TileQueue {
+    pub fn new(tiles: Vec<Tile>) -> Self {
+        Self {
+            tiles,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    // 返回块的序号和块本身
+    pub fn pop(&self) -> Option<(usize, Tile)> {
+        let id = self.next.fetch_add(1, Ordering::Relaxed);
+        self.tiles.get(id).map(|&tile| (id, tile))
+    }
+}
+
+// 进度条，单位为像素
+pub fn new_progress_bar(len: u64) -> ProgressBar {
+    let progress_bar = ProgressBar::new(len);
+    progress_bar.set_style(ProgressStyle::default_bar()
+                            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] [{pos}/{len}] ({eta})")
+                            .progress_chars("#>-"));
+    progress_bar
+}
+
+// 多线程地处理整张图像，tile_fn(tile) 计算一块的结果，每完成一块就推进进度条
+// 返回按块的顺序排列的结果，与线程的调度无关；有线程失败时返回 None
+pub fn render_tiles<F, R>(
+    width: u32,
+    height: u32,
+    thread_number: usize,
+    progress_bar: &ProgressBar,
+    tile_fn: F,
+) -> Option<Vec<(Tile, R)>>
+where
+    F: Fn(Tile) -> R + Send + Sync + 'static,
+    R: Send + 'static,
+{
+    let tiles = split_tiles(width, height, TILE_SIZE);
+    let tile_count = tiles.len();
+    let queue = Arc::new(TileQueue::new(tiles));
+    let tile_fn = Arc::new(tile_fn);
+
+    let (tx, rx) = mpsc::channel(); //信道
+    let mut thread_pool = Vec::new(); // 线程池
+    for _ in 0..thread_number {
+        let queue = queue.clone();
+        let tile_fn = tile_fn.clone();
+        let channel_send = tx.clone();
+        thread_pool.push(thread::spawn(move || {
+            while let Some((id, tile)) = queue.pop() {
+                // 主线程已经退出时，直接结束
+                if channel_send.send((id, tile, tile_fn(tile))).is_err() {
+                    break;
+                }
+            }
+        }));
+    }
+    drop(tx); // 所有线程结束后，信道随之关闭
+
+    // 接收每一块的结果，放到对应的位置
+    let mut results: Vec<Option<(Tile, R)>> = (0..tile_count).map(|_| None).collect();
+    for (id, tile, result) in rx {
+        progress_bar.inc(tile.pixel_count() as u64);
+        results[id] = Some((tile, result));
+    }
+
+    let mut thread_finish_successfully = true;
+    for (thread_id, thread) in thread_pool.into_iter().enumerate() {
+        if thread.join().is_err() {
+            thread_finish_successfully = false;
+            println!(
+                "Joining the {} thread failed!",
+                style(thread_id.to_string()).red()
+            );
+        }
+    }
+    if thread_finish_successfully {
+        results.into_iter().collect()
+    } else {
+        None
+    }
+}
+
+// 多线程渲染整张图像，pixel(x, y) 计算单个像素的结果（通常是颜色）
+// 返回按行排列的结果（y = 0 为图像底部），有线程失败时返回 None
+pub fn render<F, T>(
+    width: u32,
+    height: u32,
+    thread_number: usize,
+    progress_bar: &ProgressBar,
+    pixel: F,
+) -> Option<Vec<T>>
+where
+    F: Fn(u32, u32) -> T + Send + Sync + 'static,
+    T: Copy + Default + Send + 'static,
+{
+    let tiles = render_tiles(width, height, thread_number, progress_bar, move |tile| {
+        let mut tile_color = Vec::with_capacity(tile.pixel_count());
+        for y in tile.y0..tile.y1 {
+            for x in tile.x0..tile.x1 {
+                tile_color.push(pixel(x, y));
+            }
+        }
+        tile_color
+    })?;
+
+    // 把每一块的结果写入对应的位置
+    let mut output_pixel_color = vec![T::default(); (width * height) as usize];
+    for (tile, tile_color) in tiles {
+        let mut colors = tile_color.into_iter();
+        for y in tile.y0..tile.y1 {
+            for x in tile.x0..tile.x1 {
+                output_pixel_color[(y * width + x) as usize] = colors.next().unwrap();
+            }
+        }
+    }
+    Some(output_pixel_color)
+}
@@ -0,0 +1,43 @@
+use crate::{
+    basic_component::{
+        ray::Ray,
+        sampler::Sampler,
+        spectrum::{self, SampledWavelengths},
+        vec3::Vec3,
+    },
+    render::{path::PathTracer, progressive::Splat, Integrator},
+};
+
+// 光谱模式的路径追踪：每次采样随机选一个主波长，与另外两个轮转的波长一起追踪，
+// RGB 的反射率和发光都换成这三个波长上的光谱值，玻璃可以按波长折射（色散）
+// 结果经过 CIE XYZ 转回线性的 sRGB，等能的白光仍为 (1, 1, 1)
+pub struct SpectralTracer {
+    pub tracer: PathTracer,
+    white: Vec3, // 常数为 1 的光谱对应的 sRGB
+}
+
+impl SpectralTracer {
+    pub fn new(tracer: PathTracer) -> Self {
+        Self {
+            tracer,
+            white: spectrum::white_point(),
+        }
+    }
+}
+
+impl Integrator for SpectralTracer {
+    fn radiance(
+        &self,
+        r: Ray,
+        _pixel: (u32, u32),
+        _index: u32,
+        sampler: &mut dyn Sampler,
+        _splats: &mut Vec<Splat>,
+    ) -> Vec3 {
+        let lambdas = SampledWavelengths::sample(sampler.get_1d());
+        let mut ray = r;
+        ray.wavelength = lambdas.lambda[0];
+        let values = self.tracer.trace(ray, sampler, None);
+        spectrum::xyz_to_linear_srgb(lambdas.to_xyz(values)) / self.white
+    }
+}
@@ -0,0 +1,562 @@
+use std::sync::Arc;
+
+use crate::{
+    basic_component::{
+        camera::CameraConfig,
+        sampler::{Pcg32, Sampler},
+        vec3::Vec3,
+    },
+    environment::{ConstantEnvironment, Environment},
+    hittable::{
+        instance::{
+            constant_medium::ConstantMedium,
+            flipface::Flipface,
+            rotate::{RotateX, RotateY, RotateZ},
+            translate::Translate,
+            zoom::Zoom,
+        },
+        objects::{
+            aarect::{XYRect, XZRect, YZRect},
+            cube::Cube,
+            sphere::{MovingSphere, Sphere},
+        },
+        objloader::OBJ,
+        stlloader::STL,
+        HittableList,
+    },
+    material::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal,
+    },
+    optimization::bvh::BvhNode,
+    scene::{RenderSettings, Scene},
+    texture::{
+        checker::CheckerTexture,
+        image::ImageTexture,
+        perlin::{NoiseTexture, Perlin},
+        solid::SolidColor,
+    },
+};
+
+// 内置的场景，可以在命令行中通过名字选择
+pub struct BuiltinScene {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub build: fn(u64) -> Scene, // 参数为随机种子
+}
+
+pub static BUILTIN_SCENES: [BuiltinScene; 7] = [
+    BuiltinScene {
+        name: "cornell_box",
+        description: "Cornell box with a metal box and an astronaut model",
+        build: cornell_box_scene,
+    },
+    BuiltinScene {
+        name: "book2",
+        description: "Final scene of Ray Tracing: The Next Week",
+        build: book2_scene,
+    },
+    BuiltinScene {
+        name: "my_scene",
+        description: "Room with image textures and two OBJ models",
+        build: my_scene_scene,
+    },
+    BuiltinScene {
+        name: "random_scene",
+        description: "Final scene of Ray Tracing in One Weekend",
+        build: random_scene_scene,
+    },
+    BuiltinScene {
+        name: "two_spheres",
+        description: "Two spheres with Perlin noise textures",
+        build: two_spheres_scene,
+    },
+    BuiltinScene {
+        name: "earth",
+        description: "A globe with an image texture",
+        build: earth_scene,
+    },
+    BuiltinScene {
+        name: "simple_light",
+        description: "Noise textured spheres lit by a rectangle and a sphere light",
+        build: simple_light_scene,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static BuiltinScene> {
+    BUILTIN_SCENES.iter().find(|s| s.name == name)
+}
+
+// 《Ray Tracing in One Weekend》中的相机位置
+fn book1_camera(vfov: f64, aperture: f64) -> CameraConfig {
+    CameraConfig {
+        lookfrom: Vec3::new(13., 2., 3.),
+        lookat: Vec3::new(0., 0., 0.),
+        vfov,
+        aperture,
+        ..Default::default()
+    }
+}
+
+fn sky_background() -> Arc<dyn Environment> {
+    Arc::new(ConstantEnvironment::new(Vec3::new(0.7, 0.8, 1.)))
+}
+
+fn black_background() -> Arc<dyn Environment> {
+    Arc::new(ConstantEnvironment::new(Vec3::new(0., 0., 0.)))
+}
+
+fn cornell_box_scene(_seed: u64) -> Scene {
+    Scene {
+        world: cornell_box(),
+        lights: add_cornell_lights(),
+        camera: Default::default(),
+        background: black_background(),
+        settings: Default::default(),
+    }
+}
+
+fn book2_scene(seed: u64) -> Scene {
+    Scene {
+        world: scene_book2(seed),
+        lights: add_book2_lights(),
+        camera: CameraConfig {
+            lookfrom: Vec3::new(478., 278., -600.),
+            ..Default::default()
+        },
+        background: black_background(),
+        settings: RenderSettings {
+            samples_per_pixel: 100,
+            ..Default::default()
+        },
+    }
+}
+
+fn my_scene_scene(_seed: u64) -> Scene {
+    Scene {
+        world: my_scene(),
+        lights: add_my_lights(),
+        camera: Default::default(),
+        background: black_background(),
+        settings: Default::default(),
+    }
+}
+
+fn random_scene_scene(seed: u64) -> Scene {
+    Scene {
+        world: random_scene(seed),
+        lights: Default::default(),
+        camera: book1_camera(20., 0.1),
+        background: sky_background(),
+        settings: RenderSettings {
+            width: 1200,
+            height: 800,
+            ..Default::default()
+        },
+    }
+}
+
+fn two_spheres_scene(seed: u64) -> Scene {
+    Scene {
+        world: two_spheres(seed),
+        lights: Default::default(),
+        camera: book1_camera(20., 0.),
+        background: sky_background(),
+        settings: RenderSettings {
+            height: 450,
+            ..Default::default()
+        },
+    }
+}
+
+fn earth_scene(_seed: u64) -> Scene {
+    Scene {
+        world: earth(),
+        lights: Default::default(),
+        camera: book1_camera(20., 0.),
+        background: sky_background(),
+        settings: RenderSettings {
+            height: 450,
+            ..Default::default()
+        },
+    }
+}
+
+fn simple_light_scene(seed: u64) -> Scene {
+    Scene {
+        world: simple_light(seed),
+        lights: add_simple_lights(),
+        camera: CameraConfig {
+            lookfrom: Vec3::new(26., 3., 6.),
+            lookat: Vec3::new(0., 2., 0.),
+            vfov: 20.,
+            ..Default::default()
+        },
+        background: black_background(),
+        settings: RenderSettings {
+            height: 450,
+            samples_per_pixel: 200,
+            ..Default::default()
+        },
+    }
+}
+
+fn add_cornell_lights() -> HittableList {
+    let mut lights: HittableList = Default::default();
+
+    let light = DiffuseLight::new_from_color(Vec3::new(15., 15., 15.));
+    lights.add(XZRect::new(213., 343., 227., 332., 554., light));
+
+    lights
+}
+
+fn add_book2_lights() -> HittableList {
+    let mut lights: HittableList = Default::default();
+
+    let light = DiffuseLight::new_from_color(Vec3::new(7., 7., 7.));
+    lights.add(XZRect::new(123., 423., 147., 412., 554., light));
+
+    lights
+}
+
+fn scene_book2(seed: u64) -> HittableList {
+    let mut boxes1: HittableList = Default::default();
+    let mut boxes2: HittableList = Default::default();
+    let mut world: HittableList = Default::default();
+
+    let ground = Lambertian::new_from_color(Vec3::new(0.48, 0.83, 0.53));
+    // 生成凹凸的地面
+    let boxes_per_side = 20;
+    let mut sampler = Pcg32::new(seed); // 从特定的种子生成
+    for i in 0..boxes_per_side {
+        for j in 0..boxes_per_side {
+            let w = 100.;
+            let x0 = -1000. + i as f64 * w;
+            let z0 = -1000. + j as f64 * w;
+            let y0 = 0.;
+            let x1 = x0 + w;
+            let y1 = sampler.random_double(1.0, 101.0);
+            // 伪随机，保证每次画出的图形相同
+            let z1 = z0 + w;
+
+            boxes1.add(Cube::new(
+                Vec3::new(x0, y0, z0),
+                Vec3::new(x1, y1, z1),
+                ground,
+            ));
+        }
+    }
+    world.add(BvhNode::new_from_list(boxes1, 0., 1., &mut sampler));
+
+    // 顶部的矩形光源
+    let light = DiffuseLight::new_from_color(Vec3::new(7., 7., 7.));
+    let reverse = Flipface::new(XZRect::new(123., 423., 147., 412., 554., light));
+    world.add(reverse);
+
+    let center1 = Vec3::new(400., 400., 200.);
+    let center2 = center1 + Vec3::new(30., 0., 0.);
+    let moving_sphere_material = Lambertian::new_from_color(Vec3::new(0.7, 0.3, 0.1));
+    world.add(MovingSphere::new(
+        50.,
+        center1,
+        center2,
+        0.,
+        1.,
+        moving_sphere_material,
+    ));
+
+    // let aluminum = Metal::new(Vec3::new(0.8, 0.85, 0.88), 0.);
+    world.add(Sphere::new(
+        Vec3::new(260., 150., 45.),
+        50.,
+        Dielectric::new(1.5),
+    ));
+    world.add(Sphere::new(
+        Vec3::new(0., 150., 145.),
+        50.,
+        Metal::new(Vec3::new(0.8, 0.8, 0.9), 1.),
+    ));
+
+    // 加上黑雾作为背景
+    let mut boundary = Sphere::new(Vec3::new(360., 150., 145.), 70., Dielectric::new(1.5));
+    world.add(boundary); // 额外加入一个雾化的球（恰好在中央）
+    world.add(ConstantMedium::new_from_color(
+        boundary,
+        0.2,
+        Vec3::new(0.2, 0.4, 0.9),
+    ));
+    boundary = Sphere::new(Vec3::new(0., 0., 0.), 5000., Dielectric::new(1.5));
+    world.add(ConstantMedium::new_from_color(
+        boundary,
+        0.0001,
+        Vec3::new(1., 1., 1.),
+    ));
+
+    // 地球贴图的球体
+    let emat = Lambertian::new(ImageTexture::new_from_file("import_pic/earthmap.jpg"));
+    world.add(Sphere::new(Vec3::new(400., 200., 400.), 100., emat));
+    let pertext = NoiseTexture::new(Perlin::new(&mut sampler), 0.1);
+    world.add(Sphere::new(
+        Vec3::new(220., 280., 300.),
+        80.,
+        Lambertian::new(pertext),
+    )); // 加上频率为 0.1 的噪声纹理，变为蓝色的球
+
+    let white = Lambertian::new_from_color(Vec3::new(0.73, 0.73, 0.73));
+    let ns = 1000; //小球个数
+    for _j in 0..ns {
+        boxes2.add(Sphere::new(
+            Vec3 {
+                x: sampler.random_double(0.0, 165.0),
+                y: sampler.random_double(0.0, 165.0),
+                z: sampler.random_double(0.0, 165.0),
+            }, // 伪随机
+            10.,
+            white,
+        ));
+    }
+    // 由小球组成的立方体，旋转+平移
+    world.add(Translate::new(
+        RotateY::new(BvhNode::new_from_list(boxes2, 0., 1., &mut sampler), 15.),
+        Vec3::new(-100., 270., 395.),
+    ));
+
+    world
+}
+
+fn cornell_box() -> HittableList {
+    let mut world: HittableList = Default::default();
+
+    let red = Lambertian::new_from_color(Vec3::new(0.65, 0.05, 0.05));
+    let white = Lambertian::new_from_color(Vec3::new(0.73, 0.73, 0.73));
+    let green = Lambertian::new_from_color(Vec3::new(0.12, 0.45, 0.15));
+    let light = DiffuseLight::new_from_color(Vec3::new(15., 15., 15.));
+    // 用颜色来控制亮度？
+
+    world.add(YZRect::new(0., 555., 0., 555., 555., green));
+    world.add(YZRect::new(0., 555., 0., 555., 0., red));
+    world.add(XZRect::new(0., 555., 0., 555., 0., white));
+    world.add(XZRect::new(0., 555., 0., 555., 555., white));
+    world.add(XYRect::new(0., 555., 0., 555., 555., white));
+
+    world.add(Flipface::new(XZRect::new(
+        213., 343., 227., 332., 554., light,
+    )));
+
+    let aluminum = Metal::new(Vec3::new(0.8, 0.85, 0.88), 0.);
+    let box1 = Cube::new(Vec3::new(0., 0., 0.), Vec3::new(165., 330., 165.), aluminum);
+    // 先旋转再平移
+    let rt1 = RotateY::new(box1, 15.); //旋转后的立方体 rt1
+    let tr1 = Translate::new(rt1, Vec3::new(265., 0., 295.)); //平移后的立方体 tr1
+    world.add(tr1);
+    // 同理
+    let box2 = Cube::new(Vec3::new(0., 0., 0.), Vec3::new(165., 165., 165.), white);
+    let rt2 = RotateY::new(box2, -18.);
+    let tr2 = Translate::new(rt2, Vec3::new(130., 0., 65.));
+    world.add(tr2);
+
+    let obj = STL::load_from_file("import_pic/someobj/astronaut.stl", 0., 1., red);
+    let t1 = Zoom::new(obj, Vec3::new(3., 3., 3.));
+    let t2 = RotateX::new(t1, 90.);
+    let t3 = RotateY::new(t2, 180.);
+    let t4 = Translate::new(t3, Vec3::new(450., 350., 400.));
+    world.add(t4);
+
+    world
+}
+
+fn add_my_lights() -> HittableList {
+    let mut lights: HittableList = Default::default();
+
+    let light = DiffuseLight::new_from_color(Vec3::new(15., 15., 15.));
+    lights.add(Cube::new(
+        Vec3::new(213., 530., 127.),
+        Vec3::new(343., 550., 232.),
+        light,
+    ));
+    lights.add(XYRect::new(50., 505., 50., 382., -801., light));
+
+    lights
+}
+
+fn my_scene() -> HittableList {
+    let mut world: HittableList = Default::default();
+
+    let white = Lambertian::new_from_color(Vec3::new(0.73, 0.73, 0.73));
+    let light = DiffuseLight::new_from_color(Vec3::new(15., 15., 15.));
+
+    world.add(YZRect::new(
+        -100.,
+        620.,
+        -400.,
+        785.,
+        855.,
+        Lambertian::new(ImageTexture::new_from_file("import_pic/vapor.png")),
+    ));
+    world.add(YZRect::new(
+        -100.,
+        620.,
+        -400.,
+        785.,
+        -300.,
+        Lambertian::new(ImageTexture::new_from_file("import_pic/disco.png")),
+    ));
+    // 遮挡层，为了实现金属反光
+    world.add(YZRect::new(-100., 620., -802., 100., -299., white));
+    world.add(YZRect::new(-100., 620., -802., 100., 854., white));
+
+    world.add(Flipface::new(Cube::new(
+        Vec3::new(213., 530., 127.),
+        Vec3::new(343., 550., 232.),
+        light,
+    )));
+    world.add(XYRect::new(50., 505., 50., 382., -801., light));
+    world.add(XYRect::new(-200., 755., 0., 555., -802., white));
+
+    world.add(XZRect::new(-200., 755., -802., 555., 0., white));
+    world.add(XZRect::new(-200., 755., -802., 555., 555., white));
+    world.add(XYRect::new(
+        -200.,
+        755.,
+        0.,
+        555.,
+        500.,
+        Lambertian::new(ImageTexture::new_from_file("import_pic/cyberpunk.png")),
+    ));
+
+    let tp_obj = OBJ::load_from_file("import_pic/someobj/thomas.obj", 0., 1.);
+    let tp1 = Zoom::new(tp_obj, Vec3::new(35., 35., 35.));
+    let tp2 = RotateY::new(tp1, 220.);
+    let tp3 = RotateX::new(tp2, 0.);
+    let tp4 = RotateZ::new(tp3, 0.);
+    let tp5 = Translate::new(tp4, Vec3::new(500., 100., 300.));
+    world.add(tp5);
+
+    let tp_obj2 = OBJ::load_from_file("import_pic/someobj/guy.obj", 0., 1.);
+    let t1 = Zoom::new(tp_obj2, Vec3::new(20., 20., 20.));
+    let t2 = RotateY::new(t1, 130.);
+    let t3 = Translate::new(t2, Vec3::new(0., 50., 150.));
+    world.add(t3);
+
+    world
+}
+
+fn simple_light(seed: u64) -> HittableList {
+    let mut world: HittableList = Default::default();
+    let mut sampler = Pcg32::new(seed);
+
+    let mat1 = Lambertian::new(NoiseTexture::new(Perlin::new(&mut sampler), 4.));
+    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., mat1));
+    world.add(Sphere::new(Vec3::new(0., 2., 0.), 2., mat1));
+
+    let mat2 = DiffuseLight::new_from_color(Vec3::new(4., 4., 4.));
+    world.add(XYRect::new(3., 5., 1., 3., -2., mat2));
+    world.add(Sphere::new(Vec3::new(0., 7., 0.), 2., mat2));
+
+    world
+}
+
+fn earth() -> HittableList {
+    let mut world: HittableList = Default::default();
+    let image = ImageTexture::new_from_file("import_pic/earthmap.jpg");
+    let mat1 = Lambertian::new(image);
+
+    world.add(Sphere::new(Vec3::new(0., 0., 0.), 2., mat1));
+
+    world
+}
+
+fn two_spheres(seed: u64) -> HittableList {
+    let mut world: HittableList = Default::default();
+    let mut sampler = Pcg32::new(seed);
+    // let checker = CheckerTexture {
+    //     odd : SolidColor::new(0.2, 0.3, 0.1),
+    //     even : SolidColor::new(0.9, 0.9, 0.9),
+    // };
+    let mat1 = Lambertian::new(NoiseTexture::new(Perlin::new(&mut sampler), 4.));
+
+    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., mat1));
+    world.add(Sphere::new(Vec3::new(0., 2., 0.), 2., mat1));
+
+    world
+}
+
+fn random_scene(seed: u64) -> HittableList {
+    let mut world: HittableList = Default::default();
+    let mut sampler = Pcg32::new(seed); // 小球的位置、材质由种子决定
+
+    let checker = CheckerTexture {
+        odd: SolidColor::new(0.2, 0.3, 0.1),
+        even: SolidColor::new(0.9, 0.9, 0.9),
+    }; //棋盘状的纹理
+    let ground_material = Lambertian::new(checker);
+
+    world.add(Sphere::new(
+        Vec3::new(0., -1000., 0.),
+        1000.,
+        ground_material,
+    ));
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = sampler.random_double(0.0, 1.0);
+            let _center = Vec3::new(
+                a as f64 + sampler.random_double(0.0, 1.0),
+                0.2,
+                b as f64 + sampler.random_double(0.0, 1.0),
+            );
+
+            if (_center - Vec3::new(4., 0.2, 0.)).length() > 0.9 {
+                if choose_mat < 0.8 {
+                    //diffuse
+                    let _albedo =
+                        Vec3::random(0., 1., &mut sampler) * Vec3::random(0., 1., &mut sampler);
+                    //随机生成小球的反照率
+                    //用反照率对应生成'纹理'颜色
+                    let sphere_material = Lambertian::new_from_color(_albedo);
+                    let _center2 = _center + Vec3::new(0., sampler.random_double(0.0, 0.5), 0.);
+                    // world.add(sphere::Sphere {
+                    //     center: _center,
+                    //     radius: 0.2,
+                    //     mat: sphere_material,
+                    // });
+                    world.add(MovingSphere::new(
+                        0.2,
+                        _center,
+                        _center2,
+                        0.,
+                        1.,
+                        sphere_material,
+                    ));
+                } else if choose_mat < 0.95 {
+                    //metal
+                    let _albedo = Vec3::random(0.5, 1., &mut sampler);
+                    let _fuzz = sampler.random_double(0.0, 0.5);
+                    let sphere_material = Metal::new(_albedo, _fuzz);
+                    world.add(Sphere::new(_center, 0.2, sphere_material));
+                } else {
+                    //glass
+                    let sphere_material = Dielectric::new(1.5);
+                    world.add(Sphere::new(_center, 0.2, sphere_material));
+                }
+            }
+        }
+    }
+
+    let mat_1 = Dielectric::new(1.5);
+    world.add(Sphere::new(Vec3::new(0., 1., 0.), 1., mat_1));
+    let mat_2 = Lambertian::new_from_color(Vec3::new(0.4, 0.2, 0.1));
+    world.add(Sphere::new(Vec3::new(-4., 1., 0.), 1., mat_2));
+    let mat_3 = Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.);
+    world.add(Sphere::new(Vec3::new(4., 1., 0.), 1., mat_3));
+
+    world
+}
+
+fn add_simple_lights() -> HittableList {
+    let mut lights: HittableList = Default::default();
+
+    let light = DiffuseLight::new_from_color(Vec3::new(4., 4., 4.));
+    lights.add(XYRect::new(3., 5., 1., 3., -2., light));
+    lights.add(Sphere::new(Vec3::new(0., 7., 0.), 2., light));
+
+    lights
+}
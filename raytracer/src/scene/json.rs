@@ -0,0 +1,436 @@
+use std::fmt;
+
+// 带行号的 JSON 解析，行号用于场景文件的报错
+// 额外支持以 // 开头的行注释，方便在场景文件中写说明
+
+#[derive(Clone, Debug)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>), // 保留键的顺序
+}
+
+#[derive(Clone, Debug)]
+pub struct Json {
+    pub line: usize, // 该值在文件中开始的行（从 1 开始）
+    pub value: JsonValue,
+}
+
+#[derive(Debug)]
+pub struct SceneError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl SceneError {
+    pub fn new(line: usize, message: String) -> Self {
+        Self { line, message }
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl Json {
+    pub fn type_name(&self) -> &'static str {
+        match self.value {
+            JsonValue::Null => "null",
+            JsonValue::Bool(_) => "a boolean",
+            JsonValue::Number(_) => "a number",
+            JsonValue::String(_) => "a string",
+            JsonValue::Array(_) => "an array",
+            JsonValue::Object(_) => "an object",
+        }
+    }
+
+    pub fn error(&self, message: String) -> SceneError {
+        SceneError::new(self.line, message)
+    }
+
+    // 在对象中查找键
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match &self.value {
+            JsonValue::Object(pairs) => pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self, what: &str) -> Result<&Vec<(String, Json)>, SceneError> {
+        match &self.value {
+            JsonValue::Object(pairs) => Ok(pairs),
+            _ => Err(self.error(format!(
+                "{} must be an object, found {}",
+                what,
+                self.type_name()
+            ))),
+        }
+    }
+
+    pub fn as_array(&self, what: &str) -> Result<&Vec<Json>, SceneError> {
+        match &self.value {
+            JsonValue::Array(items) => Ok(items),
+            _ => Err(self.error(format!(
+                "{} must be an array, found {}",
+                what,
+                self.type_name()
+            ))),
+        }
+    }
+
+    pub fn as_f64(&self, what: &str) -> Result<f64, SceneError> {
+        match self.value {
+            JsonValue::Number(x) => Ok(x),
+            _ => Err(self.error(format!(
+                "{} must be a number, found {}",
+                what,
+                self.type_name()
+            ))),
+        }
+    }
+
+    pub fn as_bool(&self, what: &str) -> Result<bool, SceneError> {
+        match self.value {
+            JsonValue::Bool(b) => Ok(b),
+            _ => Err(self.error(format!(
+                "{} must be a boolean, found {}",
+                what,
+                self.type_name()
+            ))),
+        }
+    }
+
+    pub fn as_str(&self, what: &str) -> Result<&str, SceneError> {
+        match &self.value {
+            JsonValue::String(s) => Ok(s),
+            _ => Err(self.error(format!(
+                "{} must be a string, found {}",
+                what,
+                self.type_name()
+            ))),
+        }
+    }
+
+    // [x, y, z] 形式的数组
+    pub fn as_f64_array(&self, what: &str, len: usize) -> Result<Vec<f64>, SceneError> {
+        let items = self.as_array(what)?;
+        if items.len() != len {
+            return Err(self.error(format!(
+                "{} must have {} elements, found {}",
+                what,
+                len,
+                items.len()
+            )));
+        }
+        items.iter().map(|x| x.as_f64(what)).collect()
+    }
+}
+
+pub fn parse(text: &str) -> Result<Json, SceneError> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace()?;
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("unexpected trailing characters".to_string()));
+    }
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn error(&self, message: String) -> SceneError {
+        SceneError::new(self.line, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SceneError> {
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(format!("expected `{}`, found `{}`", expected, c))),
+            None => Err(self.error(format!("expected `{}`, found end of file", expected))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), SceneError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('/') => {
+                    // 行注释
+                    self.bump();
+                    if self.peek() != Some('/') {
+                        return Err(self.error("unexpected `/`".to_string()));
+                    }
+                    while let Some(c) = self.bump() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, SceneError> {
+        self.skip_whitespace()?;
+        let line = self.line;
+        let value = match self.peek() {
+            None => return Err(self.error("unexpected end of file".to_string())),
+            Some('{') => self.parse_object()?,
+            Some('[') => self.parse_array()?,
+            Some('"') => JsonValue::String(self.parse_string()?),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number()?,
+            Some(c) if c.is_ascii_alphabetic() => {
+                let word = self.take_while(|c| c.is_ascii_alphanumeric());
+                match word.as_str() {
+                    "true" => JsonValue::Bool(true),
+                    "false" => JsonValue::Bool(false),
+                    "null" => JsonValue::Null,
+                    _ => return Err(self.error(format!("unexpected identifier `{}`", word))),
+                }
+            }
+            Some(c) => return Err(self.error(format!("unexpected character `{}`", c))),
+        };
+        Ok(Json { line, value })
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, f: F) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek() {
+            if !f(c) {
+                break;
+            }
+            s.push(c);
+            self.bump();
+        }
+        s
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, SceneError> {
+        let s = self.take_while(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+        match s.parse::<f64>() {
+            Ok(x) if x.is_finite() => Ok(JsonValue::Number(x)),
+            _ => Err(self.error(format!("invalid number `{}`", s))),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, SceneError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string".to_string())),
+                Some('\n') => return Err(self.error("newline in string".to_string())),
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let c = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let hex = self.take_hex4()?;
+                            std::char::from_u32(hex).ok_or_else(|| {
+                                self.error(format!("invalid unicode escape `{:04x}`", hex))
+                            })?
+                        }
+                        _ => return Err(self.error("invalid escape in string".to_string())),
+                    };
+                    s.push(c);
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn take_hex4(&mut self) -> Result<u32, SceneError> {
+        let mut hex = 0;
+        for _ in 0..4 {
+            let digit = self
+                .bump()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("invalid unicode escape".to_string()))?;
+            hex = hex * 16 + digit;
+        }
+        Ok(hex)
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, SceneError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace()?;
+        if self.peek() == Some(']') {
+            self.bump();
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace()?;
+            match self.bump() {
+                Some(',') => continue,
+                Some(']') => return Ok(JsonValue::Array(items)),
+                _ => return Err(self.error("expected `,` or `]` in array".to_string())),
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, SceneError> {
+        self.expect('{')?;
+        let mut pairs: Vec<(String, Json)> = Vec::new();
+        self.skip_whitespace()?;
+        if self.peek() == Some('}') {
+            self.bump();
+            return Ok(JsonValue::Object(pairs));
+        }
+        loop {
+            self.skip_whitespace()?;
+            let line = self.line;
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a string key in object".to_string()));
+            }
+            let key = self.parse_string()?;
+            if pairs.iter().any(|(k, _)| *k == key) {
+                return Err(SceneError::new(line, format!("duplicate key `{}`", key)));
+            }
+            self.skip_whitespace()?;
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            pairs.push((key, value));
+            self.skip_whitespace()?;
+            match self.bump() {
+                Some(',') => continue,
+                Some('}') => return Ok(JsonValue::Object(pairs)),
+                _ => return Err(self.error("expected `,` or `}` in object".to_string())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_of(text: &str) -> SceneError {
+        parse(text).expect_err("the text should not parse")
+    }
+
+    #[test]
+    fn parses_values_and_lines() {
+        let text = "// 注释\n{\n  \"a\": [1, -2.5e1, true],\n  // 注释\n  \"b\": {\"c\": null, \"d\": \"x\\ny\\u0041\"}\n}\n";
+        let root = parse(text).unwrap();
+        assert_eq!(root.line, 2);
+
+        let a = root.get("a").unwrap();
+        assert_eq!(a.line, 3);
+        let items = a.as_array("a").unwrap();
+        assert_eq!(items[0].as_f64("").unwrap(), 1.);
+        assert_eq!(items[1].as_f64("").unwrap(), -25.);
+        assert!(items[2].as_bool("").unwrap());
+
+        let b = root.get("b").unwrap();
+        assert_eq!(b.line, 5);
+        assert!(matches!(b.get("c").unwrap().value, JsonValue::Null));
+        assert_eq!(b.get("d").unwrap().as_str("").unwrap(), "x\nyA");
+    }
+
+    #[test]
+    fn keeps_key_order() {
+        let root = parse("{\"z\": 1, \"a\": 2, \"m\": 3}").unwrap();
+        let keys: Vec<&str> = root
+            .as_object("")
+            .unwrap()
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(keys, ["z", "a", "m"]);
+    }
+
+    #[test]
+    fn reports_lines_of_syntax_errors() {
+        let e = error_of("{\n  \"a\": 1\n  \"b\": 2\n}");
+        assert_eq!(e.line, 3);
+        assert!(e.message.contains("expected `,` or `}`"), "{}", e.message);
+
+        let e = error_of("[\n1,\n2,\n]");
+        assert_eq!(e.line, 4);
+
+        let e = error_of("{\n\"a\": \"abc\n\"}");
+        assert_eq!(e.line, 3);
+        assert_eq!(e.message, "newline in string");
+
+        let e = error_of("{\n\n\"a\": 1.2.3}");
+        assert_eq!(e.line, 3);
+        assert_eq!(e.message, "invalid number `1.2.3`");
+
+        let e = error_of("{\"a\": 1}\n/ 注释");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "unexpected `/`");
+
+        let e = error_of("{\"a\": 1}\n\n2");
+        assert_eq!(e.line, 3);
+        assert_eq!(e.message, "unexpected trailing characters");
+
+        let e = error_of("{\n\"a\": tru\n}");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "unexpected identifier `tru`");
+
+        let e = error_of("{\n\"a\": [1, 2");
+        assert_eq!(e.line, 2);
+    }
+
+    #[test]
+    fn reports_duplicate_keys_at_the_second_key() {
+        let e = error_of("{\n\"a\": 1,\n\n\"a\": 2\n}");
+        assert_eq!(e.line, 4);
+        assert_eq!(e.message, "duplicate key `a`");
+    }
+
+    #[test]
+    fn reports_type_errors_at_the_value() {
+        let root = parse("{\n\"a\":\n  [1, 2]\n}").unwrap();
+        let a = root.get("a").unwrap();
+        let e = a.as_f64("`a`").unwrap_err();
+        assert_eq!(e.line, 3);
+        assert_eq!(e.message, "`a` must be a number, found an array");
+        let e = a.as_f64_array("`a`", 3).unwrap_err();
+        assert_eq!(e.message, "`a` must have 3 elements, found 2");
+        assert_eq!(e.to_string(), "line 3: `a` must have 3 elements, found 2");
+    }
+}
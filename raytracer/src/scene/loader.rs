@@ -0,0 +1,957 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use crate::{
+    basic_component::{
+        camera::CameraConfig,
+        sampler::Pcg32,
+        spectrum::{LAMBDA_MAX, LAMBDA_MIN},
+        vec3::Vec3,
+    },
+    environment::{image::ImageEnvironment, sky::SkyEnvironment, ConstantEnvironment, Environment},
+    hittable::{
+        instance::{
+            constant_medium::ConstantMedium,
+            flipface::Flipface,
+            heterogeneous_medium::HeterogeneousMedium,
+            rotate::{RotateX, RotateY, RotateZ},
+            translate::Translate,
+            zoom::Zoom,
+        },
+        objects::{
+            aarect::{XYRect, XZRect, YZRect},
+            cube::Cube,
+            sphere::{MovingSphere, Sphere},
+            triangle::Triangle,
+        },
+        objloader::OBJ,
+        stlloader::STL,
+        Hittable, HittableList,
+    },
+    material::{
+        dielectric::{Dielectric, Dispersion},
+        diffuse_light::DiffuseLight,
+        isotropic::Isotropic,
+        lambertian::Lambertian,
+        metal::Metal,
+        phase::{
+            DoubleHenyeyGreenstein, HenyeyGreenstein, IsotropicPhase, PhaseFunction, Rayleigh,
+        },
+        Material,
+    },
+    optimization::bvh::BvhNode,
+    scene::{
+        json::{self, Json, JsonValue, SceneError},
+        RenderSettings, Scene,
+    },
+    texture::{
+        checker::CheckerTexture,
+        image::ImageTexture,
+        perlin::{NoiseTexture, Perlin, TurbulenceTexture},
+        solid::SolidColor,
+        voxel::VoxelGrid,
+        Texture,
+    },
+};
+
+pub fn load_scene_file(path: &str, seed: u64) -> Result<Scene, SceneError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| SceneError::new(0, format!("cannot read `{}`: {}", path, e)))?;
+    load_scene(&text, seed)
+}
+
+pub fn load_scene(text: &str, seed: u64) -> Result<Scene, SceneError> {
+    let root = json::parse(text)?;
+    check_keys(
+        &root,
+        "scene",
+        &[
+            "camera",
+            "background",
+            "textures",
+            "materials",
+            "objects",
+            "lights",
+            "settings",
+        ],
+    )?;
+
+    let camera = match root.get("camera") {
+        Some(j) => parse_camera(j)?,
+        None => Default::default(),
+    };
+    let background = match root.get("background") {
+        Some(j) => parse_background(j)?,
+        None => Arc::new(ConstantEnvironment::new(Vec3::new(0., 0., 0.))),
+    };
+    let settings = match root.get("settings") {
+        Some(j) => parse_settings(j)?,
+        None => Default::default(),
+    };
+
+    let mut builder = Builder {
+        textures: HashMap::new(),
+        materials: HashMap::new(),
+        time0: camera.time0,
+        time1: camera.time1,
+        sampler: Pcg32::new(seed),
+    };
+
+    // 纹理、材质按照声明的顺序构造，只能引用在前面声明过的名字
+    if let Some(j) = root.get("textures") {
+        for (name, t) in j.as_object("`textures`")? {
+            let texture = builder.build_texture(t)?;
+            builder.textures.insert(name.clone(), texture);
+        }
+    }
+    if let Some(j) = root.get("materials") {
+        for (name, m) in j.as_object("`materials`")? {
+            let material = builder.build_material(m)?;
+            builder.materials.insert(name.clone(), material);
+        }
+    }
+
+    let mut world: HittableList = Default::default();
+    let mut lights: HittableList = Default::default();
+    if let Some(j) = root.get("objects") {
+        for o in j.as_array("`objects`")? {
+            world.objects.push(builder.build_object(o, false)?);
+            // "light": true 表示同时作为重要性采样的光源，光源不需要翻转
+            if let Some(flag) = o.get("light") {
+                if flag.as_bool("`light`")? {
+                    lights.objects.push(builder.build_object(o, true)?);
+                }
+            }
+        }
+    }
+    if let Some(j) = root.get("lights") {
+        for o in j.as_array("`lights`")? {
+            lights.objects.push(builder.build_object(o, true)?);
+        }
+    }
+
+    Ok(Scene {
+        world,
+        lights,
+        camera,
+        background,
+        settings,
+    })
+}
+
+fn parse_settings(j: &Json) -> Result<RenderSettings, SceneError> {
+    check_keys(
+        j,
+        "`settings`",
+        &["width", "height", "spp", "depth", "denoise"],
+    )?;
+    let mut settings: RenderSettings = Default::default();
+    if let Some(v) = j.get("width") {
+        settings.width = image_size(v, "`width`")?;
+    }
+    // 只给出宽度时，高度与宽度相同
+    settings.height = match j.get("height") {
+        Some(v) => image_size(v, "`height`")?,
+        None => settings.width,
+    };
+    if let Some(v) = j.get("spp") {
+        settings.samples_per_pixel = positive_integer(v, "`spp`")?;
+    }
+    if let Some(v) = j.get("depth") {
+        settings.max_depth = positive_integer(v, "`depth`")?;
+    }
+    if let Some(v) = j.get("denoise") {
+        settings.denoise = v.as_bool("`denoise`")?;
+    }
+    Ok(settings)
+}
+
+// 背景可以直接写一个颜色，或者是带 type 的对象
+fn parse_background(j: &Json) -> Result<Arc<dyn Environment>, SceneError> {
+    if let JsonValue::Array(_) = &j.value {
+        return Ok(Arc::new(ConstantEnvironment::new(vec3(j, "`background`")?)));
+    }
+    let ty = type_of(j, "`background`")?;
+    match ty {
+        "image" => {
+            check_keys(
+                j,
+                "`image` background",
+                &["type", "file", "rotation", "intensity"],
+            )?;
+            let file = existing_file(required(j, "file", ty)?)?;
+            let rotation = match j.get("rotation") {
+                Some(r) => r.as_f64("`rotation`")?,
+                None => 0.,
+            };
+            let intensity = match j.get("intensity") {
+                Some(k) => non_negative(k, "`intensity`")?,
+                None => 1.,
+            };
+            let env = ImageEnvironment::load(file, rotation, intensity).map_err(|e| j.error(e))?;
+            Ok(Arc::new(env))
+        }
+        "sky" => {
+            check_keys(
+                j,
+                "`sky` background",
+                &["type", "elevation", "azimuth", "turbidity", "intensity"],
+            )?;
+            let elevation = required(j, "elevation", ty)?;
+            let e = elevation.as_f64("`elevation`")?;
+            if !(0. ..=90.).contains(&e) {
+                return Err(elevation.error("`elevation` must be between 0 and 90".to_string()));
+            }
+            let azimuth = match j.get("azimuth") {
+                Some(a) => a.as_f64("`azimuth`")?,
+                None => 0.,
+            };
+            // Preetham 模型只在这个范围内拟合过
+            let turbidity = match j.get("turbidity") {
+                Some(v) => {
+                    let t = v.as_f64("`turbidity`")?;
+                    if !(2. ..=10.).contains(&t) {
+                        return Err(v.error("`turbidity` must be between 2 and 10".to_string()));
+                    }
+                    t
+                }
+                None => 3.,
+            };
+            let intensity = match j.get("intensity") {
+                Some(k) => non_negative(k, "`intensity`")?,
+                None => 1.,
+            };
+            Ok(Arc::new(SkyEnvironment::new(
+                e, azimuth, turbidity, intensity,
+            )))
+        }
+        _ => Err(j.error(format!("unknown background type `{}`", ty))),
+    }
+}
+
+// 介质的相函数，不指定时各向同性
+fn parse_phase(j: Option<&Json>) -> Result<Arc<dyn PhaseFunction>, SceneError> {
+    let j = match j {
+        Some(j) => j,
+        None => return Ok(Arc::new(IsotropicPhase)),
+    };
+    let ty = type_of(j, "`phase`")?;
+    let phase: Arc<dyn PhaseFunction> = match ty {
+        "isotropic" => {
+            check_keys(j, "`isotropic` phase", &["type"])?;
+            Arc::new(IsotropicPhase)
+        }
+        "henyey_greenstein" => {
+            check_keys(j, "`henyey_greenstein` phase", &["type", "g"])?;
+            Arc::new(HenyeyGreenstein::new(asymmetry(
+                required(j, "g", ty)?,
+                "`g`",
+            )?))
+        }
+        "double_henyey_greenstein" => {
+            check_keys(
+                j,
+                "`double_henyey_greenstein` phase",
+                &["type", "g1", "g2", "weight"],
+            )?;
+            let weight = required(j, "weight", ty)?;
+            let w = weight.as_f64("`weight`")?;
+            if !(0. ..=1.).contains(&w) {
+                return Err(weight.error("`weight` must be between 0 and 1".to_string()));
+            }
+            Arc::new(DoubleHenyeyGreenstein::new(
+                asymmetry(required(j, "g1", ty)?, "`g1`")?,
+                asymmetry(required(j, "g2", ty)?, "`g2`")?,
+                w,
+            ))
+        }
+        "rayleigh" => {
+            check_keys(j, "`rayleigh` phase", &["type"])?;
+            Arc::new(Rayleigh)
+        }
+        _ => return Err(j.error(format!("unknown phase function type `{}`", ty))),
+    };
+    Ok(phase)
+}
+
+// HG 的参数 g，g = ±1 时退化成一个方向
+fn asymmetry(j: &Json, what: &str) -> Result<f64, SceneError> {
+    let g = j.as_f64(what)?;
+    if g > -1. && g < 1. {
+        Ok(g)
+    } else {
+        Err(j.error(format!("{} must be in (-1, 1)", what)))
+    }
+}
+
+fn parse_camera(j: &Json) -> Result<CameraConfig, SceneError> {
+    check_keys(
+        j,
+        "`camera`",
+        &[
+            "lookfrom",
+            "lookat",
+            "vup",
+            "vfov",
+            "aperture",
+            "focus_dist",
+            "time0",
+            "time1",
+        ],
+    )?;
+    let mut cam: CameraConfig = Default::default();
+    if let Some(v) = j.get("lookfrom") {
+        cam.lookfrom = vec3(v, "`lookfrom`")?;
+    }
+    if let Some(v) = j.get("lookat") {
+        cam.lookat = vec3(v, "`lookat`")?;
+    }
+    if let Some(v) = j.get("vup") {
+        cam.vup = vec3(v, "`vup`")?;
+    }
+    if let Some(v) = j.get("vfov") {
+        cam.vfov = v.as_f64("`vfov`")?;
+        if cam.vfov <= 0. || cam.vfov >= 180. {
+            return Err(v.error("`vfov` must be in (0, 180)".to_string()));
+        }
+    }
+    if let Some(v) = j.get("aperture") {
+        cam.aperture = non_negative(v, "`aperture`")?;
+    }
+    if let Some(v) = j.get("focus_dist") {
+        cam.focus_dist = positive(v, "`focus_dist`")?;
+    }
+    if let Some(v) = j.get("time0") {
+        cam.time0 = v.as_f64("`time0`")?;
+    }
+    if let Some(v) = j.get("time1") {
+        cam.time1 = v.as_f64("`time1`")?;
+    }
+    Ok(cam)
+}
+
+struct Builder {
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    time0: f64,
+    time1: f64,
+    sampler: Pcg32, // 噪声纹理、BVH 使用的随机数
+}
+
+impl Builder {
+    // 纹理可以是名字、[r, g, b] 颜色，或者直接写出的纹理对象
+    fn texture_ref(&mut self, j: &Json, what: &str) -> Result<Arc<dyn Texture>, SceneError> {
+        match &j.value {
+            JsonValue::String(name) => match self.textures.get(name) {
+                Some(t) => Ok(t.clone()),
+                None => Err(j.error(format!("unknown texture `{}`", name))),
+            },
+            JsonValue::Array(_) => Ok(Arc::new(SolidColor {
+                color_value: vec3(j, what)?,
+            })),
+            JsonValue::Object(_) => self.build_texture(j),
+            _ => Err(j.error(format!(
+                "{} must be a texture name, a color or a texture object, found {}",
+                what,
+                j.type_name()
+            ))),
+        }
+    }
+
+    fn build_texture(&mut self, j: &Json) -> Result<Arc<dyn Texture>, SceneError> {
+        let ty = type_of(j, "texture")?;
+        let texture: Arc<dyn Texture> = match ty {
+            "solid" => {
+                check_keys(j, "`solid` texture", &["type", "color"])?;
+                Arc::new(SolidColor {
+                    color_value: vec3(required(j, "color", ty)?, "`color`")?,
+                })
+            }
+            "checker" => {
+                check_keys(j, "`checker` texture", &["type", "odd", "even"])?;
+                Arc::new(CheckerTexture::new(
+                    self.texture_ref(required(j, "odd", ty)?, "`odd`")?,
+                    self.texture_ref(required(j, "even", ty)?, "`even`")?,
+                ))
+            }
+            "image" => {
+                check_keys(j, "`image` texture", &["type", "file"])?;
+                let file = existing_file(required(j, "file", ty)?)?;
+                Arc::new(ImageTexture::new_from_file(file))
+            }
+            "noise" => {
+                check_keys(j, "`noise` texture", &["type", "scale"])?;
+                let scale = positive(required(j, "scale", ty)?, "`scale`")?;
+                Arc::new(NoiseTexture::new(Perlin::new(&mut self.sampler), scale))
+            }
+            "turbulence" => {
+                check_keys(j, "`turbulence` texture", &["type", "scale", "depth"])?;
+                let scale = positive(required(j, "scale", ty)?, "`scale`")?;
+                let depth = match j.get("depth") {
+                    Some(d) => positive_integer(d, "`depth`")?,
+                    None => 7,
+                };
+                Arc::new(TurbulenceTexture::new(
+                    Perlin::new(&mut self.sampler),
+                    scale,
+                    depth,
+                ))
+            }
+            "voxel" => {
+                check_keys(
+                    j,
+                    "`voxel` texture",
+                    &["type", "min", "max", "resolution", "data"],
+                )?;
+                let min = vec3(required(j, "min", ty)?, "`min`")?;
+                let max = vec3(required(j, "max", ty)?, "`max`")?;
+                if min.x >= max.x || min.y >= max.y || min.z >= max.z {
+                    return Err(j.error("`voxel` needs `min` < `max` on every axis".to_string()));
+                }
+                let res = required(j, "resolution", ty)?;
+                let items = res.as_array("`resolution`")?;
+                if items.len() != 3 {
+                    return Err(res.error("`resolution` must have 3 integers".to_string()));
+                }
+                let mut resolution = [0; 3];
+                for (i, n) in items.iter().enumerate() {
+                    resolution[i] = positive_integer(n, "`resolution`")? as usize;
+                }
+                let data = required(j, "data", ty)?;
+                let count = resolution[0]
+                    .checked_mul(resolution[1])
+                    .and_then(|n| n.checked_mul(resolution[2]))
+                    .ok_or_else(|| res.error("`resolution` is too large".to_string()))?;
+                let values = data.as_f64_array("`data`", count)?;
+                Arc::new(VoxelGrid::new(min, max, resolution, values))
+            }
+            _ => return Err(j.error(format!("unknown texture type `{}`", ty))),
+        };
+        Ok(texture)
+    }
+
+    // 材质可以是名字，或者直接写出的材质对象
+    fn material_ref(&mut self, j: &Json) -> Result<Arc<dyn Material>, SceneError> {
+        match &j.value {
+            JsonValue::String(name) => match self.materials.get(name) {
+                Some(m) => Ok(m.clone()),
+                None => Err(j.error(format!("unknown material `{}`", name))),
+            },
+            JsonValue::Object(_) => self.build_material(j),
+            _ => Err(j.error(format!(
+                "`material` must be a material name or a material object, found {}",
+                j.type_name()
+            ))),
+        }
+    }
+
+    fn build_material(&mut self, j: &Json) -> Result<Arc<dyn Material>, SceneError> {
+        let ty = type_of(j, "material")?;
+        let material: Arc<dyn Material> = match ty {
+            "lambertian" => {
+                check_keys(j, "`lambertian` material", &["type", "albedo"])?;
+                Arc::new(Lambertian::new(
+                    self.texture_ref(required(j, "albedo", ty)?, "`albedo`")?,
+                ))
+            }
+            "metal" => {
+                check_keys(j, "`metal` material", &["type", "albedo", "fuzz"])?;
+                let fuzz = match j.get("fuzz") {
+                    Some(f) => non_negative(f, "`fuzz`")?,
+                    None => 0.,
+                };
+                Arc::new(Metal::new(
+                    vec3(required(j, "albedo", ty)?, "`albedo`")?,
+                    fuzz,
+                ))
+            }
+            "dielectric" => {
+                // 固定的折射率 ir，或者按 Cauchy / Sellmeier 公式随波长变化
+                check_keys(
+                    j,
+                    "`dielectric` material",
+                    &["type", "ir", "cauchy", "sellmeier"],
+                )?;
+                let given = ["ir", "cauchy", "sellmeier"]
+                    .iter()
+                    .filter(|k| j.get(k).is_some())
+                    .count();
+                if given != 1 {
+                    return Err(j.error(
+                        "`dielectric` needs exactly one of `ir`, `cauchy` and `sellmeier`"
+                            .to_string(),
+                    ));
+                }
+                if let Some(c) = j.get("cauchy") {
+                    let v = c.as_f64_array("`cauchy`", 2)?;
+                    if v[0] < 1. || v[1] < 0. {
+                        return Err(c.error("`cauchy` needs a >= 1 and b >= 0".to_string()));
+                    }
+                    Arc::new(Dielectric::new_with_dispersion(Dispersion::Cauchy {
+                        a: v[0],
+                        b: v[1],
+                    }))
+                } else if let Some(c) = j.get("sellmeier") {
+                    check_keys(c, "`sellmeier`", &["b", "c"])?;
+                    let jb = required(c, "b", "sellmeier")?;
+                    let jc = required(c, "c", "sellmeier")?;
+                    let b = jb.as_f64_array("`b`", 3)?;
+                    let cc = jc.as_f64_array("`c`", 3)?;
+                    if b.iter().any(|&x| x < 0.) {
+                        return Err(jb.error("`b` of `sellmeier` must be >= 0".to_string()));
+                    }
+                    // c 落在可见光的波长范围内时，折射率在这个波长上发散
+                    let (lo, hi) = (LAMBDA_MIN * 1e-3, LAMBDA_MAX * 1e-3);
+                    if cc
+                        .iter()
+                        .any(|&x| x <= 0. || (lo * lo..=hi * hi).contains(&x))
+                    {
+                        return Err(jc.error(format!(
+                            "`c` of `sellmeier` must be positive and outside [{:.4}, {:.4}] (μm^2)",
+                            lo * lo,
+                            hi * hi
+                        )));
+                    }
+                    let dispersion = Dispersion::Sellmeier {
+                        b: [b[0], b[1], b[2]],
+                        c: [cc[0], cc[1], cc[2]],
+                    };
+                    // n 随波长单调减小，最长的波长上也不能小于 1
+                    if dispersion
+                        .ior(LAMBDA_MAX)
+                        .map_or(true, |n| n.is_nan() || n < 1.)
+                    {
+                        return Err(c.error(format!(
+                            "`sellmeier` gives an index of refraction below 1 at {} nm",
+                            LAMBDA_MAX
+                        )));
+                    }
+                    Arc::new(Dielectric::new_with_dispersion(dispersion))
+                } else {
+                    Arc::new(Dielectric::new(positive(required(j, "ir", ty)?, "`ir`")?))
+                }
+            }
+            "diffuse_light" => {
+                check_keys(j, "`diffuse_light` material", &["type", "emit"])?;
+                Arc::new(DiffuseLight::new(
+                    self.texture_ref(required(j, "emit", ty)?, "`emit`")?,
+                ))
+            }
+            "isotropic" => {
+                check_keys(j, "`isotropic` material", &["type", "albedo"])?;
+                Arc::new(Isotropic::new(
+                    self.texture_ref(required(j, "albedo", ty)?, "`albedo`")?,
+                ))
+            }
+            _ => return Err(j.error(format!("unknown material type `{}`", ty))),
+        };
+        Ok(material)
+    }
+
+    // for_light 为 true 时构造的是光源列表中的物体，不做翻转
+    fn build_object(&mut self, j: &Json, for_light: bool) -> Result<Box<dyn Hittable>, SceneError> {
+        let ty = type_of(j, "object")?;
+        let mut keys = vec!["type", "transforms", "flip_face", "light"];
+        let (t0, t1) = (self.time0, self.time1);
+
+        let mut object: Box<dyn Hittable> = match ty {
+            "sphere" => {
+                keys.extend_from_slice(&["center", "radius", "material"]);
+                check_keys(j, "`sphere`", &keys)?;
+                Box::new(Sphere::new(
+                    vec3(required(j, "center", ty)?, "`center`")?,
+                    positive(required(j, "radius", ty)?, "`radius`")?,
+                    self.material_ref(required(j, "material", ty)?)?,
+                ))
+            }
+            "moving_sphere" => {
+                keys.extend_from_slice(&[
+                    "center0", "center1", "time0", "time1", "radius", "material",
+                ]);
+                check_keys(j, "`moving_sphere`", &keys)?;
+                let time0 = required(j, "time0", ty)?.as_f64("`time0`")?;
+                let time1 = required(j, "time1", ty)?;
+                if time1.as_f64("`time1`")? <= time0 {
+                    return Err(time1.error("`time1` must be greater than `time0`".to_string()));
+                }
+                Box::new(MovingSphere::new(
+                    positive(required(j, "radius", ty)?, "`radius`")?,
+                    vec3(required(j, "center0", ty)?, "`center0`")?,
+                    vec3(required(j, "center1", ty)?, "`center1`")?,
+                    time0,
+                    time1.as_f64("`time1`")?,
+                    self.material_ref(required(j, "material", ty)?)?,
+                ))
+            }
+            "xy_rect" | "xz_rect" | "yz_rect" => {
+                // 两个坐标轴的名字，比如 xz_rect 对应 x, z
+                let a = &ty[0..1];
+                let b = &ty[1..2];
+                let names = [
+                    format!("{}0", a),
+                    format!("{}1", a),
+                    format!("{}0", b),
+                    format!("{}1", b),
+                ];
+                keys.extend(names.iter().map(|s| s.as_str()));
+                keys.extend_from_slice(&["k", "material"]);
+                check_keys(j, &format!("`{}`", ty), &keys)?;
+
+                let mut v = [0.; 4];
+                for (i, name) in names.iter().enumerate() {
+                    v[i] = required(j, name, ty)?.as_f64(&format!("`{}`", name))?;
+                }
+                if v[0] >= v[1] || v[2] >= v[3] {
+                    return Err(j.error(format!(
+                        "`{}` needs {} < {} and {} < {}",
+                        ty, names[0], names[1], names[2], names[3]
+                    )));
+                }
+                let k = required(j, "k", ty)?.as_f64("`k`")?;
+                let mat = self.material_ref(required(j, "material", ty)?)?;
+                match ty {
+                    "xy_rect" => Box::new(XYRect::new(v[0], v[1], v[2], v[3], k, mat)),
+                    "xz_rect" => Box::new(XZRect::new(v[0], v[1], v[2], v[3], k, mat)),
+                    _ => Box::new(YZRect::new(v[0], v[1], v[2], v[3], k, mat)),
+                }
+            }
+            "cube" => {
+                keys.extend_from_slice(&["min", "max", "material"]);
+                check_keys(j, "`cube`", &keys)?;
+                let min = vec3(required(j, "min", ty)?, "`min`")?;
+                let max = vec3(required(j, "max", ty)?, "`max`")?;
+                if min.x >= max.x || min.y >= max.y || min.z >= max.z {
+                    return Err(j.error("`cube` needs `min` < `max` on every axis".to_string()));
+                }
+                Box::new(Cube::new(
+                    min,
+                    max,
+                    self.material_ref(required(j, "material", ty)?)?,
+                ))
+            }
+            "triangle" => {
+                keys.extend_from_slice(&["vertices", "material"]);
+                check_keys(j, "`triangle`", &keys)?;
+                let vertices = required(j, "vertices", ty)?;
+                let items = vertices.as_array("`vertices`")?;
+                if items.len() != 3 {
+                    return Err(vertices.error("`vertices` must have 3 points".to_string()));
+                }
+                Box::new(Triangle::new(
+                    [
+                        vec3(&items[0], "vertex")?,
+                        vec3(&items[1], "vertex")?,
+                        vec3(&items[2], "vertex")?,
+                    ],
+                    self.material_ref(required(j, "material", ty)?)?,
+                ))
+            }
+            "obj" => {
+                // 不指定材质时，使用模型自带的贴图
+                keys.extend_from_slice(&["file", "material"]);
+                check_keys(j, "`obj`", &keys)?;
+                let file = existing_file(required(j, "file", ty)?)?;
+                match j.get("material") {
+                    Some(m) => Box::new(OBJ::load_without_texture(
+                        file,
+                        t0,
+                        t1,
+                        self.material_ref(m)?,
+                    )),
+                    None => Box::new(OBJ::load_from_file(file, t0, t1)),
+                }
+            }
+            "stl" => {
+                keys.extend_from_slice(&["file", "material"]);
+                check_keys(j, "`stl`", &keys)?;
+                let file = existing_file(required(j, "file", ty)?)?;
+                Box::new(STL::load_from_file(
+                    file,
+                    t0,
+                    t1,
+                    self.material_ref(required(j, "material", ty)?)?,
+                ))
+            }
+            "constant_medium" => {
+                keys.extend_from_slice(&["boundary", "density", "albedo", "phase"]);
+                check_keys(j, "`constant_medium`", &keys)?;
+                Box::new(ConstantMedium::new_with_phase(
+                    self.build_object(required(j, "boundary", ty)?, for_light)?,
+                    positive(required(j, "density", ty)?, "`density`")?,
+                    self.texture_ref(required(j, "albedo", ty)?, "`albedo`")?,
+                    parse_phase(j.get("phase"))?,
+                ))
+            }
+            "heterogeneous_medium" => {
+                keys.extend_from_slice(&["boundary", "density", "max_density", "albedo", "phase"]);
+                check_keys(j, "`heterogeneous_medium`", &keys)?;
+                Box::new(HeterogeneousMedium::new_with_phase(
+                    self.build_object(required(j, "boundary", ty)?, for_light)?,
+                    positive(required(j, "max_density", ty)?, "`max_density`")?,
+                    self.texture_ref(required(j, "density", ty)?, "`density`")?,
+                    self.texture_ref(required(j, "albedo", ty)?, "`albedo`")?,
+                    parse_phase(j.get("phase"))?,
+                ))
+            }
+            "list" => {
+                // 一组物体，可以选择用 BVH 加速
+                keys.extend_from_slice(&["objects", "bvh"]);
+                check_keys(j, "`list`", &keys)?;
+                let children = required(j, "objects", ty)?;
+                let mut list: HittableList = Default::default();
+                for c in children.as_array("`objects`")? {
+                    list.objects.push(self.build_object(c, for_light)?);
+                }
+                let bvh = match j.get("bvh") {
+                    Some(b) => b.as_bool("`bvh`")?,
+                    None => false,
+                };
+                if bvh {
+                    if list.objects.is_empty() {
+                        return Err(children.error("a `bvh` list must not be empty".to_string()));
+                    }
+                    Box::new(BvhNode::new_from_list(list, t0, t1, &mut self.sampler))
+                } else {
+                    Box::new(list)
+                }
+            }
+            _ => return Err(j.error(format!("unknown object type `{}`", ty))),
+        };
+
+        // 依次进行变换
+        if let Some(transforms) = j.get("transforms") {
+            for t in transforms.as_array("`transforms`")? {
+                object = apply_transform(object, t)?;
+            }
+        }
+        if let Some(flag) = j.get("flip_face") {
+            if flag.as_bool("`flip_face`")? && !for_light {
+                object = Box::new(Flipface::new(object));
+            }
+        }
+
+        Ok(object)
+    }
+}
+
+// 每个变换写成只有一个键的对象，比如 { "rotate_y": 15 }
+fn apply_transform(object: Box<dyn Hittable>, t: &Json) -> Result<Box<dyn Hittable>, SceneError> {
+    let pairs = t.as_object("transform")?;
+    if pairs.len() != 1 {
+        return Err(t.error(format!(
+            "a transform must have exactly one key, found {}",
+            pairs.len()
+        )));
+    }
+    let (kind, v) = &pairs[0];
+    let out: Box<dyn Hittable> = match kind.as_str() {
+        "translate" => Box::new(Translate::new(object, vec3(v, "`translate`")?)),
+        "rotate_x" => Box::new(RotateX::new(object, v.as_f64("`rotate_x`")?)),
+        "rotate_y" => Box::new(RotateY::new(object, v.as_f64("`rotate_y`")?)),
+        "rotate_z" => Box::new(RotateZ::new(object, v.as_f64("`rotate_z`")?)),
+        "zoom" => {
+            let factor = vec3(v, "`zoom`")?;
+            if factor.x <= 0. || factor.y <= 0. || factor.z <= 0. {
+                return Err(v.error("`zoom` factors must be positive".to_string()));
+            }
+            Box::new(Zoom::new(object, factor))
+        }
+        _ => return Err(v.error(format!("unknown transform `{}`", kind))),
+    };
+    Ok(out)
+}
+
+//------------------------------------辅助函数------------------------------------
+
+fn type_of<'a>(j: &'a Json, what: &str) -> Result<&'a str, SceneError> {
+    j.as_object(what)?;
+    match j.get("type") {
+        Some(t) => t.as_str("`type`"),
+        None => Err(j.error(format!("{} is missing `type`", what))),
+    }
+}
+
+fn required<'a>(j: &'a Json, key: &str, ty: &str) -> Result<&'a Json, SceneError> {
+    j.get(key)
+        .ok_or_else(|| j.error(format!("`{}` requires `{}`", ty, key)))
+}
+
+// 拒绝未知的键，防止拼写错误被悄悄忽略
+fn check_keys(j: &Json, what: &str, allowed: &[&str]) -> Result<(), SceneError> {
+    for (key, v) in j.as_object(what)? {
+        if !allowed.contains(&key.as_str()) {
+            return Err(v.error(format!(
+                "unknown key `{}` in {}, expected one of: {}",
+                key,
+                what,
+                allowed.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn vec3(j: &Json, what: &str) -> Result<Vec3, SceneError> {
+    let v = j.as_f64_array(what, 3)?;
+    Ok(Vec3::new(v[0], v[1], v[2]))
+}
+
+fn positive(j: &Json, what: &str) -> Result<f64, SceneError> {
+    let x = j.as_f64(what)?;
+    if x > 0. {
+        Ok(x)
+    } else {
+        Err(j.error(format!("{} must be positive", what)))
+    }
+}
+
+fn positive_integer(j: &Json, what: &str) -> Result<i32, SceneError> {
+    let x = j.as_f64(what)?;
+    if x >= 1. && x.fract() == 0. && x <= i32::MAX as f64 {
+        Ok(x as i32)
+    } else {
+        Err(j.error(format!("{} must be a positive integer", what)))
+    }
+}
+
+// 相机按 x / (width - 1) 把像素映射到胶片上，至少需要两个像素
+fn image_size(j: &Json, what: &str) -> Result<u32, SceneError> {
+    match positive_integer(j, what)? {
+        1 => Err(j.error(format!("{} must be at least 2", what))),
+        x => Ok(x as u32),
+    }
+}
+
+fn non_negative(j: &Json, what: &str) -> Result<f64, SceneError> {
+    let x = j.as_f64(what)?;
+    if x >= 0. {
+        Ok(x)
+    } else {
+        Err(j.error(format!("{} must not be negative", what)))
+    }
+}
+
+// 载入函数遇到不存在的文件会直接 panic，这里提前检查
+fn existing_file(j: &Json) -> Result<&str, SceneError> {
+    let file = j.as_str("`file`")?;
+    if Path::new(file).is_file() {
+        Ok(file)
+    } else {
+        Err(j.error(format!("file `{}` does not exist", file)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_of(text: &str) -> SceneError {
+        match load_scene(text, 0) {
+            Ok(_) => panic!("the scene should not load"),
+            Err(e) => e,
+        }
+    }
+
+    // 一个放在 objects 中的玻璃球，material 为给定的内联材质
+    fn glass_sphere(material: &str) -> String {
+        format!(
+            "{{\n\"objects\": [\n  {{\"type\": \"sphere\", \"center\": [0, 0, 0], \"radius\": 1,\n   \"material\": {}}}\n]\n}}",
+            material
+        )
+    }
+
+    #[test]
+    fn loads_the_example_scenes() {
+        for text in [
+            include_str!("../../../scenes/cornell_box.json"),
+            include_str!("../../../scenes/dispersion.json"),
+        ]
+        .iter()
+        {
+            let scene = load_scene(text, 0).unwrap_or_else(|e| panic!("{}", e));
+            assert!(!scene.world.objects.is_empty());
+            assert!(!scene.lights.objects.is_empty());
+        }
+    }
+
+    #[test]
+    fn reports_unknown_keys() {
+        let e = error_of("{\n\"camera\": {\n  \"lookfrom\": [0, 0, 1],\n  \"vfvo\": 40\n}\n}");
+        assert_eq!(e.line, 4);
+        assert!(
+            e.message.starts_with("unknown key `vfvo` in `camera`"),
+            "{}",
+            e.message
+        );
+    }
+
+    #[test]
+    fn reports_unknown_references() {
+        let e = error_of(&glass_sphere("\"glass\""));
+        assert_eq!(e.line, 4);
+        assert_eq!(e.message, "unknown material `glass`");
+    }
+
+    #[test]
+    fn reports_missing_and_invalid_parameters() {
+        let e = error_of(&glass_sphere("{\"type\": \"dielectric\"}"));
+        assert_eq!(e.line, 4);
+        assert_eq!(
+            e.message,
+            "`dielectric` needs exactly one of `ir`, `cauchy` and `sellmeier`"
+        );
+
+        let e = error_of(&glass_sphere(
+            "{\"type\": \"dielectric\", \"cauchy\": [0.9, 0]}",
+        ));
+        assert_eq!(e.line, 4);
+
+        let e = error_of(&glass_sphere("{\"type\": \"dielectric\", \"ir\": [1.5]}"));
+        assert_eq!(e.message, "`ir` must be a number, found an array");
+    }
+
+    #[test]
+    fn rejects_sellmeier_poles_in_the_visible_range() {
+        let sellmeier = |c: &str| {
+            glass_sphere(&format!(
+                "{{\"type\": \"dielectric\",\n   \"sellmeier\": {{\"b\": [1, 0.2, 1],\n   \"c\": {}}}}}",
+                c
+            ))
+        };
+        assert!(load_scene(&sellmeier("[0.006, 0.02, 100]"), 0).is_ok());
+        for c in ["[0.006, 0.3, 100]", "[0, 0.02, 100]", "[-0.1, 0.02, 100]"].iter() {
+            let e = error_of(&sellmeier(c));
+            assert_eq!(e.line, 6);
+            assert!(e.message.starts_with("`c` of `sellmeier`"), "{}", e.message);
+        }
+
+        // 极点都在可见光之外，但 830 nm 处 n^2 < 1
+        let e = error_of(&glass_sphere(
+            "{\"type\": \"dielectric\", \"sellmeier\": {\"b\": [0.1, 0, 0], \"c\": [1, 1, 1]}}",
+        ));
+        assert!(e.message.contains("below 1"), "{}", e.message);
+    }
+
+    #[test]
+    fn rejects_images_one_pixel_wide() {
+        let e = error_of("{\n\"settings\": {\"width\": 100,\n  \"height\": 1}\n}");
+        assert_eq!(e.line, 3);
+        assert_eq!(e.message, "`height` must be at least 2");
+        let e = error_of("{\"settings\": {\"width\": 1}}");
+        assert_eq!(e.message, "`width` must be at least 2");
+    }
+
+    #[test]
+    fn rejects_empty_boxes_and_oversized_grids() {
+        let e = error_of(
+            "{\"objects\": [\n  {\"type\": \"cube\", \"min\": [0, 0, 0], \"max\": [1, 0, 1],\n   \"material\": {\"type\": \"lambertian\", \"albedo\": [1, 1, 1]}}\n]}",
+        );
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "`cube` needs `min` < `max` on every axis");
+
+        let e = error_of(
+            "{\"textures\": {\"fog\": {\"type\": \"voxel\", \"min\": [0, 0, 0], \"max\": [1, 1, 1],\n  \"resolution\": [2147483647, 2147483647, 2147483647], \"data\": []}}}",
+        );
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "`resolution` is too large");
+    }
+}
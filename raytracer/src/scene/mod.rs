@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use crate::{
+    basic_component::camera::CameraConfig, environment::Environment, hittable::HittableList,
+};
+
+pub mod builtin;
+pub mod json;
+pub mod loader;
+
+// 渲染一个场景所需的全部内容
+pub struct Scene {
+    pub world: HittableList,
+    pub lights: HittableList, // 需要重点采样的光源
+    pub camera: CameraConfig,
+    pub background: Arc<dyn Environment>, // 光线没有碰到物体时看到的背景
+    pub settings: RenderSettings,         // 推荐的渲染参数，命令行中可以覆盖
+}
+
+#[derive(Copy, Clone)]
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    pub samples_per_pixel: i32,
+    pub max_depth: i32,
+    pub denoise: bool, // 渲染完成后额外输出降噪的图像
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 800,
+            samples_per_pixel: 50,
+            max_depth: 50,
+            denoise: false,
+        }
+    }
+}
@@ -1,10 +1,18 @@
 use crate::basic_component::vec3::Vec3;
+use std::sync::Arc;
 pub mod checker;
 pub mod image;
 pub mod perlin;
 pub mod solid;
+pub mod voxel;
 
 pub trait Texture: Send + Sync {
     fn get_color_value(&self, u: f64, v: f64, p: Vec3) -> Vec3;
     //(u, v) 纹理的坐标
 }
+
+impl Texture for Arc<dyn Texture> {
+    fn get_color_value(&self, u: f64, v: f64, p: Vec3) -> Vec3 {
+        (**self).get_color_value(u, v, p)
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{basic_component::vec3::Vec3, texture::Texture, utility::random_int};
+use crate::{
+    basic_component::{sampler::Sampler, vec3::Vec3},
+    texture::Texture,
+};
 
 #[derive(Clone, Copy)]
 pub struct Perlin {
@@ -9,39 +12,39 @@ pub struct Perlin {
 }
 
 impl Perlin {
-    #[allow(clippy::new_without_default, clippy::needless_range_loop)]
-    pub fn new() -> Self {
+    #[allow(clippy::needless_range_loop)]
+    pub fn new(sampler: &mut dyn Sampler) -> Self {
         let mut _ranvec: [Vec3; 256] = [Vec3::new(0., 0., 0.); 256];
         //用随机的单位向量来代替随机的double，可以进一步避免格子化的出现
         for i in 0..256 {
-            _ranvec[i] = Vec3::unit_vector(Vec3::random(-1., 1.));
+            _ranvec[i] = Vec3::unit_vector(Vec3::random(-1., 1., sampler));
         }
 
         Self {
-            perm_x: Perlin::perlin_generate_from(),
-            perm_y: Perlin::perlin_generate_from(),
-            perm_z: Perlin::perlin_generate_from(),
+            perm_x: Perlin::perlin_generate_from(sampler),
+            perm_y: Perlin::perlin_generate_from(sampler),
+            perm_z: Perlin::perlin_generate_from(sampler),
             ranvec: _ranvec,
         }
     }
 
-    pub fn perlin_generate_from() -> [i32; 256] {
+    pub fn perlin_generate_from(sampler: &mut dyn Sampler) -> [i32; 256] {
         // 生成一个随机交换的数组
         let mut p: [i32; 256] = [0; 256];
         for i in 0..256 {
             let t = i as usize;
             p[t] = i;
         }
-        Perlin::permute(p, 256);
+        Perlin::permute(p, 256, sampler);
 
         p
     }
 
-    pub fn permute(mut p: [i32; 256], n: i32) {
+    pub fn permute(mut p: [i32; 256], n: i32, sampler: &mut dyn Sampler) {
         //随机交换
         for i in (0..n).rev() {
             let t = i as usize;
-            let target = random_int(0, i) as usize;
+            let target = sampler.random_int(0, i) as usize;
             p.swap(t, target);
             // swap(&mut p[t], &mut p[target]);
         }
@@ -136,3 +139,27 @@ impl NoiseTexture {
         }
     }
 }
+
+// 直接输出湍流的强度，没有大理石的条纹，适合作为烟、云的密度场
+#[derive(Clone, Copy)]
+pub struct TurbulenceTexture {
+    pub noise: Perlin,
+    pub scale: f64, //控制频率大小
+    pub depth: i32, //叠加的层数
+}
+
+impl Texture for TurbulenceTexture {
+    fn get_color_value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        Vec3::new(1., 1., 1.) * self.noise.turb(p * self.scale, self.depth)
+    }
+}
+
+impl TurbulenceTexture {
+    pub fn new(noise: Perlin, scale: f64, depth: i32) -> Self {
+        Self {
+            noise,
+            scale,
+            depth,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use super::Texture;
+use crate::basic_component::vec3::Vec3;
+
+// 体素网格：在 [minimum, maximum] 的长方体里按 nx * ny * nz 个格点存放标量，格点之间三线性插值
+// 长方体外为 0，常用作非均匀介质的密度场
+#[derive(Clone)]
+pub struct VoxelGrid {
+    pub minimum: Vec3,
+    pub maximum: Vec3,
+    pub resolution: [usize; 3],
+    pub data: Vec<f64>, // 下标为 x + nx * (y + ny * z)
+}
+
+impl Texture for VoxelGrid {
+    fn get_color_value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        Vec3::new(1., 1., 1.) * self.value(p)
+    }
+}
+
+impl VoxelGrid {
+    pub fn new(minimum: Vec3, maximum: Vec3, resolution: [usize; 3], data: Vec<f64>) -> Self {
+        assert_eq!(
+            data.len(),
+            resolution[0] * resolution[1] * resolution[2],
+            "voxel data does not match the resolution"
+        );
+        Self {
+            minimum,
+            maximum,
+            resolution,
+            data,
+        }
+    }
+
+    pub fn value(&self, p: Vec3) -> f64 {
+        // 格点在长方体里的连续坐标
+        let mut g = [0.; 3];
+        for i in 0..3 {
+            let t = (p[i] - self.minimum[i]) / (self.maximum[i] - self.minimum[i]);
+            if !(0. ..=1.).contains(&t) {
+                return 0.;
+            }
+            g[i] = t * (self.resolution[i] - 1) as f64;
+        }
+
+        let lo = [g[0] as usize, g[1] as usize, g[2] as usize];
+        let mut accum = 0.;
+        for corner in 0..8 {
+            let mut weight = 1.;
+            let mut idx = [0; 3];
+            for i in 0..3 {
+                let up = (corner >> i) & 1 == 1;
+                let f = g[i] - lo[i] as f64;
+                idx[i] = (lo[i] + up as usize).min(self.resolution[i] - 1);
+                weight *= if up { f } else { 1. - f };
+            }
+            accum += weight * self.at(idx[0], idx[1], idx[2]);
+        }
+        accum
+    }
+
+    fn at(&self, x: usize, y: usize, z: usize) -> f64 {
+        let [nx, ny, _] = self.resolution;
+        self.data[x + nx * (y + ny * z)]
+    }
+}